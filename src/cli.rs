@@ -0,0 +1,344 @@
+//! Command line arguments
+use crate::{
+    core::{DuplicateAction, ReleaseFilter, ReleaseType, TrackSelection},
+    error::Error,
+    settings::{DownloadFormat, UserSettings},
+    Result,
+};
+
+/// Command line usage
+pub const USAGE: &str = "Usage: bandcamp_downloader [OPTIONS] [URL]...
+
+Downloads the albums found at the supplied urls (artist, label, release, or fan collection and
+wishlist pages), then exits. Without urls, the GUI is started
+with the options applied to the user settings.
+
+Options:
+  -d, --discography       Download the whole discography of the artists (or labels)
+  -n, --dry-run           List the files that would be downloaded, without downloading them
+      --after <DATE>      Only download the releases published on or after DATE (YYYY-MM-DD)
+      --before <DATE>     Only download the releases published on or before DATE (YYYY-MM-DD)
+      --title <REGEX>     Only download the releases whose title matches REGEX
+      --min-tracks <N>    Only download the releases of at least N tracks
+      --max-tracks <N>    Only download the releases of at most N tracks
+      --type <TYPE>       Only download the releases of TYPE: any, album or track
+      --tracks <RANGES>   Only download the tracks numbered in RANGES (eg. 1-3,5) of each album
+      --cookies <FILE>    Log in to Bandcamp with the cookies of FILE (cookies.txt format)
+      --fan-page <URL>    Download the releases purchased on the fan page at URL instead of
+                          their streams
+      --format <FORMAT>   Download the purchases in FORMAT, or the closest available one:
+                          flac, alac, wav, aiff-lossless, mp3-320, mp3-v0, aac-hi or vorbis
+      --free              Download the free (or name your price) releases in the purchases
+                          format instead of their streams, unless they require an email
+      --duplicates <ACTION>
+                          Handle the tracks already downloaded from another release with
+                          ACTION: download, skip, hardlink or symlink
+      --subscribe <URL>   Follow the artist (or label) at URL, then exit unless urls or --watch
+                          are supplied
+      --unsubscribe <URL> Stop following the artist (or label) at URL
+  -w, --watch             Download the new releases of the followed artists, checking again
+                          periodically until interrupted
+      --retag <DIR>       Tag again the mp3 files of DIR and its subfolders instead of
+                          downloading, each folder being matched to a release by its album.json
+                          file or the release url saved in its tags, then exit
+      --album <URL>       Match the folders found by --retag to the release at URL otherwise
+      --reorganise <DIR>  Move the albums of DIR and its subfolders described by an album.json
+                          file to the paths of the current folder and file name formats, then
+                          exit. The moves are only listed with --dry-run
+  -h, --help              Print this help and exit";
+
+/// Parsed command line arguments. Options override the matching [`UserSettings`] for the session.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    /// Urls to download without starting the GUI
+    pub urls: Vec<String>,
+    /// Print the usage and exit
+    pub help: bool,
+    /// Only list the files that would be downloaded
+    pub dry_run: bool,
+    /// Check the subscriptions for new releases until interrupted
+    pub watch: bool,
+    /// Library to tag again instead of downloading
+    pub retag: Option<String>,
+    /// Release of the library folders not matched otherwise
+    pub album_url: Option<String>,
+    /// Downloads to move to the paths of the current formats
+    pub reorganise: Option<String>,
+    subscribe: Vec<String>,
+    unsubscribe: Vec<String>,
+    discography: bool,
+    released_after: Option<String>,
+    released_before: Option<String>,
+    title_pattern: Option<String>,
+    min_tracks: Option<usize>,
+    max_tracks: Option<usize>,
+    release_type: Option<ReleaseType>,
+    track_selection: Option<TrackSelection>,
+    cookies_file: Option<String>,
+    fan_page_url: Option<String>,
+    preferred_format: Option<DownloadFormat>,
+    free: bool,
+    duplicates: Option<DuplicateAction>,
+}
+
+impl Args {
+    /// Parse the supplied arguments, excluding the program name
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            // options accept both `--name value` and `--name=value`
+            let (name, mut inline) = match arg.find('=') {
+                Some(i) if arg.starts_with("--") => (&arg[..i], Some(arg[i + 1..].to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline
+                    .take()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| Error::Usage(format!("missing value for {}", name)))
+            };
+            let number = |value: String| {
+                value.parse::<usize>().map_err(|_| {
+                    Error::Usage(format!(r#"invalid number "{}" for {}"#, value, name))
+                })
+            };
+
+            match name {
+                "-h" | "--help" => parsed.help = true,
+                "-d" | "--discography" => parsed.discography = true,
+                "-n" | "--dry-run" => parsed.dry_run = true,
+                "-w" | "--watch" => parsed.watch = true,
+                "--subscribe" => parsed.subscribe.push(value()?),
+                "--unsubscribe" => parsed.unsubscribe.push(value()?),
+                "--after" => parsed.released_after = Some(value()?),
+                "--before" => parsed.released_before = Some(value()?),
+                "--title" => parsed.title_pattern = Some(value()?),
+                "--min-tracks" => parsed.min_tracks = Some(number(value()?)?),
+                "--max-tracks" => parsed.max_tracks = Some(number(value()?)?),
+                "--type" => parsed.release_type = Some(value()?.parse()?),
+                "--tracks" => parsed.track_selection = Some(value()?.parse()?),
+                "--cookies" => parsed.cookies_file = Some(value()?),
+                "--fan-page" => parsed.fan_page_url = Some(value()?),
+                "--format" => parsed.preferred_format = Some(value()?.parse()?),
+                "--free" => parsed.free = true,
+                "--duplicates" => parsed.duplicates = Some(value()?.parse()?),
+                "--retag" => parsed.retag = Some(value()?),
+                "--album" => parsed.album_url = Some(value()?),
+                "--reorganise" => parsed.reorganise = Some(value()?),
+                _ if name.starts_with('-') => {
+                    return Err(Error::Usage(format!("unknown option {}", name)));
+                }
+                _ => parsed.urls.push(arg.clone()),
+            }
+        }
+
+        // report invalid filters before anything is started
+        let mut filter = ReleaseFilter::default();
+        parsed.apply_filter(&mut filter);
+        filter.compile()?;
+
+        Ok(parsed)
+    }
+
+    /// Override the release filters set on the command line
+    fn apply_filter(&self, filter: &mut ReleaseFilter) {
+        if let Some(after) = &self.released_after {
+            filter.released_after = after.clone();
+        }
+        if let Some(before) = &self.released_before {
+            filter.released_before = before.clone();
+        }
+        if let Some(pattern) = &self.title_pattern {
+            filter.title_pattern = pattern.clone();
+        }
+        if self.min_tracks.is_some() {
+            filter.min_tracks = self.min_tracks;
+        }
+        if self.max_tracks.is_some() {
+            filter.max_tracks = self.max_tracks;
+        }
+        if let Some(release_type) = self.release_type {
+            filter.release_type = release_type;
+        }
+    }
+
+    /// True if the followed artists are edited on the command line
+    pub fn edits_subscriptions(&self) -> bool {
+        !self.subscribe.is_empty() || !self.unsubscribe.is_empty()
+    }
+
+    /// Follow (or stop following) the artists set on the command line. Unlike the other options,
+    /// these edits are meant to be saved.
+    pub fn apply_subscriptions(&self, settings: &mut UserSettings) {
+        for url in &self.subscribe {
+            settings.subscribe(url);
+        }
+        for url in &self.unsubscribe {
+            settings.unsubscribe(url);
+        }
+    }
+
+    /// Override the user settings set on the command line
+    pub fn apply(&self, settings: &mut UserSettings) {
+        if self.discography {
+            settings.download_artist_discography = true;
+        }
+        // watching needs the subscriptions to be checked
+        if self.watch && settings.subscription_check_interval == 0 {
+            settings.subscription_check_interval =
+                UserSettings::default().subscription_check_interval;
+        }
+        self.apply_filter(&mut settings.release_filter);
+        if let Some(selection) = &self.track_selection {
+            settings.track_selection = selection.clone();
+        }
+        if let Some(path) = &self.cookies_file {
+            // the cookies file is only read without identity cookie
+            settings.identity_cookie.clear();
+            settings.cookies_file = path.clone();
+        }
+        if let Some(url) = &self.fan_page_url {
+            settings.fan_page_url = url.clone();
+        }
+        if let Some(format) = self.preferred_format {
+            settings.preferred_format = format;
+        }
+        if self.free {
+            settings.download_free_releases = true;
+        }
+        if let Some(action) = self.duplicates {
+            settings.duplicates = action;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_args() {
+        let args = parse(&[
+            "-d",
+            "-n",
+            "--after",
+            "2020-01-01",
+            "--title=^Night",
+            "theracers.bandcamp.com",
+            "--min-tracks",
+            "3",
+            "--type=album",
+            "--tracks",
+            "2-4",
+            "--cookies",
+            "cookies.txt",
+            "--format=FLAC",
+            "--free",
+            "--duplicates",
+            "hardlink",
+        ])
+        .unwrap();
+
+        let msg = "should collect the urls";
+        assert_eq!(args.urls, vec!["theracers.bandcamp.com"], "{}", msg);
+        assert!(args.dry_run, "{}", msg);
+
+        let msg = "should override the matching settings";
+        let mut settings = UserSettings {
+            download_artist_discography: false,
+            ..UserSettings::default()
+        };
+        settings.release_filter.max_tracks = Some(12);
+        args.apply(&mut settings);
+        assert!(settings.download_artist_discography, "{}", msg);
+        let expected = ReleaseFilter {
+            released_after: String::from("2020-01-01"),
+            released_before: String::new(),
+            title_pattern: String::from("^Night"),
+            min_tracks: Some(3),
+            max_tracks: Some(12),
+            release_type: ReleaseType::Album,
+        };
+        assert_eq!(settings.release_filter, expected, "{}", msg);
+        assert_eq!(settings.track_selection, "2-4".parse().unwrap(), "{}", msg);
+        assert_eq!(settings.cookies_file, "cookies.txt", "{}", msg);
+        assert_eq!(settings.preferred_format, DownloadFormat::Flac, "{}", msg);
+        assert!(settings.download_free_releases, "{}", msg);
+        assert_eq!(settings.duplicates, DuplicateAction::Hardlink, "{}", msg);
+    }
+
+    #[test]
+    fn edits_subscriptions() {
+        let args = parse(&[
+            "--subscribe",
+            "theracers.bandcamp.com",
+            "--unsubscribe=nightride.bandcamp.com",
+            "-w",
+        ])
+        .unwrap();
+        let mut settings = UserSettings {
+            subscriptions: vec![String::from("nightride.bandcamp.com")],
+            subscription_check_interval: 0,
+            ..UserSettings::default()
+        };
+
+        let msg = "should edit the followed artists";
+        assert!(args.edits_subscriptions(), "{}", msg);
+        args.apply_subscriptions(&mut settings);
+        assert_eq!(
+            settings.subscriptions,
+            vec!["theracers.bandcamp.com"],
+            "{}",
+            msg
+        );
+
+        let msg = "should check the subscriptions when watching";
+        args.apply(&mut settings);
+        assert!(settings.subscription_check_interval > 0, "{}", msg);
+    }
+
+    #[test]
+    fn parses_retag_args() {
+        let args = parse(&[
+            "--retag",
+            "~/Music",
+            "--album=theracers.bandcamp.com/album/a",
+        ])
+        .unwrap();
+
+        let msg = "should collect the library to tag again";
+        assert_eq!(args.retag.as_deref(), Some("~/Music"), "{}", msg);
+        let expected = Some("theracers.bandcamp.com/album/a");
+        assert_eq!(args.album_url.as_deref(), expected, "{}", msg);
+        assert!(args.urls.is_empty(), "{}", msg);
+
+        let args = parse(&["--reorganise=~/Music", "-n"]).unwrap();
+        let msg = "should collect the downloads to reorganise";
+        assert_eq!(args.reorganise.as_deref(), Some("~/Music"), "{}", msg);
+        assert!(args.dry_run, "{}", msg);
+    }
+
+    #[test]
+    fn rejects_invalid_args() {
+        let msg = "should reject unknown options";
+        assert!(parse(&["--foo"]).is_err(), "{}", msg);
+
+        let msg = "should reject missing values";
+        assert!(parse(&["--after"]).is_err(), "{}", msg);
+        assert!(parse(&["--subscribe"]).is_err(), "{}", msg);
+
+        let msg = "should reject invalid values";
+        assert!(parse(&["--min-tracks", "few"]).is_err(), "{}", msg);
+        assert!(parse(&["--type", "ep"]).is_err(), "{}", msg);
+        assert!(parse(&["--before", "tomorrow"]).is_err(), "{}", msg);
+        assert!(parse(&["--tracks", "2-"]).is_err(), "{}", msg);
+        assert!(parse(&["--format", "ogg"]).is_err(), "{}", msg);
+        assert!(parse(&["--duplicates", "copy"]).is_err(), "{}", msg);
+    }
+}
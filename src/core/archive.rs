@@ -0,0 +1,144 @@
+//! Extraction of the archives of purchased albums
+use std::{
+    fs,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use zip::ZipArchive;
+
+use crate::{helper, model::Album, settings::DownloadFormat, Result};
+
+/// Returns the index of the track of `album` saved in the archive file `file_name`, named like
+/// `Artist - Album - 01 Title.flac`. Tracks are matched on their number, or on their title if the
+/// file is not numbered.
+fn match_track(file_name: &str, album: &Album) -> Option<usize> {
+    let stem = Path::new(file_name).file_stem()?.to_string_lossy();
+    let last = stem.rsplit(" - ").next().unwrap_or(&stem);
+
+    let digits: String = last.chars().take_while(char::is_ascii_digit).collect();
+    if let Ok(number) = digits.parse::<u32>() {
+        if let Some(i) = album.tracks.iter().position(|track| track.number == number) {
+            return Some(i);
+        }
+    }
+
+    // titles are sanitized like the file names
+    let stem = stem.to_lowercase();
+    album.tracks.iter().position(|track| {
+        let title = helper::sanitize_file_name(&track.title).to_lowercase();
+        !title.is_empty() && stem.ends_with(&title)
+    })
+}
+
+/// Extract the tracks of `album` at the specified indices found in `archive` to their path.
+/// Returns the indices of the tracks extracted; the other files of the archive (eg. the cover) are
+/// ignored.
+fn extract<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    album: &Album,
+    tracks: &[usize],
+    format: DownloadFormat,
+) -> Result<Vec<usize>> {
+    let mut extracted = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let name = match file.enclosed_name() {
+            Some(name) if file.is_file() => name.to_string_lossy().into_owned(),
+            _ => continue,
+        };
+
+        let is_track = match Path::new(&name).extension() {
+            Some(extension) => extension.eq_ignore_ascii_case(format.extension()),
+            None => false,
+        };
+        let index = match match_track(&name, album) {
+            Some(index) if is_track && tracks.contains(&index) => index,
+            _ => continue,
+        };
+        if extracted.contains(&index) {
+            continue;
+        }
+
+        let path = Path::new(&album.tracks[index].path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut destination = fs::File::create(path)?;
+        std::io::copy(&mut file, &mut destination)?;
+        extracted.push(index);
+    }
+
+    Ok(extracted)
+}
+
+/// Extract the tracks of `album` at the specified indices found in the zip archive at `path`,
+/// saved in `format`. Returns the indices of the tracks extracted.
+pub fn extract_tracks<P: AsRef<Path>>(
+    path: P,
+    album: &Album,
+    tracks: &[usize],
+    format: DownloadFormat,
+) -> Result<Vec<usize>> {
+    let file = fs::File::open(path)?;
+    let mut archive = ZipArchive::new(file)?;
+    extract(&mut archive, album, tracks, format)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+    use crate::helper::test_helper::{test_album, test_dir};
+
+    /// Titles of the tracks of the test album
+    const TITLES: [&str; 3] = ["Final Lap", "Pit Stop", "Rain: Again?"];
+
+    #[test]
+    fn matches_archive_files() {
+        let album = test_album("/music", &TITLES, "{title}.flac");
+
+        let msg = "should match the tracks on their number";
+        let actual = match_track("The Racers - Night Drive - 02 Pit Stop.flac", &album);
+        assert_eq!(actual, Some(1), "{}", msg);
+
+        let msg = "should match the tracks on their title";
+        let actual = match_track("The Racers - Night Drive - Rain_ Again_.flac", &album);
+        assert_eq!(actual, Some(2), "{}", msg);
+
+        let msg = "should not match the other files";
+        assert_eq!(match_track("cover.jpg", &album), None, "{}", msg);
+    }
+
+    #[test]
+    fn extracts_tracks() {
+        let dir = test_dir("extracts_tracks");
+        let album = test_album(&dir.to_string_lossy(), &TITLES, "{title}.flac");
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in [
+            ("The Racers - Night Drive - 01 Final Lap.flac", b"first"),
+            ("The Racers - Night Drive - 02 Pit Stop.flac", b"pitst"),
+            ("The Racers - Night Drive - 03 Rain_ Again_.flac", b"third"),
+            ("cover.jpg", b"cover"),
+        ] {
+            writer.start_file(name, FileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        let msg = "should extract the tracks to their path";
+        let actual = extract(&mut archive, &album, &[0, 1], DownloadFormat::Flac).unwrap();
+        assert_eq!(actual, vec![0, 1], "{}", msg);
+        let data = fs::read(&album.tracks[1].path).unwrap();
+        assert_eq!(data, b"pitst", "{}", msg);
+
+        let msg = "should skip the other tracks";
+        assert!(!Path::new(&album.tracks[2].path).exists(), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
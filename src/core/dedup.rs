@@ -0,0 +1,348 @@
+//! Detection of the tracks already saved from another release (eg. an EP, its album and a label
+//! compilation)
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::Transcoding,
+    error::Error,
+    model::{Album, Track},
+    Result,
+};
+
+/// What to do with the tracks already saved from another release
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateAction {
+    /// Download the track again
+    #[default]
+    Download,
+    /// Do not save the track, its playlist entry pointing to the saved file
+    Skip,
+    /// Save the track as a hard link to the saved file
+    Hardlink,
+    /// Save the track as a symbolic link to the saved file
+    Symlink,
+}
+
+impl DuplicateAction {
+    /// list of all enum variants
+    pub const ALL: [DuplicateAction; 4] =
+        [Self::Download, Self::Skip, Self::Hardlink, Self::Symlink];
+
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Download => "download",
+            Self::Skip => "skip",
+            Self::Hardlink => "hardlink",
+            Self::Symlink => "symlink",
+        }
+    }
+
+    // TODO: language support
+    fn description(&self) -> &str {
+        match self {
+            Self::Download => "Download again",
+            Self::Skip => "Skip",
+            Self::Hardlink => "Hard link",
+            Self::Symlink => "Symbolic link",
+        }
+    }
+}
+
+impl std::fmt::Display for DuplicateAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl std::str::FromStr for DuplicateAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .find(|action| action.value().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| {
+                let actions: Vec<_> = Self::ALL.iter().map(DuplicateAction::value).collect();
+                Error::Usage(format!(
+                    r#"unknown duplicate action "{}", expected one of {}"#,
+                    s,
+                    actions.join(", ")
+                ))
+            })
+    }
+}
+
+/// Returns the keys identifying `track` of `album`: its Bandcamp id if known, then its artist (or
+/// the one of `album`), title (without an "Artist - " prefix, eg. on a compilation) and duration
+/// (in seconds)
+fn track_keys(album: &Album, track: &Track) -> Vec<String> {
+    let mut keys = Vec::with_capacity(2);
+    if let Some(id) = track.id {
+        keys.push(format!("id:{}", id));
+    }
+    let artist = track
+        .artist
+        .as_deref()
+        .unwrap_or(&album.artist)
+        .trim()
+        .to_lowercase();
+    let title = track.title.trim().to_lowercase();
+    let title = title
+        .strip_prefix(&format!("{} - ", artist))
+        .unwrap_or(&title);
+    keys.push(format!(
+        "{}|{}|{}",
+        artist,
+        title.trim(),
+        track.duration.round() as u64
+    ));
+
+    keys
+}
+
+/// Returns the path `track` is saved to, once transcoded if `transcoding` is enabled
+pub fn output_path(track: &Track, transcoding: &Transcoding) -> String {
+    if transcoding.enabled {
+        transcoding.output_path(&track.path)
+    } else {
+        track.path.clone()
+    }
+}
+
+/// Files of the tracks already saved, by track key
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TrackIndex {
+    files: BTreeMap<String, String>,
+}
+
+impl TrackIndex {
+    pub const FILE_NAME: &'static str = "track_index.json";
+
+    /// Load the index saved at `path`. A missing (or unreadable) index is empty.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|index| serde_json::from_str(&index).ok())
+            .unwrap_or_default()
+    }
+
+    /// Attempt to save the index at `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let index = serde_json::to_string_pretty(self)?;
+        fs::write(path, index).map_err(From::from)
+    }
+
+    /// Returns the saved file of `key`, if it is still on disk
+    fn find(&self, key: &str) -> Option<&String> {
+        self.files.get(key).filter(|path| Path::new(path).is_file())
+    }
+
+    /// Record the selected tracks of `album` whose file is on disk, once transcoded if
+    /// `transcoding` is enabled. The duplicates are not recorded. Returns false if nothing changed.
+    pub fn record(&mut self, album: &Album, transcoding: &Transcoding) -> bool {
+        let mut changed = false;
+        for track in album.selected_tracks() {
+            let path = output_path(track, transcoding);
+            if track.duplicate_of.is_some() || !Path::new(&path).is_file() {
+                continue;
+            }
+            for key in track_keys(album, track) {
+                if self.files.get(&key) != Some(&path) {
+                    self.files.insert(key, path.clone());
+                    changed = true;
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Point the keys of the `moved` files, by previous path, to their new path. Returns false if
+    /// nothing changed.
+    pub fn update_paths(&mut self, moved: &HashMap<String, String>) -> bool {
+        let mut changed = false;
+        for path in self.files.values_mut() {
+            if let Some(new_path) = moved.get(path) {
+                *path = new_path.clone();
+                changed = true;
+            }
+        }
+
+        changed
+    }
+}
+
+/// Flag the selected tracks of the `albums` already saved, or saved by an earlier album of the
+/// list, with the file they duplicate. Tracks already on disk are not flagged. Returns the number
+/// of duplicates.
+pub fn flag_duplicates(
+    albums: &mut [Album],
+    index: &TrackIndex,
+    transcoding: &Transcoding,
+) -> usize {
+    let mut queued: HashMap<String, String> = HashMap::new();
+    let mut count = 0;
+
+    for album in albums.iter_mut() {
+        let mut tracks = std::mem::take(&mut album.tracks);
+        for track in tracks.iter_mut().filter(|track| track.selected) {
+            let keys = track_keys(album, track);
+            let path = output_path(track, transcoding);
+            let original = keys
+                .iter()
+                .find_map(|key| index.find(key).or_else(|| queued.get(key)))
+                .filter(|original| **original != path && !Path::new(&path).exists())
+                .cloned();
+            match original {
+                Some(original) => {
+                    track.duplicate_of = Some(original);
+                    count += 1;
+                }
+                None => {
+                    for key in keys {
+                        queued.entry(key).or_insert_with(|| path.clone());
+                    }
+                }
+            }
+        }
+        album.tracks = tracks;
+    }
+
+    count
+}
+
+/// Returns the path `track` (a duplicate) is saved to by `action`
+pub fn saved_path(action: DuplicateAction, track: &Track, transcoding: &Transcoding) -> String {
+    match (action, &track.duplicate_of) {
+        (DuplicateAction::Skip, Some(original)) => original.clone(),
+        _ => output_path(track, transcoding),
+    }
+}
+
+/// Save `track` (a duplicate) as a link to the file it duplicates, if `action` links them and it
+/// is not on disk yet
+pub fn link(action: DuplicateAction, track: &Track, transcoding: &Transcoding) -> Result<()> {
+    let original = match &track.duplicate_of {
+        Some(original) => Path::new(original),
+        None => return Ok(()),
+    };
+    let path = output_path(track, transcoding);
+    if Path::new(&path).exists() {
+        return Ok(());
+    }
+
+    match action {
+        DuplicateAction::Hardlink => fs::hard_link(original, &path)?,
+        #[cfg(unix)]
+        DuplicateAction::Symlink => std::os::unix::fs::symlink(original, &path)?,
+        #[cfg(windows)]
+        DuplicateAction::Symlink => std::os::windows::fs::symlink_file(original, &path)?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helper::test_helper::{test_album, test_dir};
+
+    /// Returns the album saved in the `title` folder of `dir`, holding the same track as the other
+    /// releases
+    fn album(title: &str, dir: &Path) -> Album {
+        let folder_format = dir.join(title);
+        let mut album = test_album(
+            &folder_format.to_string_lossy(),
+            &["Pit Stop"],
+            "{title}.mp3",
+        );
+        album.title = title.to_string();
+        album.tracks[0].id = Some(42);
+        album.tracks[0].duration = 157.2;
+        album
+    }
+
+    #[test]
+    fn flags_duplicates() {
+        let dir = test_dir("flags_duplicates");
+        let transcoding = Transcoding::default();
+        let mut albums = vec![album("EP", &dir), album("Album", &dir)];
+        // the compilation of various artists does not know the track id
+        let mut compilation = album("Compilation", &dir);
+        compilation.artist = String::from("Various Artists");
+        let mut track = compilation.tracks[0].clone();
+        track.id = None;
+        track.duration = 157.4;
+        track.title = String::from("The Racers - Pit Stop");
+        track.set_artist(&compilation, None);
+        let msg = "should read the artist of the compilation tracks from their title";
+        assert_eq!(track.artist.as_deref(), Some("The Racers"), "{}", msg);
+        compilation.tracks = vec![track];
+        albums.push(compilation);
+
+        let msg = "should flag the tracks of the later albums";
+        let count = flag_duplicates(&mut albums, &TrackIndex::default(), &transcoding);
+        assert_eq!(count, 2, "{}", msg);
+        let original = &albums[0].tracks[0].path;
+        assert_eq!(albums[0].tracks[0].duplicate_of, None, "{}", msg);
+        assert_eq!(
+            albums[1].tracks[0].duplicate_of.as_ref(),
+            Some(original),
+            "{}",
+            msg
+        );
+        assert_eq!(
+            albums[2].tracks[0].duplicate_of.as_ref(),
+            Some(original),
+            "{}",
+            msg
+        );
+
+        let msg = "should point the skipped tracks to the saved file";
+        let actual = saved_path(DuplicateAction::Skip, &albums[1].tracks[0], &transcoding);
+        assert_eq!(&actual, original, "{}", msg);
+        let actual = saved_path(
+            DuplicateAction::Hardlink,
+            &albums[1].tracks[0],
+            &transcoding,
+        );
+        assert_eq!(actual, albums[1].tracks[0].path, "{}", msg);
+
+        let msg = "should find the tracks saved by an earlier run";
+        fs::create_dir_all(Path::new(original).parent().unwrap()).unwrap();
+        fs::write(original, b"mp3").unwrap();
+        let mut index = TrackIndex::default();
+        assert!(index.record(&albums[0], &transcoding), "{}", msg);
+        let mut later = vec![album("Album", &dir)];
+        let count = flag_duplicates(&mut later, &index, &transcoding);
+        assert_eq!(count, 1, "{}", msg);
+
+        let msg = "should link the duplicates to the saved file";
+        let track = &later[0].tracks[0];
+        fs::create_dir_all(Path::new(&track.path).parent().unwrap()).unwrap();
+        link(DuplicateAction::Hardlink, track, &transcoding).unwrap();
+        assert_eq!(fs::read(&track.path).unwrap(), b"mp3", "{}", msg);
+
+        let msg = "should find the saved file at its new path once moved";
+        let new_path = dir.join("Pit Stop.mp3").to_string_lossy().to_string();
+        fs::rename(original, &new_path).unwrap();
+        let moved: HashMap<_, _> = vec![(original.clone(), new_path.clone())]
+            .into_iter()
+            .collect();
+        assert!(index.update_paths(&moved), "{}", msg);
+        let mut later = vec![album("Compilation", &dir)];
+        flag_duplicates(&mut later, &index, &transcoding);
+        assert_eq!(later[0].tracks[0].duplicate_of, Some(new_path), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
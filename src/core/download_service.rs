@@ -1,19 +1,195 @@
 //! DownloadService public contract
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use futures::channel::mpsc;
 use futures::future::join_all;
 
-use crate::{settings::UserSettings, ui::Message};
+use crate::{
+    core::{
+        dedup,
+        reorganise::{self, Reorganisation},
+        DuplicateAction, History, HookData, Logger, PlannedAlbum, ProgressTracker, TrackIndex,
+        Transcoding,
+    },
+    helper,
+    model::Album,
+    settings::UserSettings,
+    source::{self, Source},
+    ui::{Message, TrackStatus},
+};
+
+/// State of the latest download run, kept to act on its tracks once downloaded
+#[derive(Debug, Clone)]
+struct Run {
+    albums: Arc<Vec<Album>>,
+    logger: Logger,
+    progress: ProgressTracker,
+    settings: Arc<UserSettings>,
+}
+
+impl Run {
+    /// Returns the failed tracks of the album at index `album` (or only its `track`), switching
+    /// them to `status` so they are not picked up twice.
+    fn take_failed(
+        &self,
+        album: usize,
+        track: Option<usize>,
+        status: TrackStatus,
+    ) -> Option<(Album, Vec<usize>)> {
+        let album = self.albums.get(album)?;
+        let tracks: Vec<_> = album
+            .tracks
+            .iter()
+            .enumerate()
+            .filter(|(i, t)| {
+                track.unwrap_or(*i) == *i
+                    && self.progress.status(&t.path) == Some(TrackStatus::Failed)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        if tracks.is_empty() {
+            return None;
+        }
+
+        for &i in &tracks {
+            self.progress.set_status(&album.tracks[i].path, status);
+        }
+
+        Some((album.clone(), tracks))
+    }
+}
 
 /// DownloadService public contract
 #[derive(Debug)]
-pub struct DownloadService {}
+pub struct DownloadService {
+    /// Sources resolving the urls to download, in priority order
+    sources: Vec<Box<dyn Source>>,
+    /// Held for the length of a download run (or of a change to the files of the library), so the
+    /// runs (eg. of new releases) wait for the one in progress rather than replacing its state
+    running: tokio::sync::Mutex<()>,
+    run: Mutex<Option<Run>>,
+    /// Releases already downloaded, and the file they are saved to
+    history: Mutex<History>,
+    history_path: PathBuf,
+    /// Files of the tracks already downloaded, to find the duplicates of other releases
+    track_index: Mutex<TrackIndex>,
+    track_index_path: PathBuf,
+}
 
 impl DownloadService {
     /// Create a new instance of this struct
     pub fn new() -> Self {
-        Self {}
+        Self::with_root_dir(&helper::get_root_dir())
+    }
+
+    /// Create a new instance of this struct, keeping its history and track index in `root_dir`
+    fn with_root_dir(root_dir: &Path) -> Self {
+        let history_path = root_dir.join(History::FILE_NAME);
+        let track_index_path = root_dir.join(TrackIndex::FILE_NAME);
+
+        Self {
+            sources: source::default_sources(),
+            running: tokio::sync::Mutex::new(()),
+            run: Mutex::new(None),
+            history: Mutex::new(History::load(&history_path)),
+            history_path,
+            track_index: Mutex::new(TrackIndex::load(&track_index_path)),
+            track_index_path,
+        }
+    }
+
+    /// Flag the tracks of the `albums` already downloaded from another release, unless the
+    /// duplicates are downloaded again
+    fn flag_duplicates(&self, albums: &mut [Album], settings: &UserSettings, logger: &Logger) {
+        if settings.duplicates == DuplicateAction::Download {
+            return;
+        }
+        let index = match self.track_index.lock() {
+            Ok(index) => index,
+            Err(_) => return,
+        };
+
+        let count = dedup::flag_duplicates(albums, &index, &settings.transcoding);
+        if count > 0 {
+            logger.info(format!(
+                "{} track(s) already downloaded from another release",
+                count
+            ));
+        }
+    }
+
+    /// Link the duplicates of the `albums` to the files they duplicate, then record the tracks
+    /// on disk in the track index
+    fn record_tracks(&self, albums: &[Album], settings: &UserSettings, logger: &Logger) {
+        let UserSettings {
+            duplicates,
+            ref transcoding,
+            ..
+        } = *settings;
+        if duplicates == DuplicateAction::Download {
+            return;
+        }
+
+        for album in albums {
+            for track in album.selected_tracks() {
+                if let Err(e) = dedup::link(duplicates, track, transcoding) {
+                    logger.error(format!("Unable to link {}: {}", track.path, e));
+                }
+            }
+        }
+
+        let mut index = match self.track_index.lock() {
+            Ok(index) => index,
+            Err(_) => return,
+        };
+        let mut changed = false;
+        for album in albums {
+            changed |= index.record(album, transcoding);
+        }
+        if changed {
+            if let Err(e) = index.save(&self.track_index_path) {
+                logger.error(format!("Unable to save the track index: {}", e));
+            }
+        }
+    }
+
+    /// Record the albums whose selected tracks are all on disk in the download history
+    fn record_downloaded(&self, albums: &[Album], transcoding: &Transcoding, logger: &Logger) {
+        let mut history = match self.history.lock() {
+            Ok(history) => history,
+            Err(_) => return,
+        };
+
+        let mut changed = false;
+        for album in albums.iter().filter(|album| !album.url.is_empty()) {
+            if PlannedAlbum::new(album.clone(), transcoding).is_complete() {
+                changed |= history.insert(&album.url);
+            }
+        }
+        if changed {
+            if let Err(e) = history.save(&self.history_path) {
+                logger.error(format!("Unable to save the download history: {}", e));
+            }
+        }
+    }
+
+    /// Returns the failed tracks of an album of the latest run, along with the run
+    fn take_failed(
+        &self,
+        album: usize,
+        track: Option<usize>,
+        status: TrackStatus,
+    ) -> Option<(Run, Album, Vec<usize>)> {
+        // hold the lock so concurrent requests cannot pick the same tracks
+        let run = self.run.lock().ok()?;
+        let run = run.as_ref()?;
+        let (album, tracks) = run.take_failed(album, track, status)?;
+
+        Some((run.clone(), album, tracks))
     }
 
     /// Start downloading the files at the specified urls
@@ -21,26 +197,79 @@ impl DownloadService {
         self: Arc<Self>,
         // TODO: use a Url type (ie with validation, parsing, etc)
         urls: String,
-        sender: mpsc::Sender<Message>,
+        logger: Logger,
         settings: UserSettings,
     ) {
-        let albums = crate::fetch_urls(
-            &urls,
-            settings.download_artist_discography,
-            &settings.downloads_path.to_string_lossy(),
-            &settings.file_name_format,
-        )
-        .await;
+        let albums = crate::fetch_urls(&self.sources, &urls, &settings, &logger).await;
+        self.download(albums, logger, settings).await;
+    }
+
+    /// Resolve the specified urls without downloading anything, returning the albums that would
+    /// be saved along with the state of their files
+    pub async fn preview(
+        self: Arc<Self>,
+        urls: String,
+        logger: Logger,
+        settings: UserSettings,
+    ) -> Vec<PlannedAlbum> {
+        let albums = crate::fetch_urls(&self.sources, &urls, &settings, &logger).await;
+        let plan: Vec<_> = albums
+            .into_iter()
+            .map(|album| PlannedAlbum::new(album, &settings.transcoding))
+            .collect();
+
+        let tracks: usize = plan
+            .iter()
+            .map(|album| album.album.selected_tracks().count())
+            .sum();
+        let existing: usize = plan.iter().map(PlannedAlbum::existing_count).sum();
+        logger.info(format!(
+            "Preview: {} album(s), {} track(s), {} already on disk",
+            plan.len(),
+            tracks,
+            existing
+        ));
+
+        plan
+    }
+
+    /// Start downloading the supplied albums, eg. the ones selected from a preview, once the run
+    /// in progress is over
+    pub async fn download(
+        self: Arc<Self>,
+        albums: Vec<Album>,
+        logger: Logger,
+        settings: UserSettings,
+    ) {
+        let _running = self.running.lock().await;
+        self.run_downloads(albums, logger, settings).await;
+    }
+
+    /// Download the supplied albums as the latest run. The caller holds the `running` lock.
+    async fn run_downloads(&self, mut albums: Vec<Album>, logger: Logger, settings: UserSettings) {
+        self.flag_duplicates(&mut albums, &settings, &logger);
+        let progress = ProgressTracker::new(&albums, logger.clone());
 
         // TODO cancellation
         // maybe using a select and a channel to signal?
 
+        let queued = Arc::new(albums.clone());
         let settings = Arc::new(settings);
+        if let Ok(mut run) = self.run.lock() {
+            *run = Some(Run {
+                albums: queued.clone(),
+                logger: logger.clone(),
+                progress: progress.clone(),
+                settings: settings.clone(),
+            });
+        }
+        logger.deliver(Message::AlbumsQueued(albums.clone())).await;
 
         if settings.download_one_album_at_a_time {
             // Download one album at a time
             for album in albums {
-                crate::download_album(album, sender.clone(), settings.clone()).await;
+                crate::download_album(album, logger.clone(), progress.clone(), settings.clone())
+                    .await;
             }
         } else {
             // Concurrent download
@@ -49,13 +278,244 @@ impl DownloadService {
                 .map(|album| {
                     tokio::spawn(crate::download_album(
                         album,
-                        sender.clone(),
+                        logger.clone(),
+                        progress.clone(),
                         settings.clone(),
                     ))
                 })
                 .collect();
             join_all(download_tasks).await;
         }
+
+        progress.emit();
+        self.record_downloaded(&queued, &settings.transcoding, &logger);
+        self.record_tracks(&queued, &settings, &logger);
+
+        let total = progress.stats().total;
+        let data = HookData::run(total.tracks_done, total.tracks_failed);
+        settings.hooks.trigger(data, &logger).await;
+    }
+
+    /// Tag again the mp3 files of the library at `dir` with the data of the releases they are
+    /// matched to, without downloading them, once the run in progress is over. `album_url` is the
+    /// release of the folders not matched otherwise.
+    pub async fn retag_library(
+        self: Arc<Self>,
+        dir: PathBuf,
+        album_url: Option<String>,
+        logger: Logger,
+        settings: UserSettings,
+    ) {
+        let _running = self.running.lock().await;
+        let settings = Arc::new(settings);
+        let album_url = album_url.as_deref();
+        let tagged = crate::retag_library(&self.sources, &dir, album_url, settings, &logger).await;
+        logger.info(format!("{} file(s) tagged", tagged));
+    }
+
+    /// Point the duplicates of the albums saved in `dir` and the track index to the new paths of
+    /// the `moved` files, by previous path
+    fn record_moved(
+        &self,
+        dir: &Path,
+        moved: &HashMap<String, String>,
+        settings: &UserSettings,
+        logger: &Logger,
+    ) {
+        if moved.is_empty() {
+            return;
+        }
+
+        match reorganise::update_duplicates(dir, moved, &settings.transcoding, logger) {
+            Ok(0) => {}
+            Ok(count) => logger.info(format!(
+                "{} album(s) pointed to the moved files they duplicate",
+                count
+            )),
+            Err(e) => logger.error(format!("Unable to read {}: {}", dir.display(), e)),
+        }
+
+        let mut index = match self.track_index.lock() {
+            Ok(index) => index,
+            Err(_) => return,
+        };
+        if index.update_paths(moved) {
+            if let Err(e) = index.save(&self.track_index_path) {
+                logger.error(format!("Unable to save the track index: {}", e));
+            }
+        }
+    }
+
+    /// Plan the reorganisation of the albums saved in `dir` with the current folder and file
+    /// name formats, then apply it unless `dry_run` is set, once the run in progress is over.
+    /// Returns the planned moves.
+    pub async fn reorganise(
+        self: Arc<Self>,
+        dir: PathBuf,
+        dry_run: bool,
+        logger: Logger,
+        settings: UserSettings,
+    ) -> Vec<Reorganisation> {
+        let _running = self.running.lock().await;
+        let service = self.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            let plan = match reorganise::plan(&dir, &settings, &logger) {
+                Ok(plan) => plan,
+                Err(e) => {
+                    logger.error(format!("Unable to read {}: {}", dir.display(), e));
+                    return Vec::new();
+                }
+            };
+            logger.info(format!("{} album(s) to reorganise", plan.len()));
+            if dry_run {
+                return plan;
+            }
+
+            let mut moved = HashMap::new();
+            for reorganisation in &plan {
+                let album = &reorganisation.album;
+                match reorganisation.apply(&settings) {
+                    Ok(_) => {
+                        logger.info(format!(
+                            r#"Moved album "{}" to {}"#,
+                            album.title, album.path
+                        ));
+                        moved.extend(reorganisation.moves.iter().map(|file| {
+                            let from = file.from.to_string_lossy().to_string();
+                            (from, file.to.to_string_lossy().to_string())
+                        }));
+                    }
+                    Err(e) => {
+                        logger.error(format!(r#"Unable to move album "{}": {}"#, album.title, e))
+                    }
+                }
+            }
+            service.record_moved(&dir, &moved, &settings, &logger);
+            plan
+        });
+
+        task.await.unwrap_or_default()
+    }
+
+    /// Check the followed artists for releases missing from the download history, and download
+    /// them. Releases already on disk are only recorded in the history.
+    pub async fn check_subscriptions(self: Arc<Self>, logger: Logger, settings: UserSettings) {
+        logger.info(format!(
+            "Checking {} subscription(s) for new releases",
+            settings.subscriptions.len()
+        ));
+
+        let history = match self.history.lock() {
+            Ok(history) => history.clone(),
+            Err(_) => return,
+        };
+        let albums = crate::fetch_new_releases(
+            &self.sources,
+            &settings.subscriptions,
+            &history,
+            &settings,
+            &logger,
+        )
+        .await;
+
+        let (on_disk, new): (Vec<_>, Vec<_>) = albums.into_iter().partition(|album| {
+            PlannedAlbum::new(album.clone(), &settings.transcoding).is_complete()
+        });
+        self.record_downloaded(&on_disk, &settings.transcoding, &logger);
+
+        if new.is_empty() {
+            logger.info("No new release found");
+            return;
+        }
+        logger.info(format!("{} new release(s) found", new.len()));
+        // queue the new releases until the run in progress is over
+        let _running = self.running.lock().await;
+        logger.deliver(Message::NewReleases(new.clone())).await;
+        self.run_downloads(new, logger, settings).await;
+    }
+
+    /// Check the subscriptions every `subscription_check_interval` minutes of the shared
+    /// `settings`, starting right away. Changes to the settings apply from the next check.
+    pub async fn watch(self: Arc<Self>, logger: Logger, settings: Arc<Mutex<UserSettings>>) {
+        /// Time between two looks at the settings
+        const TICK: Duration = Duration::from_secs(60);
+        let mut last_check: Option<Instant> = None;
+
+        loop {
+            let settings = match settings.lock() {
+                Ok(settings) => settings.clone(),
+                Err(_) => return,
+            };
+            let interval = Duration::from_secs(settings.subscription_check_interval * 60);
+            let due = match last_check {
+                Some(time) => time.elapsed() >= interval,
+                None => true,
+            };
+
+            if settings.subscription_check_interval > 0 && !settings.subscriptions.is_empty() && due
+            {
+                last_check = Some(Instant::now());
+                self.clone()
+                    .check_subscriptions(logger.clone(), settings)
+                    .await;
+            }
+            tokio::time::sleep(TICK).await;
+        }
+    }
+
+    /// Download again the failed tracks of the album at index `album` of the latest run (or only
+    /// its `track`), alongside the downloads still in progress
+    pub async fn retry(self: Arc<Self>, album: usize, track: Option<usize>) {
+        let (run, album, tracks) = match self.take_failed(album, track, TrackStatus::Queued) {
+            Some(failed) => failed,
+            None => return,
+        };
+
+        run.logger.info(format!(
+            r#"Retrying {} track(s) of album "{}""#,
+            tracks.len(),
+            album.title
+        ));
+        crate::retry_tracks(
+            album.clone(),
+            tracks,
+            run.logger.clone(),
+            run.progress.clone(),
+            run.settings.clone(),
+        )
+        .await;
+        run.progress.emit();
+        let albums = [album];
+        self.record_downloaded(&albums, &run.settings.transcoding, &run.logger);
+        self.record_tracks(&albums, &run.settings, &run.logger);
+    }
+
+    /// Give up on the failed tracks of the album at index `album` of the latest run (or only its
+    /// `track`)
+    pub fn skip(&self, album: usize, track: Option<usize>) {
+        if let Some((run, album, tracks)) = self.take_failed(album, track, TrackStatus::Skipped) {
+            run.logger.info(format!(
+                r#"Skipped {} track(s) of album "{}""#,
+                tracks.len(),
+                album.title
+            ));
+        }
+    }
+
+    /// Open the folder of the album at index `album` of the latest run in the file manager
+    pub fn open_folder(&self, album: usize) {
+        let run = match self.run.lock() {
+            Ok(run) => run,
+            Err(_) => return,
+        };
+        if let Some(run) = run.as_ref() {
+            if let Some(album) = run.albums.get(album) {
+                if let Err(e) = helper::open_folder(&album.path) {
+                    run.logger
+                        .error(format!("Unable to open folder {}: {}", album.path, e));
+                }
+            }
+        }
     }
 
     /// Cancel all downloads in-progress
@@ -63,3 +523,79 @@ impl DownloadService {
         todo!();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use futures::channel::mpsc;
+
+    use super::*;
+    use crate::{
+        core::Transcoding,
+        helper::{
+            mock_server::MockServer,
+            test_helper::{test_dir, test_logger},
+        },
+    };
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn records_transcoded_subscriptions() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("records_transcoded_subscriptions");
+        let service = Arc::new(DownloadService::with_root_dir(&dir));
+        let settings = UserSettings {
+            downloads_path: dir.join("{album}"),
+            subscriptions: vec![server.url("/")],
+            transcoding: Transcoding {
+                enabled: true,
+                encoder: String::from("cp"),
+                arguments: String::from("{input} {output}"),
+                extension: String::from("m4a"),
+                keep_originals: false,
+                ..Transcoding::default()
+            },
+            retrieve_files_size: false,
+            modify_tags: false,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            ..UserSettings::default()
+        };
+
+        let msg = "should record the releases whose tracks were transcoded";
+        service
+            .clone()
+            .check_subscriptions(test_logger(), settings.clone())
+            .await;
+        let history = History::load(dir.join(History::FILE_NAME));
+        assert!(
+            history.contains(&server.url("/album/first-album")),
+            "{}",
+            msg
+        );
+        assert!(
+            history.contains(&server.url("/track/single-track")),
+            "{}",
+            msg
+        );
+
+        let msg = "should not announce the recorded releases again";
+        let (sender, mut receiver) = mpsc::channel(16);
+        service
+            .check_subscriptions(Logger::new(sender, false), settings)
+            .await;
+        let mut lines = Vec::new();
+        while let Ok(Some(message)) = receiver.try_next() {
+            if let Message::Log(line, _) = message {
+                lines.push(line);
+            }
+        }
+        assert_eq!(
+            lines.last().map(String::as_str),
+            Some("No new release found"),
+            "{}",
+            msg
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,343 @@
+//! Release filters, deciding which of the albums found are downloaded
+use std::{ops::RangeInclusive, str::FromStr};
+
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Error, model::Album, Result};
+
+/// Kind of release page
+#[derive(Debug, Deserialize, Copy, Clone, Eq, PartialEq, Serialize)]
+pub enum ReleaseType {
+    /// Albums and tracks
+    Any,
+    /// Album pages
+    Album,
+    /// Track (single) pages
+    Track,
+}
+
+impl ReleaseType {
+    pub const ALL: [ReleaseType; 3] = [Self::Any, Self::Album, Self::Track];
+
+    pub fn description(&self) -> &str {
+        match self {
+            Self::Any => "Albums and tracks",
+            Self::Album => "Albums only",
+            Self::Track => "Tracks only",
+        }
+    }
+
+    /// Returns the type of the release page `album` was found on
+    pub fn of(album: &Album) -> Self {
+        if album.url.contains("/track/") {
+            Self::Track
+        } else {
+            Self::Album
+        }
+    }
+}
+
+impl std::fmt::Display for ReleaseType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl FromStr for ReleaseType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "any" => Ok(Self::Any),
+            "album" => Ok(Self::Album),
+            "track" => Ok(Self::Track),
+            _ => Err(Error::Filter(format!(
+                r#"unknown release type "{}", expected any, album or track"#,
+                s
+            ))),
+        }
+    }
+}
+
+/// User configurable release filters. Empty fields do not filter anything.
+#[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ReleaseFilter {
+    /// Earliest release date (`YYYY-MM-DD`)
+    pub released_after: String,
+    /// Latest release date (`YYYY-MM-DD`)
+    pub released_before: String,
+    /// Regular expression release titles must match
+    pub title_pattern: String,
+    /// Minimum number of tracks
+    pub min_tracks: Option<usize>,
+    /// Maximum number of tracks
+    pub max_tracks: Option<usize>,
+    /// Kind of release pages to download
+    pub release_type: ReleaseType,
+}
+
+impl Default for ReleaseFilter {
+    fn default() -> Self {
+        Self {
+            released_after: String::new(),
+            released_before: String::new(),
+            title_pattern: String::new(),
+            min_tracks: None,
+            max_tracks: None,
+            release_type: ReleaseType::Any,
+        }
+    }
+}
+
+impl ReleaseFilter {
+    /// Parse an optional `YYYY-MM-DD` date
+    fn parse_date(date: &str) -> Result<Option<NaiveDate>> {
+        let date = date.trim();
+        if date.is_empty() {
+            return Ok(None);
+        }
+
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| Error::Filter(format!(r#"invalid date "{}", expected YYYY-MM-DD"#, date)))
+    }
+
+    /// Validate the filters, returning a matcher for the albums
+    pub fn compile(&self) -> Result<AlbumFilter> {
+        let title = match self.title_pattern.trim() {
+            "" => None,
+            pattern => Some(Regex::new(pattern).map_err(|e| Error::Filter(e.to_string()))?),
+        };
+
+        Ok(AlbumFilter {
+            released_after: Self::parse_date(&self.released_after)?,
+            released_before: Self::parse_date(&self.released_before)?,
+            title,
+            min_tracks: self.min_tracks,
+            max_tracks: self.max_tracks,
+            release_type: self.release_type,
+        })
+    }
+}
+
+/// Validated [`ReleaseFilter`]
+#[derive(Debug)]
+pub struct AlbumFilter {
+    released_after: Option<NaiveDate>,
+    released_before: Option<NaiveDate>,
+    title: Option<Regex>,
+    min_tracks: Option<usize>,
+    max_tracks: Option<usize>,
+    release_type: ReleaseType,
+}
+
+impl AlbumFilter {
+    /// Returns the reason `album` is filtered out, or None if it should be downloaded
+    pub fn rejection(&self, album: &Album) -> Option<String> {
+        let date = album.release_date.naive_utc().date();
+        let tracks = album.tracks.len();
+
+        if self.release_type != ReleaseType::Any && self.release_type != ReleaseType::of(album) {
+            return Some(format!("not a release of type {}", self.release_type));
+        }
+        if let Some(after) = self.released_after {
+            if date < after {
+                return Some(format!("released before {}", after));
+            }
+        }
+        if let Some(before) = self.released_before {
+            if date > before {
+                return Some(format!("released after {}", before));
+            }
+        }
+        if let Some(title) = &self.title {
+            if !title.is_match(&album.title) {
+                return Some(format!("title not matching {}", title));
+            }
+        }
+        if let Some(min) = self.min_tracks {
+            if tracks < min {
+                return Some(format!("less than {} tracks", min));
+            }
+        }
+        if let Some(max) = self.max_tracks {
+            if tracks > max {
+                return Some(format!("more than {} tracks", max));
+            }
+        }
+
+        None
+    }
+}
+
+/// Track numbers to download from every album, eg. `1-3,5`. Empty selects every track.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TrackSelection(Vec<RangeInclusive<u32>>);
+
+impl TrackSelection {
+    /// True if every track is selected
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// True if the track numbered `number` is selected
+    pub fn contains(&self, number: u32) -> bool {
+        self.is_empty() || self.0.iter().any(|range| range.contains(&number))
+    }
+
+    /// Flag the tracks of `album` selected for download
+    pub fn apply(&self, album: &mut Album) {
+        for track in album.tracks.iter_mut() {
+            track.selected = self.contains(track.number);
+        }
+    }
+}
+
+impl FromStr for TrackSelection {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |range: &str| {
+            Error::Filter(format!(
+                r#"invalid track range "{}", expected eg. 1-3,5"#,
+                range
+            ))
+        };
+        let number = |n: &str, range: &str| n.trim().parse::<u32>().map_err(|_| invalid(range));
+
+        let ranges = s
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .map(|range| {
+                let (start, end) = match range.find('-') {
+                    Some(i) => (number(&range[..i], range)?, number(&range[i + 1..], range)?),
+                    None => {
+                        let n = number(range, range)?;
+                        (n, n)
+                    }
+                };
+                if start > end {
+                    return Err(invalid(range));
+                }
+                Ok(start..=end)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self(ranges))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+    use crate::model::Track;
+
+    /// Returns an album released on `date`, with `tracks` tracks
+    fn album(url: &str, title: &str, date: (i32, u32, u32), tracks: usize) -> Album {
+        let release_date: DateTime<Utc> =
+            format!("{}-{:02}-{:02}T00:00:00Z", date.0, date.1, date.2)
+                .parse()
+                .unwrap();
+        let mut album = Album::new("Artist", None, None, title, release_date, "/music");
+        album.url = url.to_string();
+        album.tracks = (1..=tracks)
+            .map(|i| {
+                let title = format!("Track {}", i);
+                let url = String::from("http://example.com");
+                Track::new(&album, 60.0, None, url, i as u32, title, "{title}.mp3")
+            })
+            .collect();
+
+        album
+    }
+
+    #[test]
+    fn keeps_everything_by_default() {
+        let filter = ReleaseFilter::default().compile().unwrap();
+        let album = album("http://a.bandcamp.com/track/a", "A", (2020, 1, 1), 1);
+
+        assert_eq!(filter.rejection(&album), None);
+    }
+
+    #[test]
+    fn filters_releases() {
+        let filter = ReleaseFilter {
+            released_after: String::from("2020-01-01"),
+            released_before: String::from("2020-12-31"),
+            title_pattern: String::from("(?i)^night"),
+            min_tracks: Some(2),
+            max_tracks: Some(10),
+            release_type: ReleaseType::Album,
+        }
+        .compile()
+        .unwrap();
+        let url = "http://a.bandcamp.com/album/a";
+
+        let msg = "should keep the matching albums";
+        let actual = filter.rejection(&album(url, "Nightcall", (2020, 1, 1), 2));
+        assert_eq!(actual, None, "{}", msg);
+
+        let msg = "should reject the albums released out of the date range";
+        let actual = filter.rejection(&album(url, "Nightcall", (2019, 12, 31), 2));
+        assert!(actual.is_some(), "{}", msg);
+        let actual = filter.rejection(&album(url, "Nightcall", (2021, 1, 1), 2));
+        assert!(actual.is_some(), "{}", msg);
+
+        let msg = "should reject the albums whose title does not match";
+        let actual = filter.rejection(&album(url, "Daybreak", (2020, 6, 1), 2));
+        assert!(actual.is_some(), "{}", msg);
+
+        let msg = "should reject the albums with too few or too many tracks";
+        let actual = filter.rejection(&album(url, "Nightcall", (2020, 6, 1), 1));
+        assert!(actual.is_some(), "{}", msg);
+        let actual = filter.rejection(&album(url, "Nightcall", (2020, 6, 1), 11));
+        assert!(actual.is_some(), "{}", msg);
+
+        let msg = "should reject the other release types";
+        let url = "http://a.bandcamp.com/track/a";
+        let actual = filter.rejection(&album(url, "Nightcall", (2020, 6, 1), 2));
+        assert!(actual.is_some(), "{}", msg);
+    }
+
+    #[test]
+    fn rejects_invalid_filters() {
+        let msg = "should reject invalid dates";
+        let filter = ReleaseFilter {
+            released_after: String::from("01/01/2020"),
+            ..ReleaseFilter::default()
+        };
+        assert!(filter.compile().is_err(), "{}", msg);
+
+        let msg = "should reject invalid title patterns";
+        let filter = ReleaseFilter {
+            title_pattern: String::from("(unclosed"),
+            ..ReleaseFilter::default()
+        };
+        assert!(filter.compile().is_err(), "{}", msg);
+    }
+
+    #[test]
+    fn selects_tracks() {
+        let msg = "should select the tracks in the ranges";
+        let selection: TrackSelection = "1-2, 4".parse().unwrap();
+        let mut album = album("http://a.bandcamp.com/album/a", "A", (2020, 1, 1), 5);
+        selection.apply(&mut album);
+        let actual: Vec<_> = album.tracks.iter().map(|track| track.selected).collect();
+        assert_eq!(actual, vec![true, true, false, true, false], "{}", msg);
+
+        let msg = "should select every track by default";
+        TrackSelection::default().apply(&mut album);
+        assert!(album.tracks.iter().all(|track| track.selected), "{}", msg);
+
+        let msg = "should reject invalid ranges";
+        assert!("1-".parse::<TrackSelection>().is_err(), "{}", msg);
+        assert!("3-1".parse::<TrackSelection>().is_err(), "{}", msg);
+        assert!("first".parse::<TrackSelection>().is_err(), "{}", msg);
+    }
+}
@@ -0,0 +1,76 @@
+//! Record of the releases already downloaded, used to find the new releases of followed artists
+use std::{collections::BTreeSet, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{helper, Result};
+
+/// Urls of the releases already downloaded
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct History {
+    releases: BTreeSet<String>,
+}
+
+impl History {
+    pub const FILE_NAME: &'static str = "download_history.json";
+
+    /// Load the history saved at `path`. A missing (or unreadable) history is empty.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|history| serde_json::from_str(&history).ok())
+            .unwrap_or_default()
+    }
+
+    /// Attempt to save the history at `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let history = serde_json::to_string_pretty(self)?;
+        fs::write(path, history).map_err(From::from)
+    }
+
+    /// True if the release at `url` was already downloaded
+    pub fn contains(&self, url: &str) -> bool {
+        self.releases.contains(&helper::url_key(url))
+    }
+
+    /// Record the release at `url` as downloaded. Returns false if it already was.
+    pub fn insert(&mut self, url: &str) -> bool {
+        self.releases.insert(helper::url_key(url))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helper::test_helper::test_dir;
+
+    #[test]
+    fn records_releases() {
+        let mut history = History::default();
+        history.insert("https://artist.bandcamp.com/album/first/");
+
+        let msg = "should match the release urls whatever their protocol";
+        assert!(
+            history.contains("http://artist.bandcamp.com/album/first"),
+            "{}",
+            msg
+        );
+        assert!(
+            !history.contains("http://artist.bandcamp.com/album/second"),
+            "{}",
+            msg
+        );
+
+        let msg = "should load the saved history";
+        let dir = test_dir("records_releases");
+        let path = dir.join(History::FILE_NAME);
+        history.save(&path).unwrap();
+        assert_eq!(History::load(&path), history, "{}", msg);
+        let _ = std::fs::remove_file(&path);
+
+        let msg = "should start from an empty history";
+        assert_eq!(History::load(&path), History::default(), "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,320 @@
+//! User commands run on download events (eg. to import the albums into a library)
+use std::{
+    fmt,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::Logger,
+    error::Error,
+    model::{Album, Track},
+    Result,
+};
+
+/// Download events triggering a hook
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    /// An album download finished, some of its tracks may have failed
+    AlbumComplete,
+    /// Every album of a download run finished
+    RunComplete,
+    /// A track could not be downloaded
+    TrackFailed,
+}
+
+impl HookEvent {
+    fn name(&self) -> &str {
+        match self {
+            Self::AlbumComplete => "album-complete",
+            Self::RunComplete => "run-complete",
+            Self::TrackFailed => "track-failed",
+        }
+    }
+}
+
+impl fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Data of an event, passed to its hook as `BANDCAMP_*` environment variables and as JSON on its
+/// standard input
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HookData {
+    pub event: HookEvent,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Url of the album page
+    pub url: Option<String>,
+    /// Folder of the album
+    pub path: Option<String>,
+    pub track: Option<String>,
+    pub track_number: Option<u32>,
+    /// File of the track
+    pub track_path: Option<String>,
+    /// Number of tracks downloaded (or already on disk) for the album, or the whole run
+    pub tracks_done: usize,
+    /// Number of tracks which could not be downloaded for the album, or the whole run
+    pub tracks_failed: usize,
+}
+
+impl HookData {
+    fn new(event: HookEvent, album: Option<&Album>, track: Option<&Track>) -> Self {
+        Self {
+            event,
+            artist: album.map(|album| album.artist.clone()),
+            album: album.map(|album| album.title.clone()),
+            url: album.map(|album| album.url.clone()),
+            path: album.map(|album| album.path.clone()),
+            track: track.map(|track| track.title.clone()),
+            track_number: track.map(|track| track.number),
+            track_path: track.map(|track| track.path.clone()),
+            tracks_done: 0,
+            tracks_failed: 0,
+        }
+    }
+
+    /// Returns the data of the complete download of `album`
+    pub fn album(album: &Album, tracks_done: usize, tracks_failed: usize) -> Self {
+        Self {
+            tracks_done,
+            tracks_failed,
+            ..Self::new(HookEvent::AlbumComplete, Some(album), None)
+        }
+    }
+
+    /// Returns the data of the failed download of `track` of `album`
+    pub fn track(album: &Album, track: &Track) -> Self {
+        Self {
+            tracks_failed: 1,
+            ..Self::new(HookEvent::TrackFailed, Some(album), Some(track))
+        }
+    }
+
+    /// Returns the data of a complete download run
+    pub fn run(tracks_done: usize, tracks_failed: usize) -> Self {
+        Self {
+            tracks_done,
+            tracks_failed,
+            ..Self::new(HookEvent::RunComplete, None, None)
+        }
+    }
+
+    /// Returns the environment variables holding the data, the missing values being empty
+    fn variables(&self) -> Vec<(&'static str, String)> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        vec![
+            ("BANDCAMP_EVENT", self.event.to_string()),
+            ("BANDCAMP_ARTIST", text(&self.artist)),
+            ("BANDCAMP_ALBUM", text(&self.album)),
+            ("BANDCAMP_URL", text(&self.url)),
+            ("BANDCAMP_PATH", text(&self.path)),
+            ("BANDCAMP_TRACK", text(&self.track)),
+            (
+                "BANDCAMP_TRACK_NUMBER",
+                self.track_number
+                    .map(|number| number.to_string())
+                    .unwrap_or_default(),
+            ),
+            ("BANDCAMP_TRACK_PATH", text(&self.track_path)),
+            ("BANDCAMP_TRACKS_DONE", self.tracks_done.to_string()),
+            ("BANDCAMP_TRACKS_FAILED", self.tracks_failed.to_string()),
+        ]
+    }
+}
+
+/// Commands run on download events. Each command is split on whitespace, the placeholders
+/// `{event}`, `{artist}`, `{album}`, `{url}`, `{path}`, `{track}` and `{track_path}` being
+/// replaced in each argument; it is not run by a shell. An empty command is not run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    /// Command run when an album download finishes
+    pub album_complete: String,
+    /// Command run when every album of a download run finishes
+    pub run_complete: String,
+    /// Command run when a track could not be downloaded
+    pub track_failed: String,
+}
+
+impl Hooks {
+    /// Returns the command template of `event`
+    fn command(&self, event: HookEvent) -> &str {
+        match event {
+            HookEvent::AlbumComplete => &self.album_complete,
+            HookEvent::RunComplete => &self.run_complete,
+            HookEvent::TrackFailed => &self.track_failed,
+        }
+    }
+
+    /// Returns the program and arguments of the hook of `data`, its placeholders replaced
+    fn arguments(&self, data: &HookData) -> Vec<String> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        self.command(data.event)
+            .split_whitespace()
+            .map(|argument| {
+                argument
+                    .replace("{event}", data.event.name())
+                    .replace("{artist}", &text(&data.artist))
+                    .replace("{album}", &text(&data.album))
+                    .replace("{url}", &text(&data.url))
+                    .replace("{path}", &text(&data.path))
+                    .replace("{track}", &text(&data.track))
+                    .replace("{track_path}", &text(&data.track_path))
+            })
+            .collect()
+    }
+
+    /// Run the hook of the event of `data`, if any, waiting for it to exit. Its output is written
+    /// to the log.
+    pub fn run(&self, data: &HookData, logger: &Logger) -> Result<()> {
+        let arguments = self.arguments(data);
+        let (program, arguments) = match arguments.split_first() {
+            Some(command) => command,
+            None => return Ok(()),
+        };
+        logger.verbose(format!("Running the {} hook: {}", data.event, program));
+
+        let mut child = Command::new(program)
+            .args(arguments)
+            .envs(data.variables())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::Io(format!("Unable to run {}: {}", program, e)))?;
+
+        // the hook may exit without reading its input
+        if let Some(mut stdin) = child.stdin.take() {
+            let json = serde_json::to_string(data)?;
+            let _ = stdin.write_all(json.as_bytes());
+        }
+        let output = child.wait_with_output()?;
+
+        let prefix = format!("[{} hook]", data.event);
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            logger.info(format!("{} {}", prefix, line));
+        }
+        for line in String::from_utf8_lossy(&output.stderr).lines() {
+            logger.warn(format!("{} {}", prefix, line));
+        }
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(Error::Io(format!("{} failed ({})", program, output.status)))
+        }
+    }
+
+    /// Run the hook of the event of `data` in the background of the download tasks, logging its
+    /// failure
+    pub async fn trigger(&self, data: HookData, logger: &Logger) {
+        if self.command(data.event).trim().is_empty() {
+            return;
+        }
+
+        let (hooks, task_logger) = (self.clone(), logger.clone());
+        let task = tokio::task::spawn_blocking(move || hooks.run(&data, &task_logger));
+        match task.await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => logger.error(format!("Hook error: {}", e)),
+            Err(e) => logger.error(format!("Hook error: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+    use futures::channel::mpsc;
+
+    use super::*;
+
+    fn album() -> Album {
+        let mut album = Album::new(
+            "The Racers",
+            None,
+            None,
+            "Night Drive",
+            Utc::now(),
+            "/music",
+        );
+        let url = String::new();
+        let track = Track::new(
+            &album,
+            0.0,
+            None,
+            url,
+            2,
+            String::from("Pit Stop"),
+            "{title}.mp3",
+        );
+        album.tracks = vec![track];
+        album
+    }
+
+    #[test]
+    fn replaces_placeholders() {
+        let album = album();
+        let hooks = Hooks {
+            track_failed: String::from("notify {event} {album}:{track}"),
+            ..Hooks::default()
+        };
+
+        let msg = "should replace the placeholders of each argument";
+        let data = HookData::track(&album, &album.tracks[0]);
+        let actual = hooks.arguments(&data);
+        let expected = vec!["notify", "track-failed", "Night Drive:Pit Stop"];
+        assert_eq!(actual, expected, "{}", msg);
+
+        let msg = "should not run the hooks of the other events";
+        let data = HookData::run(2, 0);
+        assert!(hooks.arguments(&data).is_empty(), "{}", msg);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_hooks() {
+        let dir = crate::helper::test_helper::test_dir("runs_hooks");
+        let script = dir.join("hook.sh");
+        std::fs::write(&script, "echo \"$BANDCAMP_ALBUM\"\ncat\n").unwrap();
+
+        let (sender, mut receiver) = mpsc::channel(16);
+        let logger = Logger::new(sender, false);
+        let album = album();
+        let data = HookData::album(&album, 1, 0);
+
+        let msg = "should pass the data as environment variables and on the standard input";
+        let hooks = Hooks {
+            album_complete: format!("sh {}", script.to_string_lossy()),
+            ..Hooks::default()
+        };
+        hooks.run(&data, &logger).unwrap();
+        let mut lines = Vec::new();
+        while let Ok(Some(message)) = receiver.try_next() {
+            if let crate::ui::Message::Log(line, _) = message {
+                lines.push(line);
+            }
+        }
+        let json = serde_json::to_string(&data).unwrap();
+        let expected = vec![
+            String::from("[album-complete hook] Night Drive"),
+            format!("[album-complete hook] {}", json),
+        ];
+        assert_eq!(lines, expected, "{}", msg);
+
+        let msg = "should report the failed hooks";
+        let hooks = Hooks {
+            album_complete: String::from("false"),
+            ..Hooks::default()
+        };
+        assert!(hooks.run(&data, &logger).is_err(), "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,209 @@
+//! Existing local library, whose folders are matched to Bandcamp releases to be tagged again
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    core::{sidecar, tag},
+    model::Album,
+    Result,
+};
+
+/// Release a library folder is matched to
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlbumSource {
+    /// Album read from the `album.json` sidecar of the folder
+    Sidecar(Box<Album>),
+    /// Url of the release page, eg. saved in the tags of the files
+    Url(String),
+}
+
+/// Folder of a local library holding audio files
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryFolder {
+    pub path: PathBuf,
+    /// mp3 files of the folder, sorted by name
+    pub files: Vec<PathBuf>,
+}
+
+/// Returns true if `path` is an mp3 file, the only files holding id3 tags
+fn is_mp3(path: &Path) -> bool {
+    let extension = path.extension();
+    path.is_file() && matches!(extension, Some(extension) if extension.eq_ignore_ascii_case("mp3"))
+}
+
+/// Returns the folders of `dir` and its subfolders holding mp3 files, sorted by path
+pub fn scan(dir: &Path) -> Result<Vec<LibraryFolder>> {
+    let mut folders = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(path) = pending.pop() {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                pending.push(entry_path);
+            } else if is_mp3(&entry_path) {
+                files.push(entry_path);
+            }
+        }
+        if !files.is_empty() {
+            files.sort();
+            folders.push(LibraryFolder { path, files });
+        }
+    }
+    folders.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(folders)
+}
+
+/// Returns true if the name of `file` is `title` (in lowercase), once its track number prefix
+/// (eg. "03 - ") is removed
+fn is_named(file: &Path, title: &str) -> bool {
+    let stem = match file.file_stem() {
+        Some(stem) => stem.to_string_lossy().trim().to_lowercase(),
+        None => return false,
+    };
+    let name = stem
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(|c: char| c.is_whitespace() || ".-_".contains(c));
+
+    stem == title || name == title
+}
+
+impl LibraryFolder {
+    /// Returns the release of the folder, read from its sidecar or else from the release url
+    /// saved in the tags of its files
+    pub fn album_source(&self) -> Option<AlbumSource> {
+        if let Ok((album, _)) = sidecar::read_json(&self.path) {
+            return Some(AlbumSource::Sidecar(Box::new(album)));
+        }
+
+        self.files.iter().find_map(|file| {
+            let tag = id3::Tag::read_from_path(file).ok()?;
+            tag::release_url(&tag).map(|url| AlbumSource::Url(url.to_string()))
+        })
+    }
+
+    /// Returns `album` saved to the folder, its tracks pointing to the files they are matched to
+    /// by track number, title tag or file name (after its track number). Only the matched tracks
+    /// are selected.
+    pub fn match_album(&self, mut album: Album) -> Album {
+        let mut files: Vec<_> = self
+            .files
+            .iter()
+            .map(|file| (file, id3::Tag::read_from_path(file).ok()))
+            .collect();
+
+        album.path = self.path.to_string_lossy().into();
+        for track in album.tracks.iter_mut() {
+            let title = track.title.trim().to_lowercase();
+            let matches_number = |tag: &Option<id3::Tag>| match tag {
+                Some(tag) => tag.track() == Some(track.number),
+                None => false,
+            };
+            // an untitled track only matches by number
+            let matches_title = |tag: &Option<id3::Tag>| match tag {
+                Some(tag) if !title.is_empty() => {
+                    tag.title().map(|t| t.trim().to_lowercase()).as_ref() == Some(&title)
+                }
+                _ => false,
+            };
+            let matches_name = |file: &Path| !title.is_empty() && is_named(file, &title);
+
+            let found = files
+                .iter()
+                .position(|(_, tag)| matches_number(tag))
+                .or_else(|| files.iter().position(|(_, tag)| matches_title(tag)))
+                .or_else(|| files.iter().position(|(file, _)| matches_name(file)));
+            match found {
+                Some(i) => {
+                    let (file, _) = files.remove(i);
+                    track.path = file.to_string_lossy().into();
+                    track.selected = true;
+                }
+                None => track.selected = false,
+            }
+        }
+
+        album
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::{
+        helper::test_helper::{test_album, test_dir},
+        model::Track,
+    };
+
+    #[test]
+    fn matches_library_folders() {
+        let dir = test_dir("matches_library_folders");
+        let folder = dir.join("The Racers").join("Night Drive");
+        fs::create_dir_all(&folder).unwrap();
+        let url = "https://theracers.bandcamp.com/album/night-drive";
+
+        // tagged by track number, by title, and not tagged at all
+        let mut tag = id3::Tag::new();
+        tag.set_track(1);
+        tag::update_release_url(&mut tag, url);
+        fs::write(folder.join("a.mp3"), b"").unwrap();
+        tag.write_to_path(folder.join("a.mp3"), id3::Version::Id3v24)
+            .unwrap();
+        let mut tag = id3::Tag::new();
+        tag.set_title("PIT STOP");
+        fs::write(folder.join("b.mp3"), b"").unwrap();
+        tag.write_to_path(folder.join("b.mp3"), id3::Version::Id3v24)
+            .unwrap();
+        fs::write(folder.join("03 - Final Lap.mp3"), b"").unwrap();
+        // only named after the third track
+        fs::write(folder.join("01 - Final Lap (Live).mp3"), b"").unwrap();
+        fs::write(folder.join("cover.jpg"), b"").unwrap();
+
+        let msg = "should find the folders holding mp3 files";
+        let folders = scan(&dir).unwrap();
+        assert_eq!(folders.len(), 1, "{}", msg);
+        let library_folder = &folders[0];
+        assert_eq!(library_folder.path, folder, "{}", msg);
+        assert_eq!(library_folder.files.len(), 4, "{}", msg);
+
+        let msg = "should read the release url from the tags";
+        let actual = library_folder.album_source();
+        let expected = Some(AlbumSource::Url(url.to_string()));
+        assert_eq!(actual, expected, "{}", msg);
+
+        let msg = "should match the tracks by number, title and whole file name";
+        let titles = ["Warm Up", "Pit Stop", "Final Lap"];
+        let mut album = test_album("/music", &titles, "{title}.mp3");
+        let untitled = Track::new(&album, 0.0, None, String::new(), 4, String::new(), "");
+        album.tracks.push(untitled);
+        let mut album = library_folder.match_album(album);
+        let actual: Vec<_> = album.tracks.iter().map(|track| &track.path).collect();
+        let expected: Vec<_> = ["a.mp3", "b.mp3", "03 - Final Lap.mp3"]
+            .iter()
+            .map(|file| folder.join(file).to_string_lossy().to_string())
+            .collect();
+        assert_eq!(actual[..3], expected.iter().collect::<Vec<_>>(), "{}", msg);
+
+        let msg = "should not match the untitled tracks by name";
+        assert!(!album.tracks[3].selected, "{}", msg);
+        album.tracks.pop();
+
+        let msg = "should prefer the album sidecar";
+        sidecar::write_json(&album, Utc::now()).unwrap();
+        let actual = library_folder.album_source();
+        assert_eq!(
+            actual,
+            Some(AlbumSource::Sidecar(Box::new(album))),
+            "{}",
+            msg
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
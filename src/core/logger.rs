@@ -0,0 +1,337 @@
+//! Logging facade delivering messages to the UI and a rotating log file
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures::{channel::mpsc, SinkExt};
+
+use crate::ui::{LogLevel, Message};
+
+/// Log file with size based rotation.
+///
+/// When a write would grow the file beyond `max_size` bytes, the file is renamed to
+/// `<name>.1` (shifting existing archives up to `<name>.<max_archives>`) and a new file is
+/// started.
+#[derive(Debug)]
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_archives: usize,
+}
+
+impl LogFile {
+    /// Default maximum size of a log file before it is rotated (1 MiB)
+    const MAX_SIZE: u64 = 1024 * 1024;
+    /// Default number of rotated log files to keep
+    const MAX_ARCHIVES: usize = 3;
+
+    /// Open (or create) the log file at `path` in append mode
+    fn open(path: &Path, max_size: u64, max_archives: usize) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            max_size,
+            max_archives,
+        })
+    }
+
+    /// Returns the path of the `index`th archived log file
+    fn archive_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        name.into()
+    }
+
+    /// Shift archived files and start a new log file
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.max_archives == 0 {
+            self.file = File::create(&self.path)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        let _ = fs::remove_file(self.archive_path(self.max_archives));
+        for i in (1..self.max_archives).rev() {
+            let from = self.archive_path(i);
+            if from.exists() {
+                fs::rename(&from, self.archive_path(i + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.archive_path(1))?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    /// Append a line to the log file, rotating it first if required
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+
+        Ok(())
+    }
+}
+
+/// Logging facade shared by the UI and the download tasks.
+///
+/// Every message is written to the log file (if one is configured). Messages are also forwarded
+/// to the UI channel, except [`LogLevel::Verbose`] messages when verbose logging is disabled.
+/// Delivery to the UI never blocks or panics: if the channel is full the message is dropped from
+/// the UI and a notice is displayed once room is available again.
+#[derive(Debug, Clone)]
+pub struct Logger {
+    sender: Arc<Mutex<mpsc::Sender<Message>>>,
+    file: Option<Arc<Mutex<LogFile>>>,
+    verbose: Arc<AtomicBool>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Logger {
+    /// Name of the application log file
+    pub const FILE_NAME: &'static str = "bandcamp_downloader.log";
+
+    /// Create a new Logger delivering messages to the `sender` channel
+    pub fn new(sender: mpsc::Sender<Message>, verbose: bool) -> Self {
+        Self {
+            sender: Arc::new(Mutex::new(sender)),
+            file: None,
+            verbose: Arc::new(AtomicBool::new(verbose)),
+            dropped: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Also write every message to the rotating log file at `path`
+    pub fn with_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        let path = path.as_ref();
+        match LogFile::open(path, LogFile::MAX_SIZE, LogFile::MAX_ARCHIVES) {
+            Ok(file) => self.file = Some(Arc::new(Mutex::new(file))),
+            Err(e) => self.warn(format!(
+                "Unable to open log file {}: {}",
+                path.to_string_lossy(),
+                e
+            )),
+        }
+
+        self
+    }
+
+    /// Enable or disable the display of verbose messages in the UI
+    pub fn set_verbose(&self, verbose: bool) {
+        self.verbose.store(verbose, Ordering::Relaxed);
+    }
+
+    /// Attempt to deliver a message to the UI without blocking. Returns false if the message
+    /// could not be delivered.
+    fn try_send(&self, message: Message) -> bool {
+        match self.sender.lock() {
+            Ok(mut sender) => sender.try_send(message).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Write a line to the log file, if there is one
+    fn write_file(&self, level: &LogLevel, msg: &str) {
+        if let Some(file) = &self.file {
+            let line = format!(
+                "{} {:<7} {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                level,
+                msg
+            );
+            if let Ok(mut file) = file.lock() {
+                // a failure to write the log file must not interrupt the application
+                let _ = file.write_line(&line);
+            }
+        }
+    }
+
+    /// Log some text at the specified log level
+    pub fn log<T: ToString>(&self, level: LogLevel, msg: T) {
+        let msg = msg.to_string();
+        self.write_file(&level, &msg);
+
+        if let LogLevel::Verbose = level {
+            if !self.verbose.load(Ordering::Relaxed) {
+                return;
+            }
+        }
+
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        if dropped > 0
+            && self.try_send(Message::Log(
+                format!(
+                    "{} message(s) could not be displayed, see the log file for details",
+                    dropped
+                ),
+                LogLevel::Warn,
+            ))
+        {
+            self.dropped.fetch_sub(dropped, Ordering::Relaxed);
+        }
+
+        if !self.try_send(Message::Log(msg, level)) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Log detailed information, only displayed if verbose logging is enabled
+    pub fn verbose<T: ToString>(&self, msg: T) {
+        self.log(LogLevel::Verbose, msg)
+    }
+
+    /// Log a notice of normal event progress
+    pub fn info<T: ToString>(&self, msg: T) {
+        self.log(LogLevel::Info, msg)
+    }
+
+    /// Log a potential concern
+    pub fn warn<T: ToString>(&self, msg: T) {
+        self.log(LogLevel::Warn, msg)
+    }
+
+    /// Log a problem requiring intervention
+    pub fn error<T: ToString>(&self, msg: T) {
+        self.log(LogLevel::Error, msg)
+    }
+
+    /// Deliver a non-log event (eg. download progress) to the UI. These events are superseded by
+    /// the next one of their kind, so they are silently discarded if the channel is full.
+    pub fn send(&self, message: Message) {
+        self.try_send(message);
+    }
+
+    /// Deliver an event which must not be discarded, waiting for room in the UI channel if it is
+    /// full.
+    pub async fn deliver(&self, message: Message) {
+        let sender = match self.sender.lock() {
+            Ok(sender) => Some(sender.clone()),
+            Err(_) => None,
+        };
+        if let Some(mut sender) = sender {
+            // only fails if the UI is gone, in which case there is nobody left to notify
+            let _ = sender.send(message).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::helper::test_helper::test_dir;
+
+    #[test]
+    fn rotates_log_file() {
+        let dir = test_dir("rotates_log_file");
+        let path = dir.join("test.log");
+        let mut file = LogFile::open(&path, 16, 2).unwrap();
+
+        for line in &["first line", "second line", "third line", "fourth line"] {
+            file.write_line(line).unwrap();
+        }
+
+        let msg = "should keep the latest line in the current file";
+        let actual = fs::read_to_string(&path).unwrap();
+        assert_eq!(actual, "fourth line\n", "{}", msg);
+
+        let msg = "should shift older lines into numbered archives";
+        let actual = fs::read_to_string(file.archive_path(1)).unwrap();
+        assert_eq!(actual, "third line\n", "{}", msg);
+        let actual = fs::read_to_string(file.archive_path(2)).unwrap();
+        assert_eq!(actual, "second line\n", "{}", msg);
+
+        let msg = "should discard archives beyond the limit";
+        assert!(!file.archive_path(3).exists(), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn filters_verbose_messages() {
+        let (sender, mut receiver) = mpsc::channel(10);
+        let logger = Logger::new(sender, false);
+
+        logger.verbose("hidden");
+        logger.info("shown");
+        logger.set_verbose(true);
+        logger.verbose("now shown");
+        drop(logger);
+
+        let actual: Vec<_> = futures::executor::block_on(receiver.by_ref().collect::<Vec<_>>())
+            .into_iter()
+            .filter_map(|message| match message {
+                Message::Log(msg, _) => Some(msg),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(actual, vec!["shown", "now shown"]);
+    }
+
+    #[test]
+    fn never_panics_when_channel_is_full() {
+        let dir = test_dir("never_panics_when_channel_is_full");
+        let path = dir.join("test.log");
+        let (sender, mut receiver) = mpsc::channel(1);
+        let logger = Logger::new(sender, false).with_file(&path);
+
+        for i in 0..10 {
+            logger.info(format!("message {}", i));
+        }
+
+        let msg = "should write every message to the log file";
+        let actual = fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(actual, 10, "{}", msg);
+
+        let msg = "should notify of dropped messages once the channel has room";
+        // channel capacity is the buffer size plus one slot per sender
+        for _ in 0..2 {
+            futures::executor::block_on(receiver.next());
+        }
+        logger.info("last message");
+        drop(logger);
+        let actual: Vec<_> = futures::executor::block_on(receiver.collect::<Vec<_>>())
+            .into_iter()
+            .filter_map(|message| match message {
+                Message::Log(msg, _) => Some(msg),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                "8 message(s) could not be displayed, see the log file for details",
+                "last message"
+            ],
+            "{}",
+            msg
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,8 +1,30 @@
+pub use dedup::{DuplicateAction, TrackIndex};
 pub use download_service::DownloadService;
+pub use filter::{AlbumFilter, ReleaseFilter, ReleaseType, TrackSelection};
+pub use history::History;
+pub use hooks::{HookData, HookEvent, Hooks};
+pub use logger::Logger;
+pub use plan::PlannedAlbum;
+pub use progress::{AlbumProgress, DownloadStats, ProgressCount, ProgressTracker};
+pub use session::Session;
+pub use transcode::Transcoding;
 
+pub mod archive;
+pub mod dedup;
 mod download_service;
+mod filter;
+mod history;
+mod hooks;
+pub mod library;
+mod logger;
+mod plan;
 pub mod playlist;
+mod progress;
+pub mod reorganise;
+mod session;
+pub mod sidecar;
 pub mod tag;
+mod transcode;
 
 #[doc(inline)]
 pub use tag::EditAction;
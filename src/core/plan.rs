@@ -0,0 +1,127 @@
+//! Download plan, listing the files a download run would write
+use std::{fmt, path::Path};
+
+use crate::{core::Transcoding, model::Album};
+
+/// An album a download run would save, along with the state of its track files
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedAlbum {
+    pub album: Album,
+    /// True for each track whose file (or transcoded file) is already on disk, or which
+    /// duplicates a track saved from another release
+    pub existing: Vec<bool>,
+}
+
+impl PlannedAlbum {
+    /// Plan the download of `album`, checking which of its track files already exist. The
+    /// downloaded files may have been replaced by their transcoded ones if `transcoding` is
+    /// enabled.
+    pub fn new(album: Album, transcoding: &Transcoding) -> Self {
+        let existing = album
+            .tracks
+            .iter()
+            .map(|track| {
+                track.duplicate_of.is_some()
+                    || Path::new(&track.path).is_file()
+                    || (transcoding.enabled
+                        && Path::new(&transcoding.output_path(&track.path)).is_file())
+            })
+            .collect();
+
+        Self { album, existing }
+    }
+
+    /// Returns the number of selected tracks already on disk
+    pub fn existing_count(&self) -> usize {
+        self.album
+            .tracks
+            .iter()
+            .zip(&self.existing)
+            .filter(|(track, exists)| track.selected && **exists)
+            .count()
+    }
+
+    /// True if every selected track is already on disk
+    pub fn is_complete(&self) -> bool {
+        self.existing_count() == self.album.selected_tracks().count()
+    }
+}
+
+impl fmt::Display for PlannedAlbum {
+    /// Writes the album folder and its selected track files as a tree
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let album = &self.album;
+        writeln!(
+            f,
+            "{} - {} ({} track(s), {} on disk)",
+            album.artist,
+            album.title,
+            album.selected_tracks().count(),
+            self.existing_count()
+        )?;
+        write!(f, "  {}", album.path)?;
+
+        let tracks = album.tracks.iter().zip(&self.existing);
+        for (track, exists) in tracks.filter(|(track, _)| track.selected) {
+            let file_name = Path::new(&track.path)
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            write!(f, "\n    {}", file_name)?;
+            if *exists {
+                write!(f, " (on disk)")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helper::test_helper::{test_album, test_dir};
+
+    #[test]
+    fn finds_existing_files() {
+        let dir = test_dir("finds_existing_files");
+
+        let titles = ["Warm Up", "Pit Stop"];
+        let album = test_album(&dir.to_string_lossy(), &titles, "{tracknum}.mp3");
+        std::fs::write(&album.tracks[1].path, b"mp3").unwrap();
+
+        let transcoding = Transcoding {
+            enabled: true,
+            extension: String::from("opus"),
+            ..Transcoding::default()
+        };
+        let msg = "should flag the tracks already on disk";
+        let plan = PlannedAlbum::new(album.clone(), &transcoding);
+        assert_eq!(plan.existing, vec![false, true], "{}", msg);
+
+        let msg = "should flag the tracks whose transcoded file is on disk";
+        std::fs::write(dir.join("01.opus"), b"opus").unwrap();
+        let plan = PlannedAlbum::new(album.clone(), &transcoding);
+        assert_eq!(plan.existing, vec![true, true], "{}", msg);
+        std::fs::remove_file(dir.join("01.opus")).unwrap();
+
+        let mut plan = PlannedAlbum::new(album, &Transcoding::default());
+
+        let msg = "should list the track files";
+        let expected = format!(
+            "The Racers - Night Drive (2 track(s), 1 on disk)\n  {}\n    01.mp3\n    02.mp3 (on disk)",
+            dir.to_string_lossy()
+        );
+        assert_eq!(plan.to_string(), expected, "{}", msg);
+
+        let msg = "should only list the selected tracks";
+        plan.album.tracks[1].selected = false;
+        let expected = format!(
+            "The Racers - Night Drive (1 track(s), 0 on disk)\n  {}\n    01.mp3",
+            dir.to_string_lossy()
+        );
+        assert_eq!(plan.to_string(), expected, "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -5,6 +5,7 @@ use std::{
 };
 
 use crate::{
+    helper,
     model::{Album, Track},
     settings::PlaylistFormat,
     Result,
@@ -18,7 +19,7 @@ where
     let mut file = fs::File::create(file_path)?;
     let mut writer = m3u::Writer::new(&mut file);
 
-    for track in &album.tracks {
+    for track in album.selected_tracks() {
         let entry = m3u::path_entry(&track.path);
         writer.write_entry(&entry)?;
     }
@@ -43,14 +44,23 @@ where
     P: AsRef<Path>,
 {
     let mut file = fs::File::create(file_path)?;
-    let playlist: Vec<_> = album.tracks.iter().map(From::from).collect();
+    let playlist: Vec<_> = album.selected_tracks().map(From::from).collect();
 
     pls::write(&playlist, &mut file)?;
 
     Ok(())
 }
 
-/// Write an album playlist to disk in the specified format
+/// Returns the path the playlist of `album` is written to in the specified format, named from
+/// `file_name_format`
+pub fn playlist_path(format: PlaylistFormat, album: &Album, file_name_format: &str) -> PathBuf {
+    let file_name = helper::parse_filename(file_name_format, album);
+    let mut file_path: PathBuf = [&album.path, &file_name].iter().collect();
+    file_path.set_extension(format.value());
+    file_path
+}
+
+/// Write a playlist of the selected tracks of an album to disk in the specified format
 pub fn write_playlist(format: PlaylistFormat, album: &Album, mut file_path: PathBuf) -> Result<()> {
     file_path.set_extension(format.value());
     match format {
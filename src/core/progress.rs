@@ -0,0 +1,520 @@
+//! Aggregate download progress: bytes, tracks, throughput and estimated time remaining
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    core::Logger,
+    helper,
+    model::Album,
+    ui::{Message, Progress, TrackStatus},
+};
+
+/// Completion counters for a set of tracks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressCount {
+    /// Bytes downloaded
+    pub bytes_done: u64,
+    /// Bytes expected
+    pub bytes_total: u64,
+    /// Number of tracks successfully downloaded (or already on disk)
+    pub tracks_done: usize,
+    /// Number of tracks that could not be downloaded
+    pub tracks_failed: usize,
+    /// Number of tracks to download
+    pub tracks_total: usize,
+}
+
+impl ProgressCount {
+    /// Completion percentage, in bytes
+    pub fn percent(&self) -> f32 {
+        if self.bytes_total == 0 {
+            return 0.0;
+        }
+
+        self.bytes_done as f32 / self.bytes_total as f32 * 100.0
+    }
+}
+
+/// Download progress of an album
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlbumProgress {
+    /// The album title
+    pub title: String,
+    /// Album completion counters
+    pub count: ProgressCount,
+}
+
+/// Snapshot of the progress of all the downloads in a run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DownloadStats {
+    /// Progress of each album, in download order
+    pub albums: Vec<AlbumProgress>,
+    /// Overall completion counters
+    pub total: ProgressCount,
+    /// Download speed (in bytes per second) averaged over the last few seconds
+    pub throughput: f64,
+    /// Estimated time remaining, if the download speed is known
+    pub eta: Option<Duration>,
+}
+
+/// State of a single file
+#[derive(Debug)]
+struct FileState {
+    album: usize,
+    complete: u64,
+    total: u64,
+    /// Last completion percentage reported to the UI
+    percent: u64,
+    status: TrackStatus,
+}
+
+/// Progress bookkeeping, independent of the clock and the UI
+#[derive(Debug)]
+struct Tracker {
+    titles: Vec<String>,
+    files: HashMap<String, FileState>,
+    /// Files downloaded at once (eg. the archive of a purchase), by the paths of the files they
+    /// hold
+    bundles: HashMap<String, Vec<String>>,
+    /// (time, total bytes done) samples used to compute the throughput
+    samples: VecDeque<(Instant, u64)>,
+    last_emit: Option<Instant>,
+}
+
+impl Tracker {
+    /// Duration over which the throughput is averaged
+    const WINDOW: Duration = Duration::from_secs(10);
+
+    fn new(albums: &[Album]) -> Self {
+        let titles = albums.iter().map(|album| album.title.clone()).collect();
+        let files = albums
+            .iter()
+            .enumerate()
+            .flat_map(|(i, album)| {
+                album.selected_tracks().map(move |track| {
+                    let state = FileState {
+                        album: i,
+                        complete: 0,
+                        total: track.size.unwrap_or(0),
+                        percent: 0,
+                        status: TrackStatus::Queued,
+                    };
+                    (track.path.clone(), state)
+                })
+            })
+            .collect();
+
+        Self {
+            titles,
+            files,
+            bundles: HashMap::new(),
+            samples: VecDeque::new(),
+            last_emit: None,
+        }
+    }
+
+    fn bytes_done(&self) -> u64 {
+        self.files.values().map(|file| file.complete).sum()
+    }
+
+    /// Record a throughput sample, discarding the ones outside of the window
+    fn sample(&mut self, now: Instant) {
+        let bytes_done = self.bytes_done();
+        self.samples.push_back((now, bytes_done));
+        while let Some((time, _)) = self.samples.front() {
+            if now.duration_since(*time) > Self::WINDOW {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Update the bytes downloaded for a file, or for the files of a bundle in equal shares.
+    /// Returns the progress of the files whose whole completion percentage changed.
+    fn update(&mut self, path: &str, complete: u64, total: u64, now: Instant) -> Vec<Progress> {
+        let files = match self.bundles.get(path) {
+            Some(files) => files.clone(),
+            None => vec![path.to_string()],
+        };
+        let shares = helper::split_bytes(complete, files.len())
+            .into_iter()
+            .zip(helper::split_bytes(total, files.len()));
+        let changed = files
+            .into_iter()
+            .zip(shares)
+            .filter(|(path, (complete, total))| self.update_file(path, *complete, *total))
+            .map(|(path, (complete, total))| Progress {
+                path,
+                complete,
+                total,
+            })
+            .collect();
+        self.sample(now);
+
+        changed
+    }
+
+    /// Update the bytes downloaded for a file. Returns true if its whole completion percentage
+    /// changed.
+    fn update_file(&mut self, path: &str, complete: u64, total: u64) -> bool {
+        match self.files.get_mut(path) {
+            Some(file) => {
+                file.complete = complete;
+                if total > 0 {
+                    file.total = total;
+                }
+                let percent = (complete * 100).checked_div(file.total).unwrap_or(0);
+                let changed = percent != file.percent;
+                file.percent = percent;
+                changed
+            }
+            None => false,
+        }
+    }
+
+    /// Update the processing state of a file
+    fn set_status(&mut self, path: &str, status: TrackStatus, now: Instant) {
+        if let Some(file) = self.files.get_mut(path) {
+            match status {
+                TrackStatus::Tagging | TrackStatus::Transcoding | TrackStatus::Done => {
+                    file.complete = file.total.max(file.complete);
+                    file.total = file.complete;
+                }
+                // a failed file will not download its remaining bytes
+                TrackStatus::Failed => file.total = file.complete,
+                // nothing to download for a file already on disk
+                TrackStatus::Skipped => {
+                    file.complete = 0;
+                    file.total = 0;
+                }
+                // a queued file is downloaded again from the start
+                TrackStatus::Queued => {
+                    file.complete = 0;
+                    file.percent = 0;
+                }
+                TrackStatus::Downloading => {}
+            }
+            file.status = status;
+        }
+        self.sample(now);
+    }
+
+    /// Download speed (in bytes per second) over the sampling window
+    fn throughput(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((start, start_bytes)), Some((end, end_bytes))) if end > start => {
+                end_bytes.saturating_sub(*start_bytes) as f64
+                    / end.duration_since(*start).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn stats(&self) -> DownloadStats {
+        let mut albums: Vec<_> = self
+            .titles
+            .iter()
+            .map(|title| AlbumProgress {
+                title: title.clone(),
+                count: ProgressCount::default(),
+            })
+            .collect();
+
+        for file in self.files.values() {
+            let count = &mut albums[file.album].count;
+            count.bytes_done += file.complete;
+            count.bytes_total += file.total.max(file.complete);
+            count.tracks_total += 1;
+            match file.status {
+                TrackStatus::Tagging
+                | TrackStatus::Transcoding
+                | TrackStatus::Done
+                | TrackStatus::Skipped => count.tracks_done += 1,
+                TrackStatus::Failed => count.tracks_failed += 1,
+                TrackStatus::Queued | TrackStatus::Downloading => {}
+            }
+        }
+
+        let total = albums
+            .iter()
+            .fold(ProgressCount::default(), |mut total, album| {
+                total.bytes_done += album.count.bytes_done;
+                total.bytes_total += album.count.bytes_total;
+                total.tracks_done += album.count.tracks_done;
+                total.tracks_failed += album.count.tracks_failed;
+                total.tracks_total += album.count.tracks_total;
+                total
+            });
+
+        let throughput = self.throughput();
+        let eta = if throughput > 0.0 {
+            let remaining = total.bytes_total.saturating_sub(total.bytes_done);
+            Some(Duration::from_secs_f64(remaining as f64 / throughput))
+        } else {
+            None
+        };
+
+        DownloadStats {
+            albums,
+            total,
+            throughput,
+            eta,
+        }
+    }
+}
+
+/// Shared download progress tracker for a run of album downloads.
+///
+/// Download tasks report their progress to the tracker, which forwards per file updates and
+/// periodic [`DownloadStats`] snapshots to the UI.
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    inner: Arc<Mutex<Tracker>>,
+    logger: Logger,
+}
+
+impl ProgressTracker {
+    /// Minimum delay between two [`DownloadStats`] updates sent to the UI
+    const EMIT_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Create a tracker for the selected tracks of the supplied albums
+    pub fn new(albums: &[Album], logger: Logger) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Tracker::new(albums))),
+            logger,
+        }
+    }
+
+    /// Returns a snapshot of the current progress
+    pub fn stats(&self) -> DownloadStats {
+        self.inner
+            .lock()
+            .map(|tracker| tracker.stats())
+            .unwrap_or_default()
+    }
+
+    /// Send the current progress to the UI
+    pub fn emit(&self) {
+        self.logger.send(Message::DownloadStats(self.stats()));
+    }
+
+    /// Report the bytes downloaded for the file at `path` (eg. the archive of a purchase) as
+    /// downloaded for the `files` it holds, in equal shares
+    pub fn bundle(&self, path: &str, files: Vec<String>) {
+        if let Ok(mut tracker) = self.inner.lock() {
+            tracker.bundles.insert(path.to_string(), files);
+        }
+    }
+
+    /// Update the number of bytes downloaded for the file at `path`
+    pub fn update(&self, path: &str, complete: u64, total: u64) {
+        let now = Instant::now();
+        let (changed, emit_stats) = match self.inner.lock() {
+            Ok(mut tracker) => {
+                let changed = tracker.update(path, complete, total, now);
+                let emit_stats = match tracker.last_emit {
+                    Some(last) => now.duration_since(last) >= Self::EMIT_INTERVAL,
+                    None => true,
+                };
+                if emit_stats {
+                    tracker.last_emit = Some(now);
+                }
+                (changed, emit_stats)
+            }
+            Err(_) => return,
+        };
+
+        // Only report whole percent changes to avoid flooding the UI channel
+        for progress in changed {
+            self.logger.send(Message::Progress(progress));
+        }
+        if emit_stats {
+            self.emit();
+        }
+    }
+
+    /// Returns the processing state of the file at `path`
+    pub fn status(&self, path: &str) -> Option<TrackStatus> {
+        let tracker = self.inner.lock().ok()?;
+        tracker.files.get(path).map(|file| file.status)
+    }
+
+    /// Update the processing state of the file at `path`
+    pub fn set_status(&self, path: &str, status: TrackStatus) {
+        if let Ok(mut tracker) = self.inner.lock() {
+            tracker.set_status(path, status, Instant::now());
+        }
+        self.logger
+            .send(Message::TrackStatus(path.to_string(), status));
+        self.emit();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helper::test_helper::test_album;
+
+    /// Returns an album saved in the `title` folder, with one track per size
+    fn album(title: &str, sizes: &[Option<u64>]) -> Album {
+        let titles = vec!["Pit Stop"; sizes.len()];
+        let mut album = test_album(&format!("/music/{}", title), &titles, "{tracknum}.mp3");
+        album.title = title.to_string();
+        for (track, size) in album.tracks.iter_mut().zip(sizes) {
+            track.size = *size;
+        }
+        album
+    }
+
+    #[test]
+    fn aggregates_albums() {
+        let albums = vec![
+            album("First", &[Some(100), Some(100)]),
+            album("Second", &[None]),
+        ];
+        let now = Instant::now();
+        let mut tracker = Tracker::new(&albums);
+
+        tracker.update("/music/First/01.mp3", 50, 100, now);
+        tracker.set_status("/music/First/02.mp3", TrackStatus::Done, now);
+        tracker.update("/music/Second/01.mp3", 10, 40, now);
+
+        let stats = tracker.stats();
+
+        let msg = "should count bytes and tracks per album";
+        let expected = ProgressCount {
+            bytes_done: 150,
+            bytes_total: 200,
+            tracks_done: 1,
+            tracks_failed: 0,
+            tracks_total: 2,
+        };
+        assert_eq!(stats.albums[0].count, expected, "{}", msg);
+
+        let msg = "should use the size reported by the download if it was not known beforehand";
+        assert_eq!(stats.albums[1].count.bytes_total, 40, "{}", msg);
+
+        let msg = "should sum the albums counters";
+        let expected = ProgressCount {
+            bytes_done: 160,
+            bytes_total: 240,
+            tracks_done: 1,
+            tracks_failed: 0,
+            tracks_total: 3,
+        };
+        assert_eq!(stats.total, expected, "{}", msg);
+    }
+
+    #[test]
+    fn excludes_failed_tracks_remaining_bytes() {
+        let albums = vec![album("First", &[Some(100), Some(100)])];
+        let now = Instant::now();
+        let mut tracker = Tracker::new(&albums);
+
+        tracker.update("/music/First/01.mp3", 30, 100, now);
+        tracker.set_status("/music/First/01.mp3", TrackStatus::Failed, now);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.total.tracks_failed, 1);
+        assert_eq!(stats.total.bytes_total, 130);
+    }
+
+    #[test]
+    fn excludes_skipped_tracks_bytes() {
+        let albums = vec![album("First", &[Some(100), Some(100)])];
+        let now = Instant::now();
+        let mut tracker = Tracker::new(&albums);
+
+        tracker.set_status("/music/First/01.mp3", TrackStatus::Skipped, now);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.total.tracks_done, 1);
+        assert_eq!(stats.total.bytes_total, 100);
+    }
+
+    #[test]
+    fn spreads_bundle_bytes() {
+        let albums = vec![album("First", &[None, None])];
+        let now = Instant::now();
+        let mut tracker = Tracker::new(&albums);
+        let files = vec![
+            String::from("/music/First/01.mp3"),
+            String::from("/music/First/02.mp3"),
+        ];
+        tracker
+            .bundles
+            .insert(String::from("/music/First.zip"), files);
+
+        let msg = "should report the bytes of the bundle for the files it holds";
+        let changed = tracker.update("/music/First.zip", 50, 200, now);
+        let expected = vec![
+            Progress {
+                path: String::from("/music/First/01.mp3"),
+                complete: 25,
+                total: 100,
+            },
+            Progress {
+                path: String::from("/music/First/02.mp3"),
+                complete: 25,
+                total: 100,
+            },
+        ];
+        assert_eq!(changed, expected, "{}", msg);
+
+        let msg = "should count the bytes of the bundle in the album progress";
+        let stats = tracker.stats();
+        assert_eq!(stats.total.bytes_done, 50, "{}", msg);
+        assert_eq!(stats.total.bytes_total, 200, "{}", msg);
+        assert_eq!(stats.total.tracks_total, 2, "{}", msg);
+    }
+
+    #[test]
+    fn requeues_failed_tracks() {
+        let albums = vec![album("First", &[Some(100)])];
+        let now = Instant::now();
+        let mut tracker = Tracker::new(&albums);
+        let path = "/music/First/01.mp3";
+
+        tracker.update(path, 30, 100, now);
+        tracker.set_status(path, TrackStatus::Failed, now);
+        tracker.set_status(path, TrackStatus::Queued, now);
+        tracker.update(path, 10, 100, now);
+
+        let stats = tracker.stats();
+        let msg = "should no longer count the track as failed";
+        assert_eq!(stats.total.tracks_failed, 0, "{}", msg);
+        let msg = "should count the bytes downloaded again from the start";
+        assert_eq!(stats.total.bytes_done, 10, "{}", msg);
+        assert_eq!(stats.total.bytes_total, 100, "{}", msg);
+    }
+
+    #[test]
+    fn computes_throughput_over_window() {
+        let albums = vec![album("First", &[Some(10_000)])];
+        let start = Instant::now();
+        let mut tracker = Tracker::new(&albums);
+        let path = "/music/First/01.mp3";
+
+        let msg = "should not estimate time remaining before any progress";
+        tracker.update(path, 0, 10_000, start);
+        assert_eq!(tracker.stats().eta, None, "{}", msg);
+
+        let msg = "should average the download speed";
+        tracker.update(path, 1_000, 10_000, start + Duration::from_secs(1));
+        tracker.update(path, 2_000, 10_000, start + Duration::from_secs(2));
+        let stats = tracker.stats();
+        assert!((stats.throughput - 1_000.0).abs() < f64::EPSILON, "{}", msg);
+
+        let msg = "should estimate the time remaining from the download speed";
+        assert_eq!(stats.eta, Some(Duration::from_secs(8)), "{}", msg);
+
+        let msg = "should ignore samples older than the window";
+        tracker.update(path, 7_000, 10_000, start + Duration::from_secs(11));
+        let stats = tracker.stats();
+        assert!((stats.throughput - 600.0).abs() < f64::EPSILON, "{}", msg);
+    }
+}
@@ -0,0 +1,577 @@
+//! Reorganisation of the albums already downloaded, after the folder or file name formats changed
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    core::{dedup, playlist, sidecar, DuplicateAction, Logger, Transcoding},
+    error::Error,
+    model::Album,
+    settings::{PlaylistFormat, UserSettings},
+    Result,
+};
+
+/// Move of a file of a reorganised album
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Moves saving an album described by its `album.json` sidecar to the paths of the current
+/// folder and file name formats
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reorganisation {
+    /// The album at its new location
+    pub album: Album,
+    /// Folder the album is currently saved to
+    pub folder: PathBuf,
+    /// Files of the folder to move, the files of the tracks first
+    pub moves: Vec<FileMove>,
+    /// Playlist written along with the album to the current folder, listing the previous paths
+    /// of the tracks
+    playlist: Option<PathBuf>,
+    downloaded_at: Option<DateTime<Utc>>,
+}
+
+/// Returns `dir` and its subfolders holding an album sidecar, sorted by path
+fn sidecar_folders(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut folders = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(path) = pending.pop() {
+        for entry in fs::read_dir(&path)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                pending.push(entry_path);
+            }
+        }
+        if path.join(sidecar::JSON_FILE_NAME).is_file() {
+            folders.push(path);
+        }
+    }
+    folders.sort();
+
+    Ok(folders)
+}
+
+/// Move the file at `from` to `to`, copying it if they are on different file systems
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+
+    Ok(())
+}
+
+impl Reorganisation {
+    /// Plan the reorganisation of the album saved to `folder` with the formats of the `settings`.
+    /// The files are looked up in `folder` rather than at their stored paths, in case the folder
+    /// was moved by hand. Every file of the folder is moved: the files of a track, whatever their
+    /// extension (eg. its transcoded file and its original), take its new name and the other
+    /// files (eg. the playlists made by the user) keep theirs. The playlist written along with the
+    /// album is written again instead. The skipped duplicates, saved as the file they duplicate in
+    /// the folder of another album, are left where they are.
+    pub fn new(folder: &Path, settings: &UserSettings) -> Result<Self> {
+        let (mut album, downloaded_at) = sidecar::read_json(folder)?;
+        let skipped: Vec<_> = album
+            .tracks
+            .iter()
+            .map(|track| {
+                (track.duplicate_of.as_ref() == Some(&track.path)).then(|| track.path.clone())
+            })
+            .collect();
+        let stems: Vec<_> = album
+            .tracks
+            .iter()
+            .zip(&skipped)
+            .map(|(track, skipped)| match skipped {
+                Some(_) => None,
+                None => Path::new(&track.path).file_stem().map(OsStr::to_os_string),
+            })
+            .collect();
+        let playlist = folder.join(
+            Path::new(&album.playlist_path)
+                .file_name()
+                .unwrap_or_default(),
+        );
+        let playlist = Some(playlist).filter(|playlist| playlist.is_file());
+        album.relocate(
+            &settings.downloads_path.to_string_lossy(),
+            &settings.file_name_format,
+        );
+        if playlist.is_some() || settings.create_playlist {
+            let path = playlist::playlist_path(
+                settings.playlist_format,
+                &album,
+                &settings.playlist_file_name_format,
+            );
+            album.playlist_path = path.to_string_lossy().into();
+        }
+        for (track, skipped) in album.tracks.iter_mut().zip(skipped) {
+            if let Some(path) = skipped {
+                track.path = path;
+            }
+        }
+        let new_folder = PathBuf::from(&album.path);
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(folder)? {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        files.sort();
+
+        let mut moves = Vec::new();
+        let mut other_moves = Vec::new();
+        for path in files {
+            let file_name = path.file_name().unwrap_or_default();
+            if file_name == sidecar::JSON_FILE_NAME || playlist.as_ref() == Some(&path) {
+                continue;
+            }
+
+            let track = stems
+                .iter()
+                .position(|stem| stem.is_some() && stem.as_deref() == path.file_stem());
+            match track {
+                Some(i) => {
+                    let extension = path.extension().unwrap_or_default();
+                    let to = Path::new(&album.tracks[i].path).with_extension(extension);
+                    moves.push(FileMove { from: path, to });
+                }
+                None => {
+                    let to = new_folder.join(file_name);
+                    other_moves.push(FileMove { from: path, to });
+                }
+            }
+        }
+        moves.append(&mut other_moves);
+        moves.retain(|file| file.from != file.to);
+
+        Ok(Self {
+            album,
+            folder: folder.to_path_buf(),
+            moves,
+            playlist,
+            downloaded_at,
+        })
+    }
+
+    /// True if the album is already saved to the paths of the current formats
+    pub fn is_unchanged(&self) -> bool {
+        self.moves.is_empty() && self.folder == Path::new(&self.album.path)
+    }
+
+    /// Move the files of the album, then write its sidecar again with their new paths, along with
+    /// its playlist if it had one or `settings` create one. Nothing is moved if a destination file
+    /// already exists or two files would be moved to the same path, and the files already moved
+    /// are moved back if a move or the sidecar fails.
+    pub fn apply(&self, settings: &UserSettings) -> Result<()> {
+        let mut destinations = HashSet::new();
+        if let Some(file) = self
+            .moves
+            .iter()
+            .find(|file| file.to.exists() || !destinations.insert(&file.to))
+        {
+            return Err(Error::Io(format!(
+                "{} already exists",
+                file.to.to_string_lossy()
+            )));
+        }
+
+        fs::create_dir_all(&self.album.path)?;
+        let written = self.move_files().and_then(|_| {
+            sidecar::write_json(&self.album, self.downloaded_at.unwrap_or_else(Utc::now))
+        });
+        if let Err(e) = written {
+            self.restore_files();
+            return Err(e);
+        }
+
+        if let Some(path) = &self.playlist {
+            fs::remove_file(path)?;
+        }
+        if self.playlist.is_some() || settings.create_playlist {
+            let path = PathBuf::from(&self.album.playlist_path);
+            playlist::write_playlist(settings.playlist_format, &self.album, path)?;
+        }
+
+        if self.folder != Path::new(&self.album.path) {
+            fs::remove_file(self.folder.join(sidecar::JSON_FILE_NAME))?;
+            // only an empty folder is removed
+            let _ = fs::remove_dir(&self.folder);
+        }
+
+        Ok(())
+    }
+
+    /// Move the files of the album to their new paths
+    fn move_files(&self) -> Result<()> {
+        for file in &self.moves {
+            move_file(&file.from, &file.to)?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the files already moved back to their previous paths, as much as possible
+    fn restore_files(&self) {
+        for file in self.moves.iter().rev() {
+            if file.to.exists() && !file.from.exists() {
+                let _ = move_file(&file.to, &file.from);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Reorganisation {
+    /// Writes the album folders and the moved file names
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file_name = |path: &Path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+
+        writeln!(f, "{} - {}", self.album.artist, self.album.title)?;
+        write!(f, "  {} -> {}", self.folder.display(), self.album.path)?;
+        for file in &self.moves {
+            write!(
+                f,
+                "\n    {} -> {}",
+                file_name(&file.from),
+                file_name(&file.to)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Plan the reorganisation of the albums saved in `dir` and its subfolders, skipping the ones
+/// already saved to the paths of the current formats
+pub fn plan(dir: &Path, settings: &UserSettings, logger: &Logger) -> Result<Vec<Reorganisation>> {
+    let mut plan = Vec::new();
+    for folder in sidecar_folders(dir)? {
+        match Reorganisation::new(&folder, settings) {
+            Ok(reorganisation) if reorganisation.is_unchanged() => {}
+            Ok(reorganisation) => plan.push(reorganisation),
+            Err(e) => logger.warn(format!(
+                "Unable to read the album of {}: {}",
+                folder.display(),
+                e
+            )),
+        }
+    }
+
+    Ok(plan)
+}
+
+/// Point the duplicates of the album saved to `folder` to the new paths of the `moved` files they
+/// duplicate, writing its sidecar and playlist again and linking its symbolic links again. Returns
+/// false if nothing changed.
+fn update_album_duplicates(
+    folder: &Path,
+    moved: &HashMap<String, String>,
+    transcoding: &Transcoding,
+) -> Result<bool> {
+    let (mut album, downloaded_at) = sidecar::read_json(folder)?;
+    let mut changed = false;
+    for track in album.tracks.iter_mut() {
+        let new_path = match track.duplicate_of.as_ref().and_then(|path| moved.get(path)) {
+            Some(new_path) => new_path.clone(),
+            None => continue,
+        };
+        changed = true;
+
+        // a skipped duplicate is saved as the file it duplicates
+        if track.duplicate_of.as_ref() == Some(&track.path) {
+            track.path = new_path.clone();
+            track.duplicate_of = Some(new_path);
+            continue;
+        }
+        let path = dedup::output_path(track, transcoding);
+        let is_symlink = fs::symlink_metadata(&path)
+            .map(|metadata| metadata.file_type().is_symlink())
+            .unwrap_or(false);
+        track.duplicate_of = Some(new_path);
+        if is_symlink {
+            fs::remove_file(&path)?;
+            dedup::link(DuplicateAction::Symlink, track, transcoding)?;
+        }
+    }
+    if !changed {
+        return Ok(false);
+    }
+
+    sidecar::write_json(&album, downloaded_at.unwrap_or_else(Utc::now))?;
+    let playlist = PathBuf::from(&album.playlist_path);
+    let format = PlaylistFormat::ALL
+        .iter()
+        .find(|format| playlist.extension() == Some(OsStr::new(format.value())));
+    if let (true, Some(format)) = (playlist.is_file(), format) {
+        playlist::write_playlist(*format, &album, playlist)?;
+    }
+
+    Ok(true)
+}
+
+/// Point the duplicates of the albums saved in `dir` and its subfolders to the new paths of the
+/// `moved` files they duplicate, by previous path, eg. once the albums of these files were
+/// reorganised. Returns the number of albums updated.
+pub fn update_duplicates(
+    dir: &Path,
+    moved: &HashMap<String, String>,
+    transcoding: &Transcoding,
+    logger: &Logger,
+) -> Result<usize> {
+    let mut count = 0;
+    for folder in sidecar_folders(dir)? {
+        match update_album_duplicates(&folder, moved, transcoding) {
+            Ok(true) => count += 1,
+            Ok(false) => {}
+            Err(e) => logger.warn(format!(
+                "Unable to update the duplicates of {}: {}",
+                folder.display(),
+                e
+            )),
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::helper::test_helper::{test_album, test_dir, test_logger};
+
+    #[test]
+    fn reorganises_albums() {
+        let dir = test_dir("reorganises_albums");
+        let old_format = dir.join("{artist} - {album}");
+        let mut album = test_album(&old_format.to_string_lossy(), &["Pit Stop"], "{title}.flac");
+        album.tracks[0].number = 2;
+        let old_folder = PathBuf::from(&album.path);
+        fs::create_dir_all(&old_folder).unwrap();
+        fs::write(&album.tracks[0].path, b"flac").unwrap();
+        // the original of the transcoded track, and a file unknown to the album
+        fs::write(old_folder.join("Pit Stop.mp3"), b"mp3").unwrap();
+        fs::write(old_folder.join("cover.jpg"), b"jpg").unwrap();
+        fs::write(old_folder.join("notes.txt"), b"txt").unwrap();
+        fs::write(old_folder.join("Night Drive.m3u"), b"").unwrap();
+        fs::write(old_folder.join("favourites.m3u"), b"Pit Stop.flac").unwrap();
+        album.playlist_path = old_folder.join("Night Drive.m3u").to_string_lossy().into();
+        sidecar::write_json(&album, Utc::now()).unwrap();
+
+        let logger = test_logger();
+        let settings = UserSettings {
+            downloads_path: dir.join("{artist}").join("{album}"),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            create_playlist: true,
+            playlist_format: PlaylistFormat::M3U,
+            playlist_file_name_format: String::from("{album}"),
+            ..UserSettings::default()
+        };
+
+        let msg = "should move every file to the paths of the current formats";
+        let plan = plan(&dir, &settings, &logger).unwrap();
+        assert_eq!(plan.len(), 1, "{}", msg);
+        let new_folder = dir.join("The Racers").join("Night Drive");
+        let expected = vec![
+            FileMove {
+                from: old_folder.join("Pit Stop.flac"),
+                to: new_folder.join("02 - Pit Stop.flac"),
+            },
+            FileMove {
+                from: old_folder.join("Pit Stop.mp3"),
+                to: new_folder.join("02 - Pit Stop.mp3"),
+            },
+            FileMove {
+                from: old_folder.join("cover.jpg"),
+                to: new_folder.join("cover.jpg"),
+            },
+            FileMove {
+                from: old_folder.join("favourites.m3u"),
+                to: new_folder.join("favourites.m3u"),
+            },
+            FileMove {
+                from: old_folder.join("notes.txt"),
+                to: new_folder.join("notes.txt"),
+            },
+        ];
+        assert_eq!(plan[0].moves, expected, "{}", msg);
+
+        let msg = "should not move anything before being applied";
+        assert!(old_folder.join("Pit Stop.flac").is_file(), "{}", msg);
+
+        let msg = "should move the files and write the sidecar and playlist again";
+        plan[0].apply(&settings).unwrap();
+        assert!(new_folder.join("02 - Pit Stop.flac").is_file(), "{}", msg);
+        assert!(new_folder.join("cover.jpg").is_file(), "{}", msg);
+        assert!(new_folder.join("notes.txt").is_file(), "{}", msg);
+        assert!(!old_folder.exists(), "{}", msg);
+        let playlist = fs::read_to_string(new_folder.join("Night Drive.m3u")).unwrap();
+        assert!(playlist.contains("02 - Pit Stop.flac"), "{}", msg);
+        let favourites = fs::read_to_string(new_folder.join("favourites.m3u")).unwrap();
+        assert_eq!(favourites, "Pit Stop.flac", "{}", msg);
+        let (moved, _) = sidecar::read_json(&new_folder).unwrap();
+        assert_eq!(moved, plan[0].album, "{}", msg);
+
+        let msg = "should skip the albums already reorganised";
+        let plan = super::plan(&dir, &settings, &logger).unwrap();
+        assert!(plan.is_empty(), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_skipped_duplicates() {
+        let dir = test_dir("keeps_skipped_duplicates");
+        let old_format = dir.join("{album}");
+        let titles = ["Warm Up", "Pit Stop"];
+        let mut album = test_album(&old_format.to_string_lossy(), &titles, "{title}.mp3");
+        // the second track was saved from the EP of the album
+        let original = dir.join("EP").join("Pit Stop.mp3");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, b"ep").unwrap();
+        album.tracks[1].path = original.to_string_lossy().into();
+        album.tracks[1].duplicate_of = Some(album.tracks[1].path.clone());
+        let old_folder = PathBuf::from(&album.path);
+        fs::create_dir_all(&old_folder).unwrap();
+        fs::write(&album.tracks[0].path, b"album").unwrap();
+        sidecar::write_json(&album, Utc::now()).unwrap();
+
+        let settings = UserSettings {
+            downloads_path: dir.join("{artist}").join("{album}"),
+            file_name_format: String::from("{title}.mp3"),
+            create_playlist: false,
+            ..UserSettings::default()
+        };
+
+        let msg = "should only move the files saved to the album folder";
+        let reorganisation = Reorganisation::new(&old_folder, &settings).unwrap();
+        let new_folder = dir.join("The Racers").join("Night Drive");
+        let expected = vec![FileMove {
+            from: old_folder.join("Warm Up.mp3"),
+            to: new_folder.join("Warm Up.mp3"),
+        }];
+        assert_eq!(reorganisation.moves, expected, "{}", msg);
+
+        let msg = "should keep the path of the skipped duplicates";
+        reorganisation.apply(&settings).unwrap();
+        assert_eq!(fs::read(&original).unwrap(), b"ep", "{}", msg);
+        let (moved, _) = sidecar::read_json(&new_folder).unwrap();
+        assert_eq!(moved.tracks[1].path, original.to_string_lossy(), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn moves_back_on_failure() {
+        let dir = test_dir("moves_back_on_failure");
+        let old_format = dir.join("{album}");
+        let titles = ["Warm Up", "Pit Stop"];
+        let album = test_album(&old_format.to_string_lossy(), &titles, "{title}.mp3");
+        let old_folder = PathBuf::from(&album.path);
+        fs::create_dir_all(&old_folder).unwrap();
+        for track in &album.tracks {
+            fs::write(&track.path, b"mp3").unwrap();
+        }
+        sidecar::write_json(&album, Utc::now()).unwrap();
+
+        let msg = "should not move two files to the same path";
+        let settings = UserSettings {
+            downloads_path: dir.join("{artist}").join("{album}"),
+            file_name_format: String::from("{album}.mp3"),
+            create_playlist: false,
+            ..UserSettings::default()
+        };
+        let reorganisation = Reorganisation::new(&old_folder, &settings).unwrap();
+        assert!(reorganisation.apply(&settings).is_err(), "{}", msg);
+        assert!(old_folder.join("Warm Up.mp3").is_file(), "{}", msg);
+        assert!(old_folder.join("Pit Stop.mp3").is_file(), "{}", msg);
+
+        let msg = "should move the files back if a move fails";
+        let settings = UserSettings {
+            file_name_format: String::from("{title}.mp3"),
+            ..settings
+        };
+        let reorganisation = Reorganisation::new(&old_folder, &settings).unwrap();
+        // the files are planned in order of their names
+        fs::remove_file(old_folder.join("Warm Up.mp3")).unwrap();
+        assert!(reorganisation.apply(&settings).is_err(), "{}", msg);
+        assert!(old_folder.join("Pit Stop.mp3").is_file(), "{}", msg);
+        let new_folder = dir.join("The Racers").join("Night Drive");
+        assert!(!new_folder.join("Pit Stop.mp3").exists(), "{}", msg);
+        let (album, _) = sidecar::read_json(&old_folder).unwrap();
+        assert_eq!(album.path, old_folder.to_string_lossy(), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn updates_moved_duplicates() {
+        let dir = test_dir("updates_moved_duplicates");
+        let folder_format = dir.join("{album}");
+        let titles = ["Warm Up", "Pit Stop"];
+        let mut album = test_album(&folder_format.to_string_lossy(), &titles, "{title}.mp3");
+        // both tracks were saved from the EP of the album, the first one linked to its file and
+        // the second one skipped
+        let ep_folder = dir.join("EP");
+        let new_folder = dir.join("The Racers").join("EP");
+        fs::create_dir_all(&new_folder).unwrap();
+        let mut moved = HashMap::new();
+        for title in &["Warm Up", "Pit Stop"] {
+            let file_name = format!("{}.mp3", title);
+            fs::write(new_folder.join(&file_name), b"ep").unwrap();
+            let from = ep_folder.join(&file_name).to_string_lossy().to_string();
+            moved.insert(
+                from,
+                new_folder.join(&file_name).to_string_lossy().to_string(),
+            );
+        }
+        let old_path = |file_name: &str| ep_folder.join(file_name).to_string_lossy().to_string();
+        album.tracks[0].duplicate_of = Some(old_path("Warm Up.mp3"));
+        album.tracks[1].path = old_path("Pit Stop.mp3");
+        album.tracks[1].duplicate_of = Some(album.tracks[1].path.clone());
+        let folder = PathBuf::from(&album.path);
+        fs::create_dir_all(&folder).unwrap();
+        std::os::unix::fs::symlink(old_path("Warm Up.mp3"), &album.tracks[0].path).unwrap();
+        let playlist = folder.join("Night Drive.m3u");
+        album.playlist_path = playlist.to_string_lossy().into();
+        playlist::write_playlist(PlaylistFormat::M3U, &album, playlist.clone()).unwrap();
+        sidecar::write_json(&album, Utc::now()).unwrap();
+
+        let msg = "should point the duplicates to the moved files";
+        let count =
+            update_duplicates(&dir, &moved, &Transcoding::default(), &test_logger()).unwrap();
+        assert_eq!(count, 1, "{}", msg);
+        let (updated, _) = sidecar::read_json(&folder).unwrap();
+        let new_path = |file_name: &str| new_folder.join(file_name).to_string_lossy().to_string();
+        let expected = Some(new_path("Warm Up.mp3"));
+        assert_eq!(updated.tracks[0].duplicate_of, expected, "{}", msg);
+        assert_eq!(updated.tracks[1].path, new_path("Pit Stop.mp3"), "{}", msg);
+        let expected = Some(new_path("Pit Stop.mp3"));
+        assert_eq!(updated.tracks[1].duplicate_of, expected, "{}", msg);
+
+        let msg = "should link the duplicates and list them in the playlist again";
+        assert_eq!(fs::read(&album.tracks[0].path).unwrap(), b"ep", "{}", msg);
+        let playlist = fs::read_to_string(&playlist).unwrap();
+        assert!(playlist.contains(&new_path("Pit Stop.mp3")), "{}", msg);
+        assert!(!playlist.contains(&old_path("Pit Stop.mp3")), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,135 @@
+//! Bandcamp login session, authenticating the requests to the pages of the user account
+use std::fs;
+
+use reqwest::{header::COOKIE, RequestBuilder};
+
+use crate::{error::Error, settings::UserSettings, Result};
+
+/// Cookies of a logged in Bandcamp user
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    /// Value of the `Cookie` header sent along the requests
+    cookie: String,
+}
+
+impl Session {
+    /// Name of the Bandcamp cookie identifying the user
+    const IDENTITY: &'static str = "identity";
+
+    /// Returns the session set in `settings`: the identity cookie if any, otherwise the Bandcamp
+    /// cookies of the cookies file. Returns None if neither is set.
+    pub fn from_settings(settings: &UserSettings) -> Result<Option<Self>> {
+        let identity = settings.identity_cookie.trim();
+        if !identity.is_empty() {
+            // accept both the bare cookie value and the `identity=value` pair
+            let cookie = if identity.starts_with(&format!("{}=", Self::IDENTITY)) {
+                identity.to_string()
+            } else {
+                format!("{}={}", Self::IDENTITY, identity)
+            };
+            return Ok(Some(Self { cookie }));
+        }
+
+        let path = settings.cookies_file.trim();
+        if path.is_empty() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)?;
+        Self::from_cookies_txt(&contents)
+            .map(Some)
+            .ok_or_else(|| Error::Auth(format!("no Bandcamp cookie found in {}", path)))
+    }
+
+    /// Returns the session holding the Bandcamp cookies of a cookies.txt (Netscape format) file,
+    /// or None if it has no Bandcamp identity cookie
+    fn from_cookies_txt(contents: &str) -> Option<Self> {
+        let cookies: Vec<_> = contents
+            .lines()
+            // http only cookies are prefixed, like comments
+            .map(|line| line.strip_prefix("#HttpOnly_").unwrap_or(line))
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let fields: Vec<_> = line.split('\t').collect();
+                match fields[..] {
+                    [domain, _, _, _, _, name, value] if domain.ends_with("bandcamp.com") => {
+                        Some((name.trim(), value.trim()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        if !cookies.iter().any(|(name, _)| *name == Self::IDENTITY) {
+            return None;
+        }
+
+        let cookie = cookies
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        Some(Self { cookie })
+    }
+
+    /// Add the session cookies to `request`
+    pub fn authenticate(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header(COOKIE, &self.cookie)
+    }
+
+    /// Get text from a url using the shared client, sending the session cookies
+    pub async fn get_url_text(&self, url: &str) -> Result<String> {
+        self.authenticate(crate::client().get(url))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+            .map_err(From::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_identity_cookie() {
+        let msg = "should not have a session without cookie";
+        let settings = UserSettings::default();
+        assert_eq!(Session::from_settings(&settings).unwrap(), None, "{}", msg);
+
+        let msg = "should name the bare identity cookie value";
+        let settings = UserSettings {
+            identity_cookie: String::from(" 7%09abc%7B%7D "),
+            ..UserSettings::default()
+        };
+        let expected = Session {
+            cookie: String::from("identity=7%09abc%7B%7D"),
+        };
+        let actual = Session::from_settings(&settings).unwrap();
+        assert_eq!(actual, Some(expected), "{}", msg);
+    }
+
+    #[test]
+    fn reads_cookies_txt() {
+        let contents = "# Netscape HTTP Cookie File
+.bandcamp.com\tTRUE\t/\tTRUE\t1700000000\tclient_id\tC1
+#HttpOnly_.bandcamp.com\tTRUE\t/\tTRUE\t1700000000\tidentity\t7%09abc
+.example.com\tTRUE\t/\tFALSE\t1700000000\tidentity\tother
+";
+
+        let msg = "should keep the Bandcamp cookies";
+        let expected = Session {
+            cookie: String::from("client_id=C1; identity=7%09abc"),
+        };
+        assert_eq!(
+            Session::from_cookies_txt(contents),
+            Some(expected),
+            "{}",
+            msg
+        );
+
+        let msg = "should require the identity cookie";
+        let contents = ".bandcamp.com\tTRUE\t/\tTRUE\t1700000000\tclient_id\tC1";
+        assert_eq!(Session::from_cookies_txt(contents), None, "{}", msg);
+    }
+}
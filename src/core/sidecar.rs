@@ -0,0 +1,211 @@
+//! Album metadata sidecar files, letting library tools index the releases without scraping them
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{model::Album, Result};
+
+/// Name of the JSON sidecar file in the album folder
+pub const JSON_FILE_NAME: &str = "album.json";
+/// Name of the NFO sidecar file in the album folder
+pub const NFO_FILE_NAME: &str = "album.nfo";
+
+/// Content of the JSON sidecar: the album model, without the links to its files, with the time of
+/// its download
+#[derive(Debug, Serialize)]
+struct AlbumMetadata<'a> {
+    #[serde(flatten)]
+    album: &'a Album,
+    downloaded_at: DateTime<Utc>,
+}
+
+/// Content of the JSON sidecar as read back
+#[derive(Debug, Deserialize)]
+struct StoredMetadata {
+    #[serde(flatten)]
+    album: Album,
+    downloaded_at: Option<DateTime<Utc>>,
+}
+
+/// Returns the album of the `album.json` sidecar of the folder at `dir`, with the time of its
+/// download if known
+pub fn read_json(dir: &Path) -> Result<(Album, Option<DateTime<Utc>>)> {
+    let json = fs::read_to_string(dir.join(JSON_FILE_NAME))?;
+    let metadata: StoredMetadata = serde_json::from_str(&json)?;
+
+    Ok((metadata.album, metadata.downloaded_at))
+}
+
+/// Returns `album` without the links to its files: the signed link of its purchase and the
+/// tokenised urls of its tracks are private to the user, and expire anyway
+fn without_file_links(album: &Album) -> Album {
+    let mut album = album.clone();
+    album.purchase = None;
+    for track in album.tracks.iter_mut() {
+        track.mp3_url.clear();
+    }
+
+    album
+}
+
+/// Write the `album.json` sidecar of `album` to its folder
+pub fn write_json(album: &Album, downloaded_at: DateTime<Utc>) -> Result<()> {
+    let metadata = AlbumMetadata {
+        album: &without_file_links(album),
+        downloaded_at,
+    };
+    let path: PathBuf = [&album.path, JSON_FILE_NAME].iter().collect();
+    fs::write(path, serde_json::to_string_pretty(&metadata)?)?;
+
+    Ok(())
+}
+
+/// Escape the XML special characters of `text`
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Returns the album NFO (Kodi format) of `album`, listing its selected tracks
+fn nfo(album: &Album) -> String {
+    let mut nfo = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+    nfo.push_str("<album>\n");
+    let mut element = |indent: &str, name: &str, value: &str| {
+        let _ = writeln!(nfo, "{}<{}>{}</{}>", indent, name, escape_xml(value), name);
+    };
+
+    element("  ", "title", &album.title);
+    element("  ", "artistdesc", &album.artist);
+    for tag in &album.tags {
+        element("  ", "genre", tag);
+    }
+    if let Some(label) = &album.label {
+        element("  ", "label", label);
+    }
+    element(
+        "  ",
+        "releasedate",
+        &album.release_date.format("%Y-%m-%d").to_string(),
+    );
+    element("  ", "year", &album.release_date.year().to_string());
+    let review: Vec<&str> = [&album.about, &album.credits]
+        .iter()
+        .filter_map(|text| text.as_deref())
+        .collect();
+    if !review.is_empty() {
+        element("  ", "review", &review.join("\n\n"));
+    }
+    element("  ", "url", &album.url);
+
+    for track in album.selected_tracks() {
+        nfo.push_str("  <track>\n");
+        let _ = writeln!(nfo, "    <position>{}</position>", track.number);
+        let _ = writeln!(nfo, "    <title>{}</title>", escape_xml(&track.title));
+        let duration = track.duration.round() as u64;
+        let _ = writeln!(
+            nfo,
+            "    <duration>{}:{:02}</duration>",
+            duration / 60,
+            duration % 60
+        );
+        nfo.push_str("  </track>\n");
+    }
+    nfo.push_str("</album>\n");
+
+    nfo
+}
+
+/// Write the `album.nfo` sidecar of `album` to its folder
+pub fn write_nfo(album: &Album) -> Result<()> {
+    let path: PathBuf = [&album.path, NFO_FILE_NAME].iter().collect();
+    fs::write(path, nfo(album))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{
+        helper::test_helper::{test_album, test_dir},
+        model::PurchaseLink,
+        settings::DownloadFormat,
+    };
+
+    fn album() -> Album {
+        let mut album = test_album("/music", &["Pit Stop"], "{title}.mp3");
+        album.label = Some(String::from("Tracks & Co"));
+        album.release_date = Utc.ymd(2020, 4, 24).and_hms(0, 0, 0);
+        album.about = Some(String::from("Late <night> music"));
+        album.tags = vec![String::from("electronic")];
+        album.tracks[0].number = 2;
+        album.tracks[0].duration = 157.2;
+        album
+    }
+
+    #[test]
+    fn writes_nfo() {
+        let msg = "should escape the album metadata and list the tracks";
+        let actual = nfo(&album());
+        let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>
+<album>
+  <title>Night Drive</title>
+  <artistdesc>The Racers</artistdesc>
+  <genre>electronic</genre>
+  <label>Tracks &amp; Co</label>
+  <releasedate>2020-04-24</releasedate>
+  <year>2020</year>
+  <review>Late &lt;night&gt; music</review>
+  <url></url>
+  <track>
+    <position>2</position>
+    <title>Pit Stop</title>
+    <duration>2:37</duration>
+  </track>
+</album>
+";
+        assert_eq!(actual, expected, "{}", msg);
+    }
+
+    #[test]
+    fn writes_json() {
+        let dir = test_dir("writes_json");
+        let mut album = album();
+        album.path = dir.to_string_lossy().into();
+        album.purchase = Some(PurchaseLink {
+            url: String::from("https://bandcamp.com/download/album?sig=secret"),
+            format: DownloadFormat::Flac,
+        });
+        album.tracks[0].mp3_url = String::from("https://t4.bcbits.com/stream/2?token=secret");
+        let downloaded_at = Utc.ymd(2021, 1, 2).and_hms(3, 4, 5);
+
+        let msg = "should write the album model with the download time";
+        write_json(&album, downloaded_at).unwrap();
+        let json = fs::read_to_string(dir.join(JSON_FILE_NAME)).unwrap();
+        let actual: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual["title"], "Night Drive", "{}", msg);
+        assert_eq!(actual["tracks"][0]["title"], "Pit Stop", "{}", msg);
+        assert_eq!(actual["downloaded_at"], "2021-01-02T03:04:05Z", "{}", msg);
+
+        let msg = "should not write the links to the files of the album";
+        assert!(!json.contains("secret"), "{}", msg);
+
+        let msg = "should read back the album model and the download time";
+        let actual = read_json(&dir).unwrap();
+        album.purchase = None;
+        album.tracks[0].mp3_url.clear();
+        assert_eq!(actual, (album, Some(downloaded_at)), "{}", msg);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
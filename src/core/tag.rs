@@ -35,6 +35,22 @@ impl std::fmt::Display for EditAction {
     }
 }
 
+/// Id of the frame holding the url of the release page (official audio file webpage)
+const RELEASE_URL_FRAME: &str = "WOAF";
+
+/// Returns the url of the release page saved in the [`id3::Tag`], if any
+pub fn release_url(tag: &id3::Tag) -> Option<&str> {
+    tag.get(RELEASE_URL_FRAME)?.content().link()
+}
+
+/// Updates the [`id3::Tag`] in place with the url of the release page, unless it is empty
+pub fn update_release_url(tag: &mut id3::Tag, url: &str) {
+    if !url.is_empty() {
+        let content = id3::Content::Link(url.to_string());
+        tag.add_frame(id3::Frame::with_content(RELEASE_URL_FRAME, content));
+    }
+}
+
 /// Updates the [`id3::Tag`] in place with the specified album artist based on the specified [`EditAction`].
 pub fn update_album_artist(tag: &mut id3::Tag, album_artist: &str, edit_action: EditAction) {
     match edit_action {
@@ -366,5 +382,20 @@ mod test {
         assert_eq!(actual, 0, "{}", msg);
     }
 
+    #[test]
+    fn updates_release_url() {
+        let msg = "should save the release url";
+        let expected = "https://theracers.bandcamp.com/album/final-lap";
+        let mut tag = id3::Tag::new();
+        update_release_url(&mut tag, expected);
+        let actual = release_url(&tag);
+        assert_eq!(actual, Some(expected), "{}", msg);
+
+        let msg = "should not save an empty url";
+        let mut tag = id3::Tag::new();
+        update_release_url(&mut tag, "");
+        assert_eq!(release_url(&tag), None, "{}", msg);
+    }
+
     // TODO: add update_lyrics tests
 }
@@ -0,0 +1,218 @@
+//! Transcoding of the downloaded tracks by an external encoder (eg. ffmpeg)
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Error,
+    model::{Album, Track},
+    Result,
+};
+
+/// Transcoding of the downloaded tracks, run once they are downloaded and tagged. The encoder
+/// carries the tags and artwork across to the transcoded files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Transcoding {
+    /// Transcode the downloaded tracks
+    pub enabled: bool,
+    /// Path to the encoder binary, or its name if it is in the PATH
+    pub encoder: String,
+    /// Whitespace separated arguments of the encoder. The placeholders `{input}`, `{output}`,
+    /// `{bitrate}`, `{artist}`, `{album}`, `{title}`, `{tracknum}` and `{year}` are replaced in
+    /// each argument.
+    pub arguments: String,
+    /// Extension of the transcoded files, replacing the one of the file name format
+    pub extension: String,
+    /// Bitrate of the transcoded files, in kbps
+    pub bitrate: u32,
+    /// Keep the downloaded files along with the transcoded ones
+    pub keep_originals: bool,
+}
+
+impl Default for Transcoding {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            encoder: String::from("ffmpeg"),
+            arguments: String::from(concat!(
+                "-y -loglevel error -i {input} -map 0 -map_metadata 0 -c:v copy -c:a aac ",
+                "-b:a {bitrate}k {output}"
+            )),
+            extension: String::from("m4a"),
+            bitrate: 256,
+            keep_originals: false,
+        }
+    }
+}
+
+/// Returns the path an output replacing its input at `path` is written to, keeping its extension
+/// as the encoder may pick the output format from it (eg. `track.transcoding.m4a`)
+fn temporary_path(path: &Path) -> PathBuf {
+    match path.extension() {
+        Some(extension) => {
+            path.with_extension(format!("transcoding.{}", extension.to_string_lossy()))
+        }
+        None => path.with_extension("transcoding"),
+    }
+}
+
+impl Transcoding {
+    /// Returns the path of the transcoded file of the track downloaded at `path`
+    pub fn output_path(&self, path: &str) -> String {
+        let extension = self.extension.trim().trim_start_matches('.');
+        Path::new(path)
+            .with_extension(extension)
+            .to_string_lossy()
+            .into()
+    }
+
+    /// Returns the encoder arguments transcoding `input` to `output`, the tags of `track` of
+    /// `album` replacing their placeholders
+    fn arguments(&self, input: &Path, output: &Path, album: &Album, track: &Track) -> Vec<String> {
+        let input = input.to_string_lossy();
+        let output = output.to_string_lossy();
+        self.arguments
+            .split_whitespace()
+            .map(|argument| {
+                argument
+                    .replace("{input}", &input)
+                    .replace("{output}", &output)
+                    .replace("{bitrate}", &self.bitrate.to_string())
+                    .replace("{artist}", &album.artist)
+                    .replace("{album}", &album.title)
+                    .replace("{title}", &track.title)
+                    .replace("{tracknum}", &track.number.to_string())
+                    .replace("{year}", &album.release_date.year().to_string())
+            })
+            .collect()
+    }
+
+    /// Transcode the downloaded file of `track` of `album` to `output`. The downloaded file is
+    /// removed unless [`Transcoding::keep_originals`] is set.
+    pub fn transcode(&self, album: &Album, track: &Track, output: &str) -> Result<()> {
+        let input = Path::new(&track.path);
+        // an output replacing its input is written aside first
+        let same_file = input == Path::new(output);
+        let destination = if same_file {
+            temporary_path(input)
+        } else {
+            PathBuf::from(output)
+        };
+
+        let result = Command::new(self.encoder.trim())
+            .args(self.arguments(input, &destination, album, track))
+            .output()
+            .map_err(|e| Error::Io(format!("Unable to run {}: {}", self.encoder, e)))?;
+        if !result.status.success() {
+            let _ = fs::remove_file(&destination);
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            return Err(Error::Io(format!(
+                "{} failed ({}): {}",
+                self.encoder,
+                result.status,
+                stderr.trim()
+            )));
+        }
+        if !destination.is_file() {
+            return Err(Error::Io(format!("{} wrote no file", self.encoder)));
+        }
+
+        if same_file {
+            fs::rename(&destination, output)?;
+        } else if !self.keep_originals {
+            fs::remove_file(input)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helper::test_helper::test_album;
+
+    #[test]
+    fn replaces_placeholders() {
+        let album = test_album("/music", &["Pit Stop"], "{title}.mp3");
+        let track = &album.tracks[0];
+        let transcoding = Transcoding {
+            arguments: String::from("-i {input} -metadata title={title} -b:a {bitrate}k {output}"),
+            extension: String::from(".opus"),
+            bitrate: 96,
+            ..Transcoding::default()
+        };
+
+        let msg = "should replace the extension of the downloaded file";
+        let output = transcoding.output_path(&track.path);
+        assert!(output.ends_with("Pit Stop.opus"), "{}", msg);
+
+        let msg = "should replace the placeholders of each argument";
+        let actual = transcoding.arguments(
+            Path::new("in.mp3"),
+            Path::new("out dir/out.opus"),
+            &album,
+            track,
+        );
+        let expected = vec![
+            "-i",
+            "in.mp3",
+            "-metadata",
+            "title=Pit Stop",
+            "-b:a",
+            "96k",
+            "out dir/out.opus",
+        ];
+        assert_eq!(actual, expected, "{}", msg);
+
+        let msg = "should keep the extension of an output written aside";
+        let actual = temporary_path(Path::new("/music/Pit Stop.m4a"));
+        assert_eq!(
+            actual,
+            Path::new("/music/Pit Stop.transcoding.m4a"),
+            "{}",
+            msg
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_encoder() {
+        let dir = crate::helper::test_helper::test_dir("runs_encoder");
+        let mut album = test_album("/music", &["Pit Stop"], "{title}.mp3");
+        album.tracks[0].path = dir.join("Pit Stop.mp3").to_string_lossy().into();
+        let track = &album.tracks[0];
+        fs::write(&track.path, b"stream").unwrap();
+
+        let transcoding = Transcoding {
+            enabled: true,
+            encoder: String::from("cp"),
+            arguments: String::from("{input} {output}"),
+            ..Transcoding::default()
+        };
+        let output = transcoding.output_path(&track.path);
+
+        let msg = "should write the output and remove the original";
+        transcoding.transcode(&album, track, &output).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), b"stream", "{}", msg);
+        assert!(!Path::new(&track.path).exists(), "{}", msg);
+
+        let msg = "should report the encoder failures";
+        let failing = Transcoding {
+            encoder: String::from("false"),
+            ..transcoding
+        };
+        assert!(
+            failing.transcode(&album, track, &output).is_err(),
+            "{}",
+            msg
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
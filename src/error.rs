@@ -5,36 +5,40 @@ use serde_json::error::Category;
 /// Domain specific Error type
 #[derive(Debug, Clone)]
 pub enum Error {
-    Api,
+    Api(String),
+    Auth(String),
     Download,
+    Filter(String),
     Io(String),
     NoAlbumData,
     NoAlbumFound,
     NoDiscography,
     NoArtwork,
     Serialization(String),
+    Usage(String),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match *self {
-            Self::Api => write!(f, "API error occured"),
+            Self::Api(ref msg) => write!(f, "API error occured: {}", msg),
+            Self::Auth(ref msg) => write!(f, "Authentication error: {}", msg),
             Self::Download => write!(f, "Download error"),
+            Self::Filter(ref msg) => write!(f, "Invalid release filter: {}", msg),
             Self::Io(_) => write!(f, "IO error"),
             Self::NoAlbumData => write!(f, "No album data found for this artist"),
             Self::NoAlbumFound => write!(f, "No album found for this artist"),
             Self::NoArtwork => write!(f, "No artwork found for this album"),
             Self::NoDiscography => write!(f, "No discography could be found on the supplied url"),
             Self::Serialization(_) => write!(f, "A serialization error occured"),
+            Self::Usage(ref msg) => write!(f, "{}", msg),
         }
     }
 }
 
 impl From<reqwest::Error> for Error {
     fn from(error: reqwest::Error) -> Error {
-        dbg!(error);
-
-        Error::Api
+        Error::Api(error.to_string())
     }
 }
 
@@ -63,3 +67,9 @@ impl From<tokio::io::Error> for Error {
         Error::Io(format!("{}:\n\t{:?}", msg, error))
     }
 }
+
+impl From<zip::result::ZipError> for Error {
+    fn from(error: zip::result::ZipError) -> Error {
+        Error::Io(format!("Archive error: {}", error))
+    }
+}
@@ -1,81 +1,115 @@
 use std::collections::HashSet;
 
-use futures::channel::mpsc;
-use regex::Regex;
-
 use crate::{
     error::Error,
-    model::{Album, JsonAlbum},
-    ui::{LogLevel, Message},
-    Result, ALBUM_RE, BAND_RE, HTML_AMP_RE, HTML_GT_RE, HTML_LT_RE, HTML_QUOTE_RE,
+    model::{
+        Album, CollectionKind, CollectionPage, JsonAlbum, JsonBand, JsonClientItem,
+        JsonDownloadPage, JsonFanPage, JsonLdAlbum, PurchaseLink,
+    },
+    settings::DownloadFormat,
+    Result,
 };
 
 mod file_helper;
+#[cfg(test)]
+pub mod mock_server;
+mod page;
+#[cfg(test)]
+pub mod test_helper;
 pub use file_helper::*;
+pub use page::Page;
 
-fn log_channel<T: ToString>(mut sender: mpsc::Sender<Message>, level: LogLevel, msg: T) {
-    sender
-        .try_send(Message::Log(msg.to_string(), level))
-        .expect("Failed to send message");
-}
+/// Returns a human readable representation of a number of bytes (eg. "1.50 MB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
 
-pub fn log_info<T: ToString>(sender: mpsc::Sender<Message>, msg: T) {
-    log_channel(sender, LogLevel::Info, msg)
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit])
+    }
 }
 
-pub fn log_warn<T: ToString>(sender: mpsc::Sender<Message>, msg: T) {
-    log_channel(sender, LogLevel::Warn, msg)
+/// Returns `bytes` split in `parts` shares, differing by one byte at most
+pub fn split_bytes(bytes: u64, parts: usize) -> Vec<u64> {
+    let parts = parts as u64;
+    (0..parts)
+        .map(|i| bytes * (i + 1) / parts - bytes * i / parts)
+        .collect()
 }
 
-pub fn log_error<T: ToString>(sender: mpsc::Sender<Message>, msg: T) {
-    log_channel(sender, LogLevel::Error, msg)
+/// Returns a duration formatted as `h:mm:ss`
+pub fn format_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
 }
 
 /// Get the TralbumData content from the page
-fn get_album_data(raw_html: &str) -> Result<String> {
-    lazy_static! {
-        static ref ALBUM_DATA_RE: Regex =
-            Regex::new(r#"(?s)data-tralbum="(?P<data>\{.*?\})"\s?"#).unwrap();
-    }
-
-    let album_data = HTML_QUOTE_RE.replace_all(raw_html, "\"");
-    let album_data = HTML_AMP_RE.replace_all(&album_data, "&");
-    let album_data = HTML_LT_RE.replace_all(&album_data, "<");
-    let album_data = HTML_GT_RE.replace_all(&album_data, ">");
-    ALBUM_DATA_RE
-        .captures(&album_data)
-        .and_then(|captures| captures.name("data"))
-        .map(|name| name.as_str().into())
-        .ok_or(Error::NoAlbumData)
+fn get_album_data(page: &Page) -> Result<&str> {
+    page.attribute("data-tralbum").ok_or(Error::NoAlbumData)
 }
 
-// We're pulling from a javascript object literal, so we need to turn it into valid JSON before we
-// can deserialize it.
-// In trackinfo property, we have for instance:
-//     url: "http://verbalclick.bandcamp.com" + "/album/404"
-// -> Remove the " + "
-fn fix_json(album_data: &str) -> String {
-    lazy_static! {
-        static ref JSON_FIX_RE: regex::Regex =
-            regex::Regex::new(r#"(?P<root>url: ".+)" \+ "(?P<album>.+",)"#).unwrap();
-    }
-    let fixed = JSON_FIX_RE.replace(album_data, "${root}${album}");
-
-    fixed.to_string()
+/// Get the name of the band (or label) account hosting the page
+fn get_band_name(page: &Page) -> Option<String> {
+    page.attribute("data-band")
+        .and_then(|band| serde_json::from_str::<JsonBand>(band).ok())
+        .map(|band| band.name)
 }
 
 /// Retrieves the data on the album of the specified Bandcamp page.  Takes the HTML source code of
 /// a Bandcamp album page and returns the data on the album of the specified Bandcamp page.
-pub fn get_album(raw_html: &str, folder_path: &str, filename_format: &str) -> Result<Album> {
-    // Keep the necessary part of the html only
-    // it's a js object literal, which isnt JSON, so we need to adjust it to match the actual
-    // spec prior to deserialization
-    let album_data = fix_json(raw_html);
-    let album_data = get_album_data(&album_data)?;
+///
+/// `label` overrides the label found on the page, eg. for the releases found on a label page.
+pub fn get_album(
+    raw_html: &str,
+    label: Option<&str>,
+    folder_path: &str,
+    filename_format: &str,
+) -> Result<Album> {
+    let page = Page::parse(raw_html);
+    let mut ld_json = page
+        .ld_json()
+        .and_then(|ld_json| serde_json::from_str::<JsonLdAlbum>(&ld_json).ok());
+
+    let album_data = match get_album_data(&page) {
+        Ok(album_data) => album_data,
+        // Fall back on the structured data, which describes the same release
+        Err(e) => {
+            return ld_json
+                .and_then(|ld_json| ld_json.into_album(label, folder_path, filename_format))
+                .ok_or(e);
+        }
+    };
+
     // Deserialize JSON
     // TODO serializer interface
-    let album =
-        serde_json::from_str::<JsonAlbum>(&album_data)?.into_album(folder_path, filename_format);
+    let mut album_data = serde_json::from_str::<JsonAlbum>(album_data)?;
+
+    // Fall back on the name of the band hosting the page if no artist is credited
+    if album_data.artist.is_none() {
+        album_data.artist = get_band_name(&page);
+    }
+
+    // The label is part of the album paths, it must be known beforehand
+    let label = label
+        .map(String::from)
+        .or_else(|| ld_json.as_mut().and_then(JsonLdAlbum::take_label));
+    let mut album = album_data.into_album(label.as_deref(), folder_path, filename_format);
+
+    // Merge the fields only found in the structured data
+    if let Some(mut ld_json) = ld_json {
+        album.tags = ld_json.take_tags();
+        // Albums without art_id may still reference their cover
+        if album.artwork_url.is_none() {
+            album.artwork_url = ld_json.image;
+        }
+    }
 
     // TODO lyrics
     // Extract lyrics from album page
@@ -83,26 +117,110 @@ pub fn get_album(raw_html: &str, folder_path: &str, filename_format: &str) -> Re
     Ok(album)
 }
 
+/// Returns the name of the label hosting the provided Bandcamp page, or None if the page belongs
+/// to an artist. Unlike artists, labels have an `/artists` page listing their roster.
+pub fn get_label(raw_html: &str) -> Option<String> {
+    let page = Page::parse(raw_html);
+    let artists_url = format!("{}/artists", page.band_url()?);
+    let is_label = page
+        .links()
+        .into_iter()
+        .any(|href| href == "/artists" || href == artists_url);
+
+    if is_label {
+        get_band_name(&page)
+    } else {
+        None
+    }
+}
+
+/// True if `artist` matches one of the comma separated names of `filter` (case insensitive), or if
+/// `filter` is empty.
+pub fn matches_artist(artist: &str, filter: &str) -> bool {
+    let artist = artist.to_lowercase();
+    let mut names = filter
+        .split(',')
+        .map(|name| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .peekable();
+
+    names.peek().is_none() || names.any(|name| artist.contains(&name))
+}
+
+/// Returns the key identifying the page at `url`, ignoring its protocol, trailing slash and case
+pub fn url_key(url: &str) -> String {
+    let url = url.trim();
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+
+    url.trim_end_matches('/').to_lowercase()
+}
+
+/// Returns the absolute url of a release (album or track) page, or None if `href` links to
+/// another kind of page.
+fn release_url(artist_url: &str, href: &str) -> Option<String> {
+    if href.starts_with("/album/") || href.starts_with("/track/") {
+        return Some(format!("{}{}", artist_url, href));
+    }
+
+    // Releases of other bands (eg. on label pages) link to their own page
+    if href.starts_with("http") && (href.contains("/album/") || href.contains("/track/")) {
+        let url = href.split('?').next().unwrap_or(href);
+        return Some(url.to_string());
+    }
+
+    None
+}
+
+/// Returns the first releases of the collection (or wishlist) found on the provided raw HTML source
+/// code of a fan page
+pub fn get_collection(raw_html: &str, kind: CollectionKind) -> Result<CollectionPage> {
+    let page = Page::parse(raw_html);
+    let data = page.attribute("data-blob").ok_or(Error::NoAlbumFound)?;
+    let fan_page = serde_json::from_str::<JsonFanPage>(data)?;
+    Ok(fan_page.into_page(kind))
+}
+
+/// Returns the link to download the release in the first available of `formats`, found on the
+/// provided raw HTML source code of a download page (of a purchase, or a free release). Returns
+/// None if the release is available in none of them.
+pub fn get_download_link(
+    raw_html: &str,
+    formats: &[DownloadFormat],
+) -> Result<Option<PurchaseLink>> {
+    let page = Page::parse(raw_html);
+    let data = page.attribute("data-blob").ok_or(Error::NoAlbumData)?;
+    let mut links = serde_json::from_str::<JsonDownloadPage>(data)?.into_links();
+    Ok(formats.iter().find_map(|&format| {
+        links
+            .remove(format.value())
+            .map(|url| PurchaseLink { url, format })
+    }))
+}
+
 /// Retrieves all the album URLs existing in the provided raw HTML source code of a Bandcamp page.
 pub fn get_albums_url(raw_html: &str) -> Result<Vec<String>> {
+    let page = Page::parse(raw_html);
     // Get artist bandcamp page
-    let artist_url = BAND_RE
-        .captures(raw_html)
-        .ok_or(Error::NoAlbumFound)?
-        .name("url")
-        .unwrap()
-        .as_str();
+    let artist_url = page.band_url().ok_or(Error::NoAlbumFound)?;
 
-    // Get albums ("real" albums or track-only pages) relative urls
-    let captures = ALBUM_RE.captures_iter(raw_html);
+    // Get albums ("real" albums or track-only pages) urls
+    let mut album_urls: HashSet<_> = page
+        .links()
+        .into_iter()
+        .filter_map(|href| release_url(artist_url, href))
+        .collect();
 
-    let mut album_urls = HashSet::new();
-    for cap in captures {
-        album_urls.insert(format!(
-            "{}{}",
-            artist_url,
-            cap.name("album_url").unwrap().as_str()
-        ));
+    // Large music grids only link their first items, the others are loaded by script
+    if let Some(items) = page.attribute("data-client-items") {
+        let items: Vec<JsonClientItem> = serde_json::from_str(items)?;
+        album_urls.extend(
+            items
+                .iter()
+                .filter_map(|item| release_url(artist_url, &item.page_url)),
+        );
     }
 
     if album_urls.is_empty() {
@@ -120,19 +238,37 @@ mod test {
     mod strings;
 
     #[test]
-    fn fixes_json() {
-        let raw = r#"url: "http://verbalclick.bandcamp.com" + "/album/404","#;
+    fn formats_bytes() {
+        let msg = "should display small sizes in bytes";
+        let expected = "512 B";
+        let actual = format_bytes(512);
+        assert_eq!(actual, expected, "{}", msg);
 
-        let msg = "fixes bad json";
-        let expected = r#"url: "http://verbalclick.bandcamp.com/album/404","#;
-        let actual = fix_json(raw);
+        let msg = "should display larger sizes with the largest fitting unit";
+        let expected = "1.50 MB";
+        let actual = format_bytes(1024 * 1024 * 3 / 2);
         assert_eq!(actual, expected, "{}", msg);
     }
 
+    #[test]
+    fn splits_bytes() {
+        let msg = "should split the bytes in shares adding up to the whole";
+        assert_eq!(split_bytes(10, 3), vec![3, 3, 4], "{}", msg);
+        assert_eq!(split_bytes(0, 2), vec![0, 0], "{}", msg);
+    }
+
+    #[test]
+    fn formats_duration() {
+        let expected = "1:02:03";
+        let actual = format_duration(std::time::Duration::from_secs(3723));
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn gets_album_data() {
         let expected = strings::TRALBUM_DATA;
-        let actual = get_album_data(strings::TRALBUM_HTML).unwrap();
+        let page = Page::parse(strings::TRALBUM_HTML);
+        let actual = get_album_data(&page).unwrap();
         assert_eq!(actual, expected);
     }
 
@@ -144,6 +280,8 @@ mod test {
             artwork_path: String::from(""),
             artwork_temp_path: String::from(""),
             artwork_url: Some(String::from("https://f4.bcbits.com/img/a2129006133_0.jpg")),
+            label: None,
+            tags: Vec::new(),
             path: String::from("/home/partylich/music/test/The Racers/2020 - Final Lap"),
             playlist_path: String::from(
                 "/home/partylich/music/test/The Racers/2020 - Final Lap/2020_Final Lap",
@@ -153,21 +291,163 @@ mod test {
                 .datetime_from_str("24 Apr 2020 00:00:00 +0000", "%d %b %Y %T %z")
                 .unwrap(),
             tracks: vec![Track {
+                id: Some(350943074),
                 duration: 311.327,
                 lyrics: None,
                 mp3_url: String::from("https://t4.bcbits.com/stream/8e264c1615dca0ab965f6e3b320ea9da/mp3-128/350943074?p=0&ts=1631806573&t=1c02736b48124fcde7acb2743812134a3e4b25de&token=1631806573_49c0e23c8c2b500fcf206501d703e81527972f5b"),
                 number: 1,
                 path: String::from("/home/partylich/music/test/The Racers/2020 - Final Lap/01 - Final Lap.mp3"),
-                title: String::from("Final Lap")
+                title: String::from("Final Lap"),
+                artist: None,
+                duplicate_of: None,
+                selected: true,
+                size: None,
             },
             ],
+            url: String::new(),
+            purchase: None,
+            free_download_page: None,
+            requires_email: false,
+            id: Some(350943074),
+            about: None,
+            band_id: Some(1173700968),
+            credits: Some(String::from("Produced by : \nAlexis Bidaud\nLeo Archer")),
         };
         let save_dir = "/home/partylich/music/test/{artist}/{year} - {album}";
         let filename_format = "{tracknum} - {title}.mp3";
-        let actual = get_album(strings::TRALBUM_HTML, save_dir, filename_format).unwrap();
+        let actual = get_album(strings::TRALBUM_HTML, None, save_dir, filename_format).unwrap();
+        assert_eq!(actual, expected, "{}", msg);
+    }
+
+    #[test]
+    fn falls_back_on_band_name() {
+        let raw_html = r#"<script data-tralbum="{&quot;artist&quot;:null,&quot;art_id&quot;:null,&quot;current&quot;:{&quot;title&quot;:&quot;Don&#39;t Stop&quot;,&quot;release_date&quot;:&quot;24 Apr 2020 00:00:00 GMT&quot;},&quot;trackinfo&quot;:[]}" data-band="{&quot;id&quot;:1,&quot;name&quot;:&quot;The Racers&quot;}"></script>"#;
+        let album = get_album(raw_html, None, "/music/{artist}/{album}", "{title}.mp3").unwrap();
+
+        let msg = "should credit the band when the album has no artist";
+        assert_eq!(album.artist, "The Racers", "{}", msg);
+
+        let msg = "should decode numeric character references";
+        assert_eq!(album.title, "Don't Stop", "{}", msg);
+    }
+
+    #[test]
+    fn gets_album_from_ld_json() {
+        let album = get_album(
+            strings::LD_JSON_ALBUM_HTML,
+            None,
+            "/music/{artist}/{album}",
+            "{tracknum} - {title}.mp3",
+        )
+        .unwrap();
+
+        let msg = "should build the album from the structured data when tralbum data is missing";
+        assert_eq!(album.title, "The Midnight By The Racers I", "{}", msg);
+        assert_eq!(album.artist, "The Racers", "{}", msg);
+        assert_eq!(
+            album.tags,
+            vec!["Electronic", "synthwave", "Paris"],
+            "{}",
+            msg
+        );
+
+        let msg = "should not file the self-released albums under a label";
+        assert_eq!(album.label, None, "{}", msg);
+        assert_eq!(album.publisher(), "The Racers", "{}", msg);
+
+        let msg = "should order the tracks by position";
+        let actual: Vec<_> = album
+            .tracks
+            .iter()
+            .map(|track| (track.number, track.title.as_str(), track.duration))
+            .collect();
+        let expected = vec![(1, "Tunnel Vision", 311.327), (2, "Deep Blue", 252.0)];
         assert_eq!(actual, expected, "{}", msg);
     }
 
+    #[test]
+    fn merges_ld_json() {
+        let raw_html = format!(
+            r#"{}<script type="application/ld+json">{}</script>"#,
+            strings::TRALBUM_HTML,
+            strings::LD_JSON_TRACK
+        );
+        let album = get_album(&raw_html, None, "/music/{label}/{album}", "{title}.mp3").unwrap();
+
+        let msg = "should keep the tralbum data";
+        assert_eq!(album.title, "Final Lap", "{}", msg);
+        assert!(
+            album.tracks[0]
+                .mp3_url
+                .starts_with("https://t4.bcbits.com/stream/8e264c"),
+            "{}",
+            msg
+        );
+
+        let msg = "should merge the tags and label from the structured data";
+        assert_eq!(album.tags, vec!["electronic", "synthwave"], "{}", msg);
+        assert_eq!(album.label.as_deref(), Some("Nightride Records"), "{}", msg);
+
+        let msg = "should file the album under its label";
+        assert_eq!(album.path, "/music/Nightride Records/Final Lap", "{}", msg);
+
+        let msg = "should prefer the label supplied";
+        let album = get_album(
+            &raw_html,
+            Some("Synthwave Co"),
+            "/music/{label}",
+            "{title}.mp3",
+        );
+        let actual = album.unwrap().path;
+        assert_eq!(actual, "/music/Synthwave Co", "{}", msg);
+    }
+
+    #[test]
+    fn matches_artist_filter() {
+        let msg = "should match any artist when the filter is empty";
+        assert!(matches_artist("The Racers", " "), "{}", msg);
+
+        let msg = "should match part of the name, ignoring case";
+        assert!(matches_artist("The Racers", "racers"), "{}", msg);
+
+        let msg = "should match any of the names";
+        assert!(
+            matches_artist("The Racers", "Nightride, the racers"),
+            "{}",
+            msg
+        );
+        assert!(
+            !matches_artist("The Racers", "Nightride, Midnight"),
+            "{}",
+            msg
+        );
+    }
+
+    #[test]
+    fn gets_label() {
+        let msg = "should return the name of a label";
+        let actual = get_label(strings::CLIENT_ITEMS_HTML);
+        assert_eq!(actual.as_deref(), Some("Nightride Records"), "{}", msg);
+
+        let msg = "should return None for an artist";
+        let actual = get_label(strings::ALBUM_HTML);
+        assert_eq!(actual, None, "{}", msg);
+    }
+
+    #[test]
+    fn gets_client_items_url() {
+        let mut expected = vec![
+            "https://nightride.bandcamp.com/album/first-light",
+            "https://nightride.bandcamp.com/album/second-wind",
+            "https://nightride.bandcamp.com/track/third-gear",
+            "https://theracers.bandcamp.com/album/guest-release",
+        ];
+        let mut actual = get_albums_url(strings::CLIENT_ITEMS_HTML).unwrap();
+        actual.sort();
+        expected.sort();
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn gets_albums_url() {
         let mut expected = vec![
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use chrono::Datelike;
 use regex::Regex;
@@ -20,6 +20,22 @@ pub fn get_root_dir() -> PathBuf {
     }
 }
 
+/// Opens a folder in the platform file manager
+pub fn open_folder<P: AsRef<Path>>(path: P) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+
+    std::process::Command::new(program)
+        .arg(path.as_ref())
+        .spawn()
+        .map(|_| ())
+}
+
 /// Replaces the forbidden characters \ / : * ? " &lt; &gt; | from with an underscore _
 /// in order to be used for a Windows file or folder.
 // Windows rules: https://docs.microsoft.com/en-us/windows/desktop/FileIO/naming-a-file
@@ -52,7 +68,8 @@ pub fn parse_filename(filename_format: &str, album: &Album) -> String {
         .replace("{month}", &format!("{:02}", album.release_date.month()))
         .replace("{day}", &format!("{:02}", album.release_date.day()))
         .replace("{album}", &album.title)
-        .replace("{artist}", &album.artist);
+        .replace("{artist}", &album.artist)
+        .replace("{label}", album.publisher());
 
     sanitize_file_name(&file_name)
 }
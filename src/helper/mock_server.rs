@@ -0,0 +1,292 @@
+//! Local HTTP server standing in for Bandcamp in tests
+use std::{collections::HashMap, sync::Arc};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+pub mod pages;
+
+/// Placeholder replaced with the server url in the response bodies
+const BASE_URL: &str = "{base_url}";
+
+/// A canned HTTP response
+#[derive(Debug, Clone)]
+pub struct Response {
+    content_type: &'static str,
+    body: Vec<u8>,
+    /// Cookie the request must send, answered with 403 Forbidden otherwise
+    cookie: Option<&'static str>,
+}
+
+impl Response {
+    /// Create a `text/html` response
+    pub fn html(body: &str) -> Self {
+        Self {
+            content_type: "text/html; charset=utf-8",
+            body: body.into(),
+            cookie: None,
+        }
+    }
+
+    /// Create a `text/plain` response
+    pub fn text(body: &str) -> Self {
+        Self {
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+            cookie: None,
+        }
+    }
+
+    /// Create an `application/json` response
+    pub fn json(body: &str) -> Self {
+        Self {
+            content_type: "application/json",
+            body: body.into(),
+            cookie: None,
+        }
+    }
+
+    /// Create an `audio/mpeg` response
+    pub fn mp3(body: &[u8]) -> Self {
+        Self {
+            content_type: "audio/mpeg",
+            body: body.to_vec(),
+            cookie: None,
+        }
+    }
+
+    /// Create an `audio/flac` response
+    pub fn flac(body: &[u8]) -> Self {
+        Self {
+            content_type: "audio/flac",
+            body: body.to_vec(),
+            cookie: None,
+        }
+    }
+
+    /// Create an `application/zip` response
+    pub fn zip(body: &[u8]) -> Self {
+        Self {
+            content_type: "application/zip",
+            body: body.to_vec(),
+            cookie: None,
+        }
+    }
+
+    /// Only answer the requests sending `cookie`, eg. the pages of a logged in user
+    pub fn requiring_cookie(mut self, cookie: &'static str) -> Self {
+        self.cookie = Some(cookie);
+        self
+    }
+}
+
+/// Minimal HTTP/1.1 server answering GET and HEAD requests with canned responses.
+///
+/// The server runs on the current tokio runtime and stops along with it.
+#[derive(Debug)]
+pub struct MockServer {
+    base_url: String,
+}
+
+impl MockServer {
+    /// Start a server on a free local port. `routes` maps request paths (including the query
+    /// string, and followed by a space and the body for requests with one) to their response.
+    /// `{base_url}` is replaced with the server url in text bodies.
+    pub async fn start(routes: Vec<(&str, Response)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let routes: HashMap<_, _> = routes
+            .into_iter()
+            .map(|(path, mut response)| {
+                let content_type = response.content_type;
+                if content_type.starts_with("text/") || content_type == "application/json" {
+                    let body = String::from_utf8_lossy(&response.body);
+                    response.body = body.replace(BASE_URL, &base_url).into_bytes();
+                }
+                (path.to_string(), response)
+            })
+            .collect();
+        let routes = Arc::new(routes);
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(respond(stream, routes.clone()));
+            }
+        });
+
+        Self { base_url }
+    }
+
+    /// Start a server hosting a fake Bandcamp artist, with an album of two tracks and a single
+    /// track release
+    pub async fn bandcamp() -> Self {
+        Self::start(vec![
+            ("/", Response::html(pages::ARTIST_HTML)),
+            ("/music", Response::html(pages::MUSIC_HTML)),
+            ("/album/first-album", Response::html(pages::ALBUM_HTML)),
+            ("/track/single-track", Response::html(pages::TRACK_HTML)),
+            ("/stream/1.mp3", Response::mp3(pages::MP3_1)),
+            ("/stream/2.mp3", Response::mp3(pages::MP3_2)),
+            ("/stream/3.mp3", Response::mp3(pages::MP3_3)),
+        ])
+        .await
+    }
+
+    /// Start a server hosting a fake Bandcamp label, releasing the albums of
+    /// [`MockServer::bandcamp`]
+    pub async fn label() -> Self {
+        Self::start(vec![
+            ("/", Response::html(pages::LABEL_HTML)),
+            ("/music", Response::html(pages::LABEL_HTML)),
+            ("/album/first-album", Response::html(pages::ALBUM_HTML)),
+            ("/track/single-track", Response::html(pages::TRACK_HTML)),
+        ])
+        .await
+    }
+
+    /// Start a server hosting the fake Bandcamp artist of [`MockServer::bandcamp`], along with the
+    /// fan page of a user who purchased both of its releases (the track being name your price), and
+    /// their public wishlist of three albums. The collection pages require the `identity=fan`
+    /// cookie.
+    pub async fn collection() -> Self {
+        Self::start(vec![
+            ("/album/first-album", Response::html(pages::ALBUM_HTML)),
+            ("/track/single-track", Response::html(pages::TRACK_HTML)),
+            ("/stream/1.mp3", Response::mp3(pages::MP3_1)),
+            ("/stream/2.mp3", Response::mp3(pages::MP3_2)),
+            ("/stream/3.mp3", Response::mp3(pages::MP3_3)),
+            (
+                "/fan",
+                Response::html(pages::FAN_HTML).requiring_cookie(pages::FAN_COOKIE),
+            ),
+            (
+                "/download/album?id=1",
+                Response::html(pages::ALBUM_DOWNLOAD_HTML).requiring_cookie(pages::FAN_COOKIE),
+            ),
+            (
+                "/download/track?id=2",
+                Response::html(pages::TRACK_DOWNLOAD_HTML).requiring_cookie(pages::FAN_COOKIE),
+            ),
+            ("/purchase/1-flac.zip", Response::zip(&pages::album_zip())),
+            ("/purchase/2.flac", Response::flac(pages::TRACK_FLAC)),
+            (
+                "/download/free?id=2",
+                Response::html(pages::FREE_DOWNLOAD_HTML),
+            ),
+            ("/fan/wishlist", Response::html(pages::WISHLIST_HTML)),
+            (
+                &format!(
+                    "/api/fancollection/1/wishlist_items {}",
+                    pages::WISHLIST_REQUEST_1
+                ),
+                Response::json(pages::WISHLIST_ITEMS_1),
+            ),
+            (
+                &format!(
+                    "/api/fancollection/1/wishlist_items {}",
+                    pages::WISHLIST_REQUEST_2
+                ),
+                Response::json(pages::WISHLIST_ITEMS_2),
+            ),
+        ])
+        .await
+    }
+
+    /// Start a server hosting a free album of the fake Bandcamp artist of
+    /// [`MockServer::bandcamp`], downloaded from its download page, along with a free track
+    /// release whose download is only sent by email
+    pub async fn free_releases() -> Self {
+        Self::start(vec![
+            ("/album/free-album", Response::html(pages::FREE_ALBUM_HTML)),
+            (
+                "/track/email-track",
+                Response::html(pages::EMAIL_TRACK_HTML),
+            ),
+            ("/stream/1.mp3", Response::mp3(pages::MP3_1)),
+            ("/stream/2.mp3", Response::mp3(pages::MP3_2)),
+            ("/stream/3.mp3", Response::mp3(pages::MP3_3)),
+            (
+                "/download/free?id=4",
+                Response::html(pages::FREE_DOWNLOAD_HTML),
+            ),
+            ("/free/4-flac.zip", Response::zip(&pages::album_zip())),
+        ])
+        .await
+    }
+
+    /// Returns the absolute url of `path` on this server
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+}
+
+/// Read a request from `stream` and write the matching response
+async fn respond(mut stream: TcpStream, routes: Arc<HashMap<String, Response>>) {
+    // read up to the end of the headers, then the body if any
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    let head_end = loop {
+        if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    };
+    let head = String::from_utf8_lossy(&request[..head_end]).into_owned();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .unwrap_or(0);
+    while request.len() < head_end + content_length {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => request.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let body = String::from_utf8_lossy(&request[head_end..head_end + content_length]);
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default();
+    let path = request_line.next().unwrap_or_default();
+    let has_cookie = |cookie: &str| {
+        head.lines().any(|line| {
+            let line = line.to_ascii_lowercase();
+            line.starts_with("cookie:") && line.contains(cookie)
+        })
+    };
+
+    // requests with a body are routed along with it
+    let route = if body.is_empty() {
+        path.to_string()
+    } else {
+        format!("{} {}", path, body)
+    };
+
+    let (status, content_type, body) = match routes.get(&route) {
+        Some(Response {
+            cookie: Some(cookie),
+            ..
+        }) if !has_cookie(cookie) => ("403 Forbidden", "text/plain", &b"Forbidden"[..]),
+        Some(response) => ("200 OK", response.content_type, &response.body[..]),
+        None => ("404 Not Found", "text/plain", &b"Not Found"[..]),
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    if method != "HEAD" {
+        let _ = stream.write_all(body).await;
+    }
+    let _ = stream.shutdown().await;
+}
@@ -0,0 +1,190 @@
+//! Pages of a fake Bandcamp artist, label and fan, trimmed down from recorded pages. `{base_url}`
+//! is replaced with the url of the server hosting them.
+use std::io::{Cursor, Write};
+
+use zip::{write::FileOptions, ZipWriter};
+
+pub const ARTIST_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Music | The Mock Racers</title></head>
+<body>
+<div id="customHeaderWrapper">
+    <div class="desktop-header">
+        <a href="{base_url}" referrerpolicy="strict-origin-when-cross-origin"><img src="/img/header.png" width="975" height="180"></a>
+    </div>
+</div>
+<div class="leftMiddleColumns">
+    <ol id="music-grid" class="music-grid">
+        <li class="music-grid-item square first-four"><a href="/album/first-album"><p class="title">First Album</p></a></li>
+    </ol>
+</div>
+</body>
+</html>"#;
+
+pub const MUSIC_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Music | The Mock Racers</title></head>
+<body>
+<div id="customHeaderWrapper">
+    <div class="desktop-header">
+        <a href="{base_url}" referrerpolicy="strict-origin-when-cross-origin"><img src="/img/header.png" width="975" height="180"></a>
+    </div>
+</div>
+<div class="leftMiddleColumns">
+    <ol id="music-grid" class="music-grid" data-client-items="[{&quot;id&quot;:2,&quot;title&quot;:&quot;Single Track&quot;,&quot;type&quot;:&quot;track&quot;,&quot;artist&quot;:null,&quot;page_url&quot;:&quot;/track/single-track&quot;}]">
+        <li class="music-grid-item square first-four"><a href="/album/first-album"><p class="title">First Album</p></a></li>
+    </ol>
+</div>
+</body>
+</html>"#;
+
+pub const LABEL_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Music | Mock Label Records</title></head>
+<body>
+<div id="customHeaderWrapper">
+    <div class="desktop-header">
+        <a href="{base_url}" referrerpolicy="strict-origin-when-cross-origin"><img src="/img/header.png" width="975" height="180"></a>
+    </div>
+</div>
+<div id="pagedata" data-band="{&quot;id&quot;:3,&quot;name&quot;:&quot;Mock Label Records&quot;}"></div>
+<ol id="band-navbar">
+    <li><a href="{base_url}/music">music</a></li>
+    <li><a href="{base_url}/artists">artists</a></li>
+</ol>
+<div class="leftMiddleColumns">
+    <ol id="music-grid" class="music-grid">
+        <li class="music-grid-item square first-four"><a href="/album/first-album"><p class="title">First Album</p></a></li>
+        <li class="music-grid-item square first-four"><a href="/track/single-track"><p class="title">Single Track</p></a></li>
+    </ol>
+</div>
+</body>
+</html>"#;
+
+pub const ALBUM_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>First Album | The Mock Racers</title>
+<script type="text/javascript" src="/bundle/tralbum_head.js" nonce="" data-tralbum="{&quot;item_type&quot;:&quot;album&quot;,&quot;art_id&quot;:null,&quot;artist&quot;:&quot;The Mock Racers&quot;,&quot;current&quot;:{&quot;type&quot;:&quot;album&quot;,&quot;title&quot;:&quot;First Album&quot;,&quot;release_date&quot;:&quot;24 Apr 2020 00:00:00 GMT&quot;},&quot;trackinfo&quot;:[{&quot;duration&quot;:1.5,&quot;title_link&quot;:&quot;/track/opening-lap&quot;,&quot;file&quot;:{&quot;mp3-128&quot;:&quot;{base_url}/stream/1.mp3&quot;},&quot;lyrics&quot;:null,&quot;track_num&quot;:1,&quot;title&quot;:&quot;Opening Lap&quot;},{&quot;duration&quot;:2.5,&quot;title_link&quot;:&quot;/track/pit-stop&quot;,&quot;file&quot;:{&quot;mp3-128&quot;:&quot;{base_url}/stream/2.mp3&quot;},&quot;lyrics&quot;:&quot;Round and round&quot;,&quot;track_num&quot;:2,&quot;title&quot;:&quot;Pit Stop&quot;}],&quot;url&quot;:&quot;{base_url}/album/first-album&quot;}" data-band="{&quot;id&quot;:1,&quot;name&quot;:&quot;The Mock Racers&quot;}"></script>
+</head>
+<body>
+<div id="customHeaderWrapper">
+    <div class="desktop-header">
+        <a href="{base_url}" referrerpolicy="strict-origin-when-cross-origin"><img src="/img/header.png" width="975" height="180"></a>
+    </div>
+</div>
+</body>
+</html>"#;
+
+pub const TRACK_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>Single Track | The Mock Racers</title>
+<script type="text/javascript" src="/bundle/tralbum_head.js" nonce="" data-tralbum="{&quot;item_type&quot;:&quot;track&quot;,&quot;art_id&quot;:null,&quot;artist&quot;:&quot;The Mock Racers&quot;,&quot;freeDownloadPage&quot;:&quot;{base_url}/download/free?id=2&quot;,&quot;current&quot;:{&quot;type&quot;:&quot;track&quot;,&quot;title&quot;:&quot;Single Track&quot;,&quot;release_date&quot;:&quot;05 Jun 2021 00:00:00 GMT&quot;},&quot;trackinfo&quot;:[{&quot;duration&quot;:3.0,&quot;title_link&quot;:&quot;/track/single-track&quot;,&quot;file&quot;:{&quot;mp3-128&quot;:&quot;{base_url}/stream/3.mp3&quot;},&quot;lyrics&quot;:null,&quot;track_num&quot;:null,&quot;title&quot;:&quot;Single Track&quot;}],&quot;url&quot;:&quot;{base_url}/track/single-track&quot;}" data-band="{&quot;id&quot;:1,&quot;name&quot;:&quot;The Mock Racers&quot;}"></script>
+</head>
+<body>
+<div id="customHeaderWrapper">
+    <div class="desktop-header">
+        <a href="{base_url}" referrerpolicy="strict-origin-when-cross-origin"><img src="/img/header.png" width="975" height="180"></a>
+    </div>
+</div>
+</body>
+</html>"#;
+
+/// Cookie of the logged in fan
+pub const FAN_COOKIE: &str = "identity=fan";
+
+pub const FAN_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Mock Fan | Bandcamp</title></head>
+<body>
+<div id="pagedata" data-blob="{&quot;fan_data&quot;:{&quot;fan_id&quot;:7,&quot;username&quot;:&quot;mockfan&quot;},&quot;item_cache&quot;:{&quot;collection&quot;:{&quot;a1&quot;:{&quot;item_url&quot;:&quot;{base_url}/album/first-album&quot;,&quot;item_title&quot;:&quot;First Album&quot;,&quot;band_name&quot;:&quot;The Mock Racers&quot;,&quot;item_type&quot;:&quot;album&quot;,&quot;sale_item_type&quot;:&quot;p&quot;,&quot;sale_item_id&quot;:1},&quot;t2&quot;:{&quot;item_url&quot;:&quot;{base_url}/track/single-track&quot;,&quot;item_title&quot;:&quot;Single Track&quot;,&quot;band_name&quot;:&quot;The Mock Racers&quot;,&quot;item_type&quot;:&quot;track&quot;,&quot;sale_item_type&quot;:&quot;p&quot;,&quot;sale_item_id&quot;:2}}},&quot;collection_data&quot;:{&quot;redownload_urls&quot;:{&quot;p1&quot;:&quot;{base_url}/download/album?id=1&quot;,&quot;p2&quot;:&quot;{base_url}/download/track?id=2&quot;}}}"></div>
+</body>
+</html>"#;
+
+pub const WISHLIST_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Mock Fan | Bandcamp</title></head>
+<body>
+<div id="pagedata" data-blob="{&quot;fan_data&quot;:{&quot;fan_id&quot;:7,&quot;username&quot;:&quot;mockfan&quot;},&quot;item_cache&quot;:{&quot;wishlist&quot;:{&quot;a3&quot;:{&quot;item_url&quot;:&quot;{base_url}/album/second-album&quot;,&quot;item_title&quot;:&quot;Second Album&quot;,&quot;band_name&quot;:&quot;The Mock Racers&quot;,&quot;item_type&quot;:&quot;album&quot;}}},&quot;wishlist_data&quot;:{&quot;item_count&quot;:3,&quot;last_token&quot;:&quot;t3&quot;}}"></div>
+</body>
+</html>"#;
+
+/// Requests of the collection API for the wishlist releases following the page
+pub const WISHLIST_REQUEST_1: &str = r#"{"fan_id":7,"older_than_token":"t3","count":100}"#;
+pub const WISHLIST_REQUEST_2: &str = r#"{"fan_id":7,"older_than_token":"t4","count":100}"#;
+
+pub const WISHLIST_ITEMS_1: &str = r#"{"items":[{"item_url":"{base_url}/album/third-album","item_title":"Third Album","band_name":"The Mock Racers","item_type":"album"}],"more_available":true,"last_token":"t4"}"#;
+pub const WISHLIST_ITEMS_2: &str = r#"{"items":[{"item_url":"{base_url}/album/fourth-album","item_title":"Fourth Album","band_name":"The Mock Racers","item_type":"album"}],"more_available":false,"last_token":"t5"}"#;
+
+pub const ALBUM_DOWNLOAD_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Download First Album | Bandcamp</title></head>
+<body>
+<div id="pagedata" data-blob="{&quot;digital_items&quot;:[{&quot;title&quot;:&quot;First Album&quot;,&quot;downloads&quot;:{&quot;mp3-320&quot;:{&quot;size_mb&quot;:&quot;0.1MB&quot;,&quot;url&quot;:&quot;{base_url}/purchase/1-mp3-320.zip&quot;},&quot;flac&quot;:{&quot;size_mb&quot;:&quot;0.2MB&quot;,&quot;url&quot;:&quot;{base_url}/purchase/1-flac.zip&quot;}}}]}"></div>
+</body>
+</html>"#;
+
+pub const TRACK_DOWNLOAD_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Download Single Track | Bandcamp</title></head>
+<body>
+<div id="pagedata" data-blob="{&quot;digital_items&quot;:[{&quot;title&quot;:&quot;Single Track&quot;,&quot;downloads&quot;:{&quot;mp3-320&quot;:{&quot;size_mb&quot;:&quot;0.1MB&quot;,&quot;url&quot;:&quot;{base_url}/purchase/2.mp3&quot;},&quot;flac&quot;:{&quot;size_mb&quot;:&quot;0.1MB&quot;,&quot;url&quot;:&quot;{base_url}/purchase/2.flac&quot;}}}]}"></div>
+</body>
+</html>"#;
+
+/// Free album, downloaded from its download page
+pub const FREE_ALBUM_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>Free Album | The Mock Racers</title>
+<script type="text/javascript" src="/bundle/tralbum_head.js" nonce="" data-tralbum="{&quot;item_type&quot;:&quot;album&quot;,&quot;art_id&quot;:null,&quot;artist&quot;:&quot;The Mock Racers&quot;,&quot;freeDownloadPage&quot;:&quot;{base_url}/download/free?id=4&quot;,&quot;current&quot;:{&quot;type&quot;:&quot;album&quot;,&quot;title&quot;:&quot;Free Album&quot;,&quot;release_date&quot;:&quot;24 Apr 2020 00:00:00 GMT&quot;,&quot;require_email&quot;:null},&quot;trackinfo&quot;:[{&quot;duration&quot;:1.5,&quot;title_link&quot;:&quot;/track/opening-lap&quot;,&quot;file&quot;:{&quot;mp3-128&quot;:&quot;{base_url}/stream/1.mp3&quot;},&quot;lyrics&quot;:null,&quot;track_num&quot;:1,&quot;title&quot;:&quot;Opening Lap&quot;},{&quot;duration&quot;:2.5,&quot;title_link&quot;:&quot;/track/pit-stop&quot;,&quot;file&quot;:{&quot;mp3-128&quot;:&quot;{base_url}/stream/2.mp3&quot;},&quot;lyrics&quot;:null,&quot;track_num&quot;:2,&quot;title&quot;:&quot;Pit Stop&quot;}],&quot;url&quot;:&quot;{base_url}/album/free-album&quot;}" data-band="{&quot;id&quot;:1,&quot;name&quot;:&quot;The Mock Racers&quot;}"></script>
+</head>
+<body>
+</body>
+</html>"#;
+
+/// Free track release, whose download is only sent by email
+pub const EMAIL_TRACK_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>Email Track | The Mock Racers</title>
+<script type="text/javascript" src="/bundle/tralbum_head.js" nonce="" data-tralbum="{&quot;item_type&quot;:&quot;track&quot;,&quot;art_id&quot;:null,&quot;artist&quot;:&quot;The Mock Racers&quot;,&quot;freeDownloadPage&quot;:null,&quot;current&quot;:{&quot;type&quot;:&quot;track&quot;,&quot;title&quot;:&quot;Email Track&quot;,&quot;release_date&quot;:&quot;05 Jun 2021 00:00:00 GMT&quot;,&quot;require_email&quot;:1},&quot;trackinfo&quot;:[{&quot;duration&quot;:3.0,&quot;title_link&quot;:&quot;/track/email-track&quot;,&quot;file&quot;:{&quot;mp3-128&quot;:&quot;{base_url}/stream/3.mp3&quot;},&quot;lyrics&quot;:null,&quot;track_num&quot;:null,&quot;title&quot;:&quot;Email Track&quot;}],&quot;url&quot;:&quot;{base_url}/track/email-track&quot;}" data-band="{&quot;id&quot;:1,&quot;name&quot;:&quot;The Mock Racers&quot;}"></script>
+</head>
+<body>
+</body>
+</html>"#;
+
+pub const FREE_DOWNLOAD_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head><title>Download Free Album | Bandcamp</title></head>
+<body>
+<div id="pagedata" data-blob="{&quot;digital_items&quot;:[{&quot;title&quot;:&quot;Free Album&quot;,&quot;downloads&quot;:{&quot;mp3-320&quot;:{&quot;size_mb&quot;:&quot;0.1MB&quot;,&quot;url&quot;:&quot;{base_url}/free/4-mp3-320.zip&quot;},&quot;flac&quot;:{&quot;size_mb&quot;:&quot;0.2MB&quot;,&quot;url&quot;:&quot;{base_url}/free/4-flac.zip&quot;}}}]}"></div>
+</body>
+</html>"#;
+
+/// Fake MP3 streams, of distinct sizes. They are large enough for an id3 tag to stay within the
+/// allowed file size difference.
+pub const MP3_1: &[u8] = &[0xff; 32 * 1024];
+pub const MP3_2: &[u8] = &[0xfb; 64 * 1024];
+pub const MP3_3: &[u8] = &[0xf3; 16 * 1024];
+pub const FLAC_1: &[u8] = &[0x61; 40 * 1024];
+pub const FLAC_2: &[u8] = &[0x62; 72 * 1024];
+pub const TRACK_FLAC: &[u8] = &[0x66; 24 * 1024];
+
+/// Returns the archive of the tracks of the album, as served to the fans who bought it
+pub fn album_zip() -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in [
+        (
+            "The Mock Racers - First Album - 01 Opening Lap.flac",
+            FLAC_1,
+        ),
+        ("The Mock Racers - First Album - 02 Pit Stop.flac", FLAC_2),
+        ("cover.jpg", &[0xd8; 1024][..]),
+    ] {
+        writer.start_file(name, FileOptions::default()).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
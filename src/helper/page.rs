@@ -0,0 +1,106 @@
+//! HTML page parsing
+use scraper::{Html, Selector};
+
+lazy_static! {
+    /// Link to the artist page in the page header
+    static ref BAND_LINK: Selector = Selector::parse(".desktop-header > a[href]").unwrap();
+    /// Any element referencing another page
+    static ref LINK: Selector = Selector::parse("[href]").unwrap();
+    /// Structured data (schema.org) block
+    static ref LD_JSON: Selector = Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+}
+
+/// A parsed HTML page, giving access to the data Bandcamp embeds in its markup.
+///
+/// Attribute values and text are returned with their character references (eg. `&quot;`,
+/// `&#39;`) decoded.
+pub struct Page {
+    document: Html,
+}
+
+impl Page {
+    /// Parse the HTML source code of a page
+    pub fn parse(raw_html: &str) -> Self {
+        Self {
+            document: Html::parse_document(raw_html),
+        }
+    }
+
+    /// Returns the value of the first `name` attribute of the page (eg. `data-tralbum`)
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        let selector = Selector::parse(&format!("[{}]", name)).ok()?;
+        self.document.select(&selector).next()?.value().attr(name)
+    }
+
+    /// Returns the contents of the `application/ld+json` structured data block
+    pub fn ld_json(&self) -> Option<String> {
+        let script = self.document.select(&LD_JSON).next()?;
+        Some(script.text().collect())
+    }
+
+    /// Returns the url of the artist page, from the page header
+    pub fn band_url(&self) -> Option<&str> {
+        self.document
+            .select(&BAND_LINK)
+            .next()?
+            .value()
+            .attr("href")
+    }
+
+    /// Returns the target of every link of the page, in document order
+    pub fn links(&self) -> Vec<&str> {
+        self.document
+            .select(&LINK)
+            .filter_map(|element| element.value().attr("href"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decodes_attributes() {
+        let page = Page::parse(
+            r#"<div data-tralbum="{&quot;title&quot;:&quot;Don&#39;t &amp; Won&#x27;t &lt;3&quot;}"></div>"#,
+        );
+
+        let msg = "should decode named and numeric character references";
+        let expected = Some(r#"{"title":"Don't & Won't <3"}"#);
+        let actual = page.attribute("data-tralbum");
+        assert_eq!(actual, expected, "{}", msg);
+
+        let msg = "should return None for a missing attribute";
+        let actual = page.attribute("data-band");
+        assert_eq!(actual, None, "{}", msg);
+    }
+
+    #[test]
+    fn gets_band_url() {
+        let page = Page::parse(
+            r#"<div class="desktop-header">
+            <a href="https://theracers.bandcamp.com" referrerpolicy="strict-origin-when-cross-origin"><img src="https://f4.bcbits.com/img/0024058603_100.png" width="975" height="180"></a>
+
+        </div>"#,
+        );
+
+        let expected = Some("https://theracers.bandcamp.com");
+        let actual = page.band_url();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn gets_ld_json() {
+        let page = Page::parse(
+            r#"<head><script type="application/ld+json">
+            {"@type":"MusicAlbum","name":"Final Lap"}
+            </script></head>"#,
+        );
+
+        let msg = "should return the structured data block contents";
+        let actual = page.ld_json().map(|json| json.trim().to_string());
+        let expected = Some(String::from(r#"{"@type":"MusicAlbum","name":"Final Lap"}"#));
+        assert_eq!(actual, expected, "{}", msg);
+    }
+}
@@ -97,3 +97,26 @@ pub const ALBUM_HTML: &str = r#"<div class="desktop-header">
 
 /// tralbum JSON string
 pub const TRALBUM_DATA: &str = r#"{"album_upsell_url":null,"item_type":"track","PAID":2,"freeDownloadPage":null,"hasAudio":true,"is_band_member":null,"client_id_sig":"+Nw4YECXl5IYa4ZqCzC2A2k+nDU=","id":350943074,"licensed_version_ids":null,"art_id":2129006133,"album_is_preorder":null,"album_url":null,"tralbum_subscriber_only":null,"is_private_stream":null,"has_discounts":false,"packages":null,"album_release_date":null,"playing_from":"track page","artist":"The Racers","preorder_count":null,"items_purchased":null,"is_bonus":null,"defaultPrice":1.0,"current":{"type":"track","new_desc_format":1,"minimum_price_nonzero":1.0,"new_date":"24 Apr 2020 10:46:40 GMT","pending_encodings_id":null,"license_type":1,"download_desc_id":null,"is_set_price":null,"audit":0,"encodings_id":1928142095,"selling_band_id":1173700968,"download_pref":2,"lyrics":null,"require_email":null,"about":null,"streaming":1,"preorder_download":null,"isrc":null,"album_id":null,"file_name":null,"require_email_0":null,"minimum_price":1.0,"art_id":2129006133,"auto_repriced":null,"credits":"Produced by : \nAlexis Bidaud\nLeo Archer","private":null,"mod_date":"04 Sep 2021 09:02:48 GMT","release_date":"24 Apr 2020 00:00:00 GMT","band_id":1173700968,"artist":null,"killed":null,"publish_date":"24 Apr 2020 10:46:44 GMT","title":"Final Lap","id":350943074,"track_number":null,"set_price":1.0},"package_associated_license_id":null,"is_purchased":null,"for the curious":"https://bandcamp.com/help/audio_basics#steal https://bandcamp.com/terms_of_use","has_video":null,"play_cap_data":{"streaming_limits_enabled":true,"streaming_limit":3},"last_subscription_item":null,"FREE":1,"trackinfo":[{"video_caption":null,"duration":311.327,"title_link":"/track/final-lap","file":{"mp3-128":"https://t4.bcbits.com/stream/8e264c1615dca0ab965f6e3b320ea9da/mp3-128/350943074?p=0&ts=1631806573&t=1c02736b48124fcde7acb2743812134a3e4b25de&token=1631806573_49c0e23c8c2b500fcf206501d703e81527972f5b"},"track_license_id":null,"is_capped":false,"video_id":null,"sizeof_lyrics":0,"video_featured":null,"has_lyrics":false,"license_type":1,"track_id":350943074,"video_source_type":null,"encodings_id":1928142095,"alt_link":null,"lyrics":null,"has_info":true,"video_source_id":null,"track_num":null,"encoding_error":null,"is_draft":false,"is_downloadable":true,"streaming":1,"video_mobile_url":null,"album_preorder":false,"private":null,"encoding_pending":null,"has_free_download":null,"title":"Final Lap","artist":null,"video_poster_url":null,"unreleased_track":false,"id":350943074,"play_count":0,"free_album_download":false}],"url":"https://theracers.bandcamp.com/track/final-lap"}"#;
+
+/// album page structured data, without tralbum data
+pub const LD_JSON_ALBUM_HTML: &str = r#"<script type="application/ld+json">
+{"@type":"MusicAlbum","@id":"https://theracers.bandcamp.com/album/the-midnight-by-the-racers-i","additionalProperty":[{"@type":"PropertyValue","name":"art_id","value":1419925404}],"name":"The Midnight By The Racers I","dateModified":"02 Sep 2021 14:19:55 GMT","datePublished":"10 Jul 2020 00:00:00 GMT","description":"Remixes of The Midnight tracks","keywords":["Electronic","synthwave","Paris"],"image":"https://f4.bcbits.com/img/a1419925404_10.jpg","byArtist":{"@type":"MusicGroup","name":"The Racers","@id":"https://theracers.bandcamp.com"},"publisher":{"@type":"MusicGroup","@id":"https://theracers.bandcamp.com","name":"The Racers"},"numTracks":2,"track":{"@type":"ItemList","numberOfItems":2,"itemListElement":[{"@type":"ListItem","position":2,"item":{"@type":"MusicRecording","@id":"https://theracers.bandcamp.com/track/deep-blue","name":"Deep Blue","duration":"P00H04M12S","additionalProperty":[{"@type":"PropertyValue","name":"track_id","value":2436474853},{"@type":"PropertyValue","name":"file_mp3-128","value":"https://t4.bcbits.com/stream/deep-blue"}]}},{"@type":"ListItem","position":1,"item":{"@type":"MusicRecording","@id":"https://theracers.bandcamp.com/track/tunnel-vision","name":"Tunnel Vision","duration":"P00H05M11S","additionalProperty":[{"@type":"PropertyValue","name":"duration_secs","value":311.327},{"@type":"PropertyValue","name":"file_mp3-128","value":"https://t4.bcbits.com/stream/tunnel-vision"}]}}]}}
+</script>"#;
+
+/// track page structured data, published by a label
+pub const LD_JSON_TRACK: &str = r#"{"@type":"MusicRecording","@id":"https://theracers.bandcamp.com/track/final-lap","name":"Final Lap","datePublished":"24 Apr 2020 00:00:00 GMT","keywords":"electronic, synthwave","byArtist":{"@type":"MusicGroup","name":"The Racers"},"publisher":{"@type":"MusicGroup","name":"Nightride Records"},"duration":"P00H05M11S","additionalProperty":[{"@type":"PropertyValue","name":"file_mp3-128","value":"https://t4.bcbits.com/stream/final-lap"}]}"#;
+
+/// label music page, with lazy loaded grid items
+pub const CLIENT_ITEMS_HTML: &str = r#"<div class="desktop-header">
+    <a href="https://nightride.bandcamp.com" referrerpolicy="strict-origin-when-cross-origin"><img src="https://f4.bcbits.com/img/0024058604_100.png" width="975" height="180"></a>
+</div>
+<ol id="band-navbar">
+    <li><a href="/music">music</a></li>
+    <li><a href="/artists">artists</a></li>
+</ol>
+<div id="pagedata" data-band="{&quot;id&quot;:2512826312,&quot;name&quot;:&quot;Nightride Records&quot;}"></div>
+<ol id="music-grid" class="editable-grid music-grid columns-4 public" data-edit-callback="/music_reorder" data-client-items="[{&quot;id&quot;:1836482729,&quot;title&quot;:&quot;Second Wind&quot;,&quot;type&quot;:&quot;album&quot;,&quot;art_id&quot;:3826738209,&quot;artist&quot;:null,&quot;band_id&quot;:2512826312,&quot;page_url&quot;:&quot;/album/second-wind&quot;,&quot;publish_date&quot;:&quot;01 Feb 2021 00:00:00 GMT&quot;},{&quot;id&quot;:2318827190,&quot;title&quot;:&quot;Third Gear&quot;,&quot;type&quot;:&quot;track&quot;,&quot;art_id&quot;:3826738210,&quot;artist&quot;:null,&quot;band_id&quot;:2512826312,&quot;page_url&quot;:&quot;/track/third-gear&quot;,&quot;publish_date&quot;:&quot;01 Mar 2021 00:00:00 GMT&quot;},{&quot;id&quot;:2318827191,&quot;title&quot;:&quot;Guest Release&quot;,&quot;type&quot;:&quot;album&quot;,&quot;art_id&quot;:3826738211,&quot;artist&quot;:&quot;The Racers&quot;,&quot;band_id&quot;:1173700968,&quot;page_url&quot;:&quot;https://theracers.bandcamp.com/album/guest-release?label=2512826312&amp;tab=music&quot;,&quot;publish_date&quot;:&quot;01 Apr 2021 00:00:00 GMT&quot;}]">
+    <li data-item-id="album-1836482728" class="music-grid-item square first-four">
+        <a href="/album/first-light"><p class="title">First Light</p></a>
+    </li>
+</ol>"#;
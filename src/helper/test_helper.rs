@@ -0,0 +1,59 @@
+//! Fixtures shared by the tests
+use std::{fs, path::PathBuf};
+
+use chrono::Utc;
+use futures::channel::mpsc;
+
+use crate::{
+    core::Logger,
+    model::{Album, Track},
+};
+
+/// Returns a Logger whose UI messages are discarded
+pub fn test_logger() -> Logger {
+    let (sender, _) = mpsc::channel(1);
+    Logger::new(sender, false)
+}
+
+/// Returns a fresh directory for test output
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "bandcamp_downloader_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Returns the album "Night Drive" by "The Racers" saved to the folder of `folder_format`, with a
+/// track per title, numbered from 1 and named from `file_name_format`
+pub fn test_album(folder_format: &str, titles: &[&str], file_name_format: &str) -> Album {
+    let mut album = Album::new(
+        "The Racers",
+        None,
+        None,
+        "Night Drive",
+        Utc::now(),
+        folder_format,
+    );
+    album.tracks = titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            let number = i as u32 + 1;
+            let title = title.to_string();
+            Track::new(
+                &album,
+                0.0,
+                None,
+                String::new(),
+                number,
+                title,
+                file_name_format,
+            )
+        })
+        .collect();
+    album
+}
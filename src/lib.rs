@@ -1,52 +1,54 @@
 //! Functions for downloading mp3s from bandcamp
-use std::{collections::HashSet, path::Path, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
 
-use futures::channel::mpsc;
+use chrono::Utc;
 use futures::future::join_all;
-use regex::Regex;
 use tokio::{fs, io::AsyncWriteExt};
 
 use crate::{
-    core::{playlist, tag},
+    core::{
+        archive, dedup, library, library::AlbumSource, playlist, sidecar, tag, History, HookData,
+        Logger, ProgressTracker, ReleaseType, Session,
+    },
     error::Error,
 };
-use model::{Album, Track};
+use model::{Album, PurchaseLink, Track};
 use settings::UserSettings;
-use ui::{LogLevel, Message, Progress};
+use source::{collection, Release, Source};
+use ui::TrackStatus;
 
 #[macro_use]
 extern crate lazy_static;
 
+pub mod cli;
 pub mod core;
 mod error;
 mod helper;
 mod model;
 pub mod settings;
+mod source;
 pub mod ui;
 
 /// A [`std::Result`](std::result::Result) alias where the [`Err`] case is `bandcamp_downloader::Error`.
 pub type Result<T> = std::result::Result<T, error::Error>;
 
 lazy_static! {
-    /// Band url parsing
-    static ref BAND_RE: Regex = Regex::new(r#"(?m)"desktop-header">\s*<a href="(?P<url>.*?)".*?</a>"#).unwrap();
-    /// album and track url parsing
-    static ref ALBUM_RE: Regex = Regex::new("href=\"(?P<album_url>/(album|track)/.*?)\"").unwrap();
-    // html escape replacements
-    // TODO: use one of the libraries dedicated to this task?
-    // " is replaced with &quot;
-    static ref HTML_QUOTE_RE: Regex = Regex::new(r#"&quot;"#).unwrap();
-    // & is replaced with &amp;
-    static ref HTML_AMP_RE: Regex = Regex::new(r#"&amp;"#).unwrap();
-    // < is replaced with &lt;
-    static ref HTML_LT_RE: Regex = Regex::new(r#"&lt;"#).unwrap();
-    // > is replaced with &gt;
-    static ref HTML_GT_RE: Regex = Regex::new(r#"&gt;"#).unwrap();
-}
-
-/// Get text from a url using the reqwest shortcut method
+    /// HTTP client shared by every request, so they reuse its connections
+    static ref CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+/// Returns the shared HTTP client
+fn client() -> &'static reqwest::Client {
+    &CLIENT
+}
+
+/// Get text from a url using the shared client
 async fn get_url_text(url: &str) -> Result<String> {
-    reqwest::get(url).await?.text().await.map_err(From::from)
+    client_get_url_text(client(), url).await
 }
 
 /// Get text from a url using a reqwest Client
@@ -62,7 +64,7 @@ async fn client_get_url_text(client: &reqwest::Client, url: &str) -> Result<Stri
 
 /// Get the content_type and response body (as a vec of bytes) from a url
 async fn get_url_bytes(url: &str) -> Result<(Option<String>, Vec<u8>)> {
-    let res = reqwest::get(url).await?;
+    let res = client().get(url).send().await?;
 
     let content_type = res
         .headers()
@@ -75,129 +77,97 @@ async fn get_url_bytes(url: &str) -> Result<(Option<String>, Vec<u8>)> {
     Ok((content_type, bytes))
 }
 
-/// Get artist "music" bandcamp page (http://artist.bandcamp.com/music)
-async fn get_music_page_url(client: &reqwest::Client, url: &str) -> Result<String> {
-    // Retrieve URL HTML source code
-    let raw_html = match client_get_url_text(client, url).await {
-        Ok(res) => res,
-        Err(e) => {
-            println!("Could not retrieve data for {}", url);
-            return Err(e);
-        }
-    };
-
-    // Get artist "music" bandcamp page (http://artist.bandcamp.com/music)
-    BAND_RE.captures(&raw_html)
-        .and_then(|captures| captures.name("url"))
-        .map(|url| format!("{}{}", url.as_str(), "/music"))
-        .ok_or_else(|| {
-            println!("No discography could be found on {}. Try to uncheck the \"Download artist discography\" option", url);
-            Error::NoDiscography
-        })
-}
-
-/// Returns the artist's discography from any URL (artist, album, track).
-async fn get_disco_urls(client: &reqwest::Client, url: &str) -> Result<Vec<String>> {
-    println!("Retrieving artist discography from {}", url);
-
-    // Get artist "music" bandcamp page (http://artist.bandcamp.com/music)
-    let music_page_url = match get_music_page_url(client, url).await {
-        Ok(res) => res,
-        Err(e) => {
-            println!("Could not retrieve music page url for {}", url);
-            return Err(e);
-        }
-    };
-
-    // Retrieve artist "music" page HTML source code
-    let raw_html = match client_get_url_text(client, &music_page_url).await {
-        Ok(res) => res,
-        Err(e) => {
-            println!("Could not retrieve HTML for {}", music_page_url);
-            return Err(e);
-        }
-    };
-
-    let mut albums_urls = Vec::new();
-    match helper::get_albums_url(&raw_html) {
-        Err(_) => {
-            println!("No referred album could be found on {}. Try to uncheck the \"Download artist discography\" option" ,music_page_url);
-        }
-        Ok(found_albums) => {
-            albums_urls.extend(found_albums);
-        }
-    }
-
-    if albums_urls.is_empty() {
-        // This seem to be a one-album artist with no "music" page => URL redirects to the unique album URL
-        albums_urls.push(url.to_string());
-    }
-
-    Ok(albums_urls)
-}
-
-/// Returns all discography lists from a set of URLs (artist, album, track).
-async fn get_artist_discography(urls: &HashSet<&str>) -> Vec<String> {
-    // TODO: proxy support
-    let client = reqwest::Client::new();
-
+/// Returns the releases found at a set of URLs, using the first of the `sources` able to handle
+/// each of them. If `discography` is true, returns the artists discography instead.
+async fn get_releases(
+    sources: &[Box<dyn Source>],
+    urls: &HashSet<String>,
+    discography: bool,
+    logger: &Logger,
+) -> Vec<Release> {
     let tasks: Vec<_> = urls
         .iter()
-        .map(|url| get_disco_urls(&client, url))
+        .filter_map(|url| match source::find(sources, url) {
+            Some(source) => {
+                logger.verbose(format!("Using {} source for {}", source.name(), url));
+                Some(source.releases(url, discography, logger))
+            }
+            None => {
+                logger.warn(format!("Unsupported url {}, it will be ignored", url));
+                None
+            }
+        })
         .collect();
 
     let results = join_all(tasks).await;
-    let albums_urls: HashSet<_> = results.into_iter().flatten().flatten().collect();
 
-    albums_urls.into_iter().collect()
+    // A release may be found from several urls, keep the label it was found on if any
+    let mut releases: HashMap<String, Release> = HashMap::new();
+    for release in results.into_iter().flatten().flatten() {
+        match releases.get(&release.url) {
+            Some(known) if release.label.is_none() || known.label.is_some() => {}
+            _ => {
+                releases.insert(release.url.clone(), release);
+            }
+        }
+    }
+
+    releases.into_values().collect()
 }
 
-/// Returns the albums located at the specified URLs.
+/// Returns the albums of the specified releases. The releases found on a label page are only kept
+/// if their artist matches [`UserSettings::label_artist_filter`].
 async fn get_albums(
-    urls: HashSet<&str>,
-    save_dir: &str,
-    filename_format: &str,
-) -> Result<Vec<Album>> {
-    let client = reqwest::Client::new();
-
-    let tasks = urls.iter().map(|url| {
-        let client = &client;
-        async move {
-            println!("Retrieving album data for {}", url);
-
-            // Retrieve URL HTML source code
-            // TODO proxy support
-            // TODO cancellation
-
-            let raw_html = match client_get_url_text(client, url).await {
-                Ok(res) => res,
-                Err(_) => {
-                    println!("Could not retrieve html for {}", url);
-                    return None;
-                }
-            };
+    sources: &[Box<dyn Source>],
+    releases: Vec<Release>,
+    settings: &UserSettings,
+    logger: &Logger,
+) -> Vec<Album> {
+    let save_dir = settings.downloads_path.to_string_lossy();
+    let tasks = releases.iter().filter_map(|release| {
+        let url = &release.url;
+        let source = source::find(sources, url)?;
+        let save_dir = &save_dir;
+        Some(async move {
+            logger.info(format!("Retrieving album data for {}", url));
 
             // Get info on album
-            let album = match helper::get_album(&raw_html, save_dir, filename_format) {
+            let mut album = match source
+                .album(release, save_dir, &settings.file_name_format)
+                .await
+            {
                 Ok(a) => a,
-                Err(_) => {
-                    println!("Could not retrieve album info for {}", url);
+                Err(e) => {
+                    logger.error(format!("Could not retrieve album info for {}: {}", url, e));
                     return None;
                 }
             };
 
+            album.url = url.clone();
+
+            if release.label.is_some()
+                && !helper::matches_artist(&album.artist, &settings.label_artist_filter)
+            {
+                logger.verbose(format!(
+                    r#"Album "{}" by {} does not match the artist filter, it will not be downloaded"#,
+                    album.title, album.artist
+                ));
+                return None;
+            }
+
             if album.tracks.is_empty() {
-                println!("No tracks found for {}, album will not be downloaded", url);
+                logger.warn(format!(
+                    "No tracks found for {}, album will not be downloaded",
+                    url
+                ));
                 return None;
             }
 
             Some(album)
-        }
+        })
     });
 
-    let albums = join_all(tasks).await.into_iter().flatten().collect();
-
-    Ok(albums)
+    join_all(tasks).await.into_iter().flatten().collect()
 }
 
 /// Barebones http protocol add.
@@ -211,162 +181,403 @@ fn prepend_http(url: &str) -> String {
     }
 }
 
-/// Fetch albums data from the URLs specified.
+/// Get the size (in bytes) of the file at a url, using a HEAD request
+async fn get_file_size(client: &reqwest::Client, url: &str) -> Result<u64> {
+    let res = client.head(url).send().await?.error_for_status()?;
+
+    // the Content-Length header is read directly: the body of a HEAD response is always empty
+    res.headers()
+        .get(reqwest::header::CONTENT_LENGTH)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.parse().ok())
+        .ok_or_else(|| Error::Api(format!("No content length for {}", url)))
+}
+
+/// Retrieve the size of every track of the supplied albums before downloading them, storing it
+/// in [`Track::size`]. The size of the archive of a purchased album is split between its tracks.
+async fn retrieve_files_size(albums: &mut [Album], logger: &Logger) {
+    let client = client();
+    logger.info("Computing files size...");
+
+    for album in albums.iter_mut() {
+        // a purchased album is downloaded as a whole, in its archive
+        if let Some(purchase) = &album.purchase {
+            match get_file_size(client, &purchase.url).await {
+                Ok(size) => {
+                    let tracks: Vec<_> = album.tracks.iter_mut().filter(|t| t.selected).collect();
+                    let shares = helper::split_bytes(size, tracks.len());
+                    for (track, share) in tracks.into_iter().zip(shares) {
+                        track.size = Some(share);
+                    }
+                }
+                Err(e) => logger.warn(format!(
+                    r#"Failed to retrieve the size of the album "{}": {}"#,
+                    album.title, e
+                )),
+            }
+        } else {
+            let tasks = album
+                .tracks
+                .iter_mut()
+                .filter(|track| track.selected)
+                .map(|track| {
+                    let client = &client;
+                    async move {
+                        match get_file_size(client, &track.mp3_url).await {
+                            Ok(size) => track.size = Some(size),
+                            Err(e) => logger.warn(format!(
+                                r#"Failed to retrieve the size of the track "{}": {}"#,
+                                track.title, e
+                            )),
+                        }
+                    }
+                });
+            join_all(tasks).await;
+        }
+
+        logger.verbose(format!(
+            r#"Album "{}": {}"#,
+            album.title,
+            helper::format_bytes(album.size())
+        ));
+    }
+
+    let total: u64 = albums.iter().map(Album::size).sum();
+    logger.info(format!(
+        "Total size to download: {}",
+        helper::format_bytes(total)
+    ));
+}
+
+/// Fetch albums data from the URLs specified, using the first of the `sources` able to handle
+/// each of them.
 async fn fetch_urls(
+    sources: &[Box<dyn Source>],
     urls: &str,
-    discography: bool,
-    save_dir: &str,
-    filename_format: &str,
+    settings: &UserSettings,
+    logger: &Logger,
 ) -> Vec<Album> {
-    let retrieve_file_size = false;
     let urls: HashSet<_> = urls.lines().map(prepend_http).collect();
-    let urls: HashSet<_> = urls.iter().map(|s| s.as_str()).collect();
+    let discography = settings.download_artist_discography;
 
-    // Get info on albums
     // Get URLs of albums to download
     if discography {
-        println!("collecting discography urls");
-        let url_list = get_artist_discography(&urls).await;
-        let urls = url_list.iter().map(|s| s.as_str()).collect();
+        logger.verbose("Collecting discography urls");
+    }
+    let releases = get_releases(sources, &urls, discography, logger).await;
 
-        get_albums(urls, save_dir, filename_format)
-            .await
-            .expect("FIXME")
-    } else {
-        get_albums(urls, save_dir, filename_format)
-            .await
-            .expect("FIXME")
+    fetch_releases(sources, releases, settings, logger).await
+}
+
+/// Fetch albums data of the releases of the followed artists at `urls` which are missing from
+/// the download `history`.
+async fn fetch_new_releases(
+    sources: &[Box<dyn Source>],
+    urls: &[String],
+    history: &History,
+    settings: &UserSettings,
+    logger: &Logger,
+) -> Vec<Album> {
+    let urls: HashSet<_> = urls.iter().map(|url| prepend_http(url)).collect();
+    let mut releases = get_releases(sources, &urls, true, logger).await;
+    releases.retain(|release| !history.contains(&release.url));
+
+    fetch_releases(sources, releases, settings, logger).await
+}
+
+/// Fetch albums data of the `releases`, keeping the ones matching the release filters
+async fn fetch_releases(
+    sources: &[Box<dyn Source>],
+    releases: Vec<Release>,
+    settings: &UserSettings,
+    logger: &Logger,
+) -> Vec<Album> {
+    // Get info on albums
+    let mut albums = get_albums(sources, releases, settings, logger).await;
+
+    // Keep the albums matching the release filters
+    match settings.release_filter.compile() {
+        Ok(filter) => albums.retain(|album| match filter.rejection(album) {
+            Some(reason) => {
+                logger.info(format!(
+                    r#"Album "{}" filtered out ({}), it will not be downloaded"#,
+                    album.title, reason
+                ));
+                false
+            }
+            None => true,
+        }),
+        Err(e) => {
+            logger.error(e);
+            return Vec::new();
+        }
+    }
+
+    // Keep the tracks selected on the command line
+    if !settings.track_selection.is_empty() {
+        for album in albums.iter_mut() {
+            settings.track_selection.apply(album);
+        }
+        albums.retain(|album| {
+            let selected = album.selected_tracks().next().is_some();
+            if !selected {
+                logger.info(format!(
+                    r#"No track of album "{}" is selected, it will not be downloaded"#,
+                    album.title
+                ));
+            }
+            selected
+        });
+    }
+
+    // Download the releases bought by the user instead of their streams
+    let purchased = attach_purchases(&mut albums, settings, logger).await;
+    if settings.download_free_releases {
+        attach_free_downloads(&mut albums, &purchased, settings, logger).await;
+    }
+
+    // Get the size of the files to download
+    if settings.retrieve_files_size {
+        retrieve_files_size(&mut albums, logger).await;
+    }
+
+    albums
+}
+
+/// Set the purchase download of `album`: track releases are downloaded as a single file, albums
+/// as an archive of their tracks. The tracks are saved in the format of the purchase.
+fn attach_purchase(album: &mut Album, link: PurchaseLink) {
+    let is_track = ReleaseType::of(album) == ReleaseType::Track;
+    for track in album.tracks.iter_mut() {
+        track.path = Path::new(&track.path)
+            .with_extension(link.format.extension())
+            .to_string_lossy()
+            .into();
+        if is_track {
+            track.mp3_url = link.url.clone();
+            track.size = None;
+        }
+    }
+    if !is_track {
+        album.purchase = Some(link);
     }
 }
 
+/// Find the `albums` bought by the user in their collection, and attach their purchase download in
+/// the format set in [`UserSettings::preferred_format`], or the closest available one. Returns the
+/// [`helper::url_key`] of the albums whose purchase is attached. Does nothing without a Bandcamp
+/// session.
+async fn attach_purchases(
+    albums: &mut [Album],
+    settings: &UserSettings,
+    logger: &Logger,
+) -> HashSet<String> {
+    let session = match Session::from_settings(settings) {
+        Ok(Some(session)) => session,
+        Ok(None) => return HashSet::new(),
+        Err(e) => {
+            logger.error(format!("Could not read the Bandcamp session: {}", e));
+            return HashSet::new();
+        }
+    };
+    let fan_url = settings.fan_page_url.trim();
+    if fan_url.is_empty() {
+        logger.warn("No fan page is set, the purchased releases cannot be found");
+        return HashSet::new();
+    }
+
+    let items = match collection::purchases(&session, &prepend_http(fan_url), logger).await {
+        Ok(items) => items,
+        Err(e) => {
+            logger.error(format!("Could not retrieve the collection: {}", e));
+            return HashSet::new();
+        }
+    };
+    let items: HashMap<_, _> = items
+        .into_iter()
+        .map(|item| (helper::url_key(&item.url), item))
+        .collect();
+
+    let formats = settings.preferred_format.fallback_order();
+    let (formats, session) = (&formats, &session);
+    let tasks = albums.iter_mut().filter_map(|album| {
+        let item = items.get(&helper::url_key(&album.url))?;
+        Some(async move {
+            match collection::purchase_link(session, item, formats).await {
+                Ok(Some(link)) => {
+                    logger.info(format!(
+                        r#"Album "{}" was purchased, it will be downloaded in {}"#,
+                        album.title, link.format
+                    ));
+                    attach_purchase(album, link);
+                    return Some(helper::url_key(&album.url));
+                }
+                Ok(None) => logger.warn(format!(
+                    r#"Purchased album "{}" has no download, its stream will be downloaded"#,
+                    album.title
+                )),
+                Err(e) => logger.error(format!(
+                    r#"Could not retrieve the purchase download of "{}": {}"#,
+                    album.title, e
+                )),
+            }
+            None
+        })
+    });
+    join_all(tasks).await.into_iter().flatten().collect()
+}
+
+/// Attach the download of the free (or name your price) `albums` not `purchased` by the user, by
+/// [`helper::url_key`], in the format set in [`UserSettings::preferred_format`], or the closest
+/// available one. The releases whose download is only sent by email keep their streams.
+async fn attach_free_downloads(
+    albums: &mut [Album],
+    purchased: &HashSet<String>,
+    settings: &UserSettings,
+    logger: &Logger,
+) {
+    let formats = settings.preferred_format.fallback_order();
+    let formats = &formats;
+    let tasks = albums
+        .iter_mut()
+        .filter(|album| !purchased.contains(&helper::url_key(&album.url)))
+        .filter_map(|album| {
+            let download_page = match &album.free_download_page {
+                Some(download_page) => download_page.clone(),
+                None => {
+                    if album.requires_email {
+                        logger.info(format!(
+                            r#"Free album "{}" requires an email, its stream will be downloaded"#,
+                            album.title
+                        ));
+                    }
+                    return None;
+                }
+            };
+            Some(async move {
+                let link = match get_url_text(&download_page).await {
+                    Ok(raw_html) => helper::get_download_link(&raw_html, formats),
+                    Err(e) => Err(e),
+                };
+                match link {
+                    Ok(Some(link)) => {
+                        logger.info(format!(
+                            r#"Album "{}" is free, it will be downloaded in {}"#,
+                            album.title, link.format
+                        ));
+                        attach_purchase(album, link);
+                    }
+                    Ok(None) => logger.warn(format!(
+                        r#"Free album "{}" has no download, its stream will be downloaded"#,
+                        album.title
+                    )),
+                    Err(e) => logger.error(format!(
+                        r#"Could not retrieve the free download of "{}": {}"#,
+                        album.title, e
+                    )),
+                }
+            })
+        });
+    join_all(tasks).await;
+}
+
 /// Compare file size and return true if size on disk is within the provided threshold
 fn file_size_ok(allowed_difference: f64, size_on_disk: f64, new_file_size: f64) -> bool {
     let margin = size_on_disk * allowed_difference;
-    println!("allowed diff {} margin: {}", allowed_difference, margin);
     new_file_size > size_on_disk - margin && new_file_size < size_on_disk + margin
 }
 
-/// Downloads a track. Returns `Ok()` if the track has been correctly downloaded; Err otherwise.
+/// Downloads a track. Returns [`TrackStatus::Done`] if the track has been correctly downloaded,
+/// [`TrackStatus::Skipped`] if it already exists; Err otherwise.
 async fn download_track_stream(
     track: Track,
     allowed_file_size_difference: f32,
     max_tries: u32,
-    mut sender: mpsc::Sender<Message>,
-) -> Result<()> {
-    println!(
-        r#"Downloading track "{}" from url: {:?}"#,
+    logger: Logger,
+    progress: ProgressTracker,
+) -> Result<TrackStatus> {
+    logger.verbose(format!(
+        r#"Downloading track "{}" from url: {}"#,
         track.title, track.mp3_url
-    );
-    sender
-        .try_send(Message::Log(
-            format!(r#"Downloading track "{}""#, track.title,),
-            LogLevel::Info,
-        ))
-        .expect("Failed to send message");
+    ));
+    logger.info(format!(r#"Downloading track "{}""#, track.title));
 
     let mut tries = 0u32;
     while tries < max_tries {
         // TODO cancellation
-        // TODO reuse client?
         // Start download
-        let response = reqwest::get(&track.mp3_url).await;
-        if let Err(e) = response {
-            if e.is_status() {
-                eprintln!("http error status {}", e.status().unwrap());
-                tries += 1;
-                continue;
-            } else if e.is_timeout() {
+        let response = client().get(&track.mp3_url).send().await;
+        let mut response = match response {
+            Ok(response) => response,
+            Err(e) if e.is_status() || e.is_timeout() => {
+                logger.warn(format!(
+                    r#"Unable to download track "{}", retrying: {}"#,
+                    track.title, e
+                ));
                 tries += 1;
                 continue;
-            } else {
-                eprintln!("download error {}", e);
+            }
+            Err(e) => {
+                logger.error(format!(
+                    r#"Unable to download track "{}": {}"#,
+                    track.title, e
+                ));
                 return Err(Error::Download);
             }
-        }
-        let mut response = response.unwrap();
+        };
 
-        let total_size = response.content_length().unwrap_or(0);
+        let total_size = response.content_length().or(track.size).unwrap_or(0);
         let track_path = Path::new(&track.path);
         if track_path.exists() {
-            let size_on_disk = fs::metadata(&track.path)
-                .await
-                .unwrap_or_else(|_| panic!("Unable to stat file {}", track.path))
-                .len();
+            let size_on_disk = fs::metadata(&track.path).await?.len();
 
             if file_size_ok(
                 allowed_file_size_difference as f64,
                 size_on_disk as f64,
                 total_size as f64,
             ) {
-                sender.try_send(Message::Log(
-                    format!(
-                        "Track already exists within allowed file size range: \"{:?}\" - Skipping download!",
-                        track_path.file_name().unwrap()),
-                        LogLevel::Info,
-                    ))
-                    .expect("Failed to send message");
-                return Err(Error::Io(String::from("File already exists")));
+                logger.info(format!(
+                    "Track already exists within allowed file size range: \"{}\" - Skipping download!",
+                    track_path.file_name().unwrap_or_default().to_string_lossy()
+                ));
+                return Ok(TrackStatus::Skipped);
             }
         }
 
         let dir = track_path.parent();
         if let Some(parent_dir) = dir {
             if !parent_dir.exists() {
-                sender
-                    .try_send(Message::Log(
-                        format!("creating dir {}", parent_dir.to_string_lossy()),
-                        LogLevel::Info,
-                    ))
-                    .expect("Failed to send message");
+                logger.verbose(format!("Creating dir {}", parent_dir.to_string_lossy()));
 
                 fs::create_dir_all(parent_dir).await?;
             }
         }
 
-        println!("creating file {}", &track.path);
+        logger.verbose(format!("Creating file {}", &track.path));
         let mut destination = fs::File::create(&track.path).await?;
-        println!("file created");
 
         let mut downloaded = 0;
         while let Some(chunk) = response.chunk().await? {
             destination.write_all(&chunk).await?;
 
             downloaded += chunk.len() as u64;
-            sender
-                .try_send(Message::Progress(Progress {
-                    path: track.path.to_string(),
-                    complete: downloaded,
-                    total: total_size,
-                }))
-                .expect("Failed to send message");
-
-            let percent = (downloaded as f32 / total_size as f32) * 100.0;
-            println!(
-                "{}",
-                format!(
-                    "{} downloaded: {} of {} ({:.2}%)",
-                    &track.title, downloaded, total_size, percent
-                ),
-            );
-        }
-
-        println!(
+            progress.update(&track.path, downloaded, total_size);
+        }
+        // the last write may still be pending, the file is read right away (eg. by the hooks)
+        destination.flush().await?;
+
+        logger.info(format!(
             "Downloaded track \"{}\" ",
-            track_path.file_name().unwrap().to_string_lossy(),
-        );
-        sender
-            .try_send(Message::Log(
-                format!(
-                    "Downloaded track \"{}\" ",
-                    track_path.file_name().unwrap().to_string_lossy(),
-                ),
-                LogLevel::Info,
-            ))
-            .expect("Failed to send message");
+            track_path.file_name().unwrap_or_default().to_string_lossy(),
+        ));
 
-        return Ok(());
+        return Ok(TrackStatus::Done);
     }
 
+    logger.error(format!(
+        r#"Unable to download track "{}" after {} tries"#,
+        track.title, max_tries
+    ));
     Err(Error::Download)
 }
 
@@ -374,7 +585,7 @@ async fn download_track_stream(
 fn tag_track(
     album: Arc<Album>,
     track_index: usize,
-    mut sender: mpsc::Sender<Message>,
+    logger: Logger,
     artwork: Option<id3::frame::Picture>,
     settings: Arc<UserSettings>,
 ) -> Result<()> {
@@ -386,40 +597,68 @@ fn tag_track(
         return Err(Error::Io(String::from("File does not exist")));
     }
 
+    // Only mp3 files hold id3 tags
+    let extension = Path::new(&track.path).extension();
+    let is_mp3 = matches!(extension, Some(extension) if extension.eq_ignore_ascii_case("mp3"));
+    if !is_mp3 {
+        logger.verbose(format!(r#"Not an mp3 file, skipping "{}""#, track.title));
+        return Ok(());
+    }
+
     // Don't overwrite existing tag
     if id3::Tag::read_from_path(&track.path).is_ok() {
-        sender
-            .try_send(Message::Log(
-                format!(r#"Track already tagged, skipping "{}""#, track.title,),
-                LogLevel::Info,
-            ))
-            .expect("Failed to send message");
+        logger.info(format!(
+            r#"Track already tagged, skipping "{}""#,
+            track.title,
+        ));
         return Ok(());
     }
 
     let mut tag = id3::Tag::new();
-    println!(r#"Tagging track "{}" "#, track.title,);
-    sender
-        .try_send(Message::Log(
-            format!(r#"Tagging track "{}" "#, track.title,),
-            LogLevel::Info,
-        ))
-        .expect("Failed to send message");
+    logger.info(format!(r#"Tagging track "{}" "#, track.title));
+    update_tag(&mut tag, &album, track, artwork, &settings);
+
+    tag.write_to_path(&track.path, id3::Version::Id3v24)
+        .map_err(|e| Error::Io(e.description.to_string()))
+}
 
+/// Apply the tag rules of the `settings` to the id3 tag of `track` of `album`, replacing its
+/// front cover with the `artwork` if any
+fn update_tag(
+    tag: &mut id3::Tag,
+    album: &Album,
+    track: &Track,
+    artwork: Option<id3::frame::Picture>,
+    settings: &UserSettings,
+) {
     tag.set_total_tracks(album.tracks.len() as u32);
 
-    tag::update_album_artist(&mut tag, &album.artist, settings.tag_album_artist);
-    tag::update_artist(&mut tag, &album.artist, settings.tag_artist);
-    tag::update_album_title(&mut tag, &album.title, settings.tag_album_title);
-    tag::update_album_date(&mut tag, &album.release_date, settings.tag_year);
-    tag::update_track_number(&mut tag, track.number, settings.tag_track_number);
-    tag::update_track_title(&mut tag, &track.title, settings.tag_track_title);
-    tag::update_track_lyrics(&mut tag, &track.lyrics, settings.tag_lyrics);
-    tag::update_comments(&mut tag, settings.tag_comments);
+    tag::update_album_artist(tag, &album.artist, settings.tag_album_artist);
+    tag::update_artist(tag, &album.artist, settings.tag_artist);
+    tag::update_album_title(tag, &album.title, settings.tag_album_title);
+    tag::update_album_date(tag, &album.release_date, settings.tag_year);
+    tag::update_track_number(tag, track.number, settings.tag_track_number);
+    tag::update_track_title(tag, &track.title, settings.tag_track_title);
+    tag::update_track_lyrics(tag, &track.lyrics, settings.tag_lyrics);
+    tag::update_comments(tag, settings.tag_comments);
+    tag::update_release_url(tag, &album.url);
 
     if let Some(artwork) = artwork {
+        tag.remove_picture_by_type(id3::frame::PictureType::CoverFront);
         tag.add_picture(artwork);
     }
+}
+
+/// Apply the tag rules of the `settings` to the existing file of `track` of `album`, keeping the
+/// frames they do not cover
+fn retag_track(
+    album: &Album,
+    track: &Track,
+    artwork: Option<id3::frame::Picture>,
+    settings: &UserSettings,
+) -> Result<()> {
+    let mut tag = id3::Tag::read_from_path(&track.path).unwrap_or_default();
+    update_tag(&mut tag, album, track, artwork, settings);
 
     tag.write_to_path(&track.path, id3::Version::Id3v24)
         .map_err(|e| Error::Io(e.description.to_string()))
@@ -443,227 +682,1263 @@ async fn download_artwork(album: &Album) -> Result<id3::frame::Picture> {
     Ok(id3_picture)
 }
 
-/// Downloads an album, delivering status updates to a channel via the `sender`
-async fn download_album(
-    album: Album,
-    mut sender: mpsc::Sender<Message>,
-    settings: Arc<UserSettings>,
-) {
+/// Downloads the streams of the tracks of `album` at the specified indices. Returns their status,
+/// reported to the `progress` tracker unless they are done and still have to be tagged.
+async fn download_streams(
+    album: &Album,
+    tracks: &[usize],
+    logger: &Logger,
+    progress: &ProgressTracker,
+    settings: &UserSettings,
+) -> Vec<TrackStatus> {
     let UserSettings {
         allowed_file_size_difference,
-        save_cover_art_in_folder,
-        save_cover_art_in_tags,
         modify_tags,
         download_max_tries,
         ..
     } = *settings;
 
-    // TODO cancellation
+    let download_tasks: Vec<_> = tracks
+        .iter()
+        .map(|&i| {
+            let track = album.tracks[i].clone();
+            let logger = logger.clone();
+            let progress = progress.clone();
+            tokio::spawn(async move {
+                let path = track.path.clone();
+                progress.set_status(&path, TrackStatus::Downloading);
+                let status = download_track_stream(
+                    track,
+                    allowed_file_size_difference,
+                    download_max_tries,
+                    logger,
+                    progress.clone(),
+                )
+                .await
+                .unwrap_or(TrackStatus::Failed);
+                // downloaded tracks are done once tagged
+                if status != TrackStatus::Done || !modify_tags {
+                    progress.set_status(&path, status);
+                }
+                status
+            })
+        })
+        .collect();
+    join_all(download_tasks)
+        .await
+        .into_iter()
+        .map(|res| res.unwrap_or(TrackStatus::Failed))
+        .collect()
+}
 
-    // Create directory to place track files
-    if let Err(e) = fs::create_dir_all(&album.path).await {
-        eprintln!("{}", e);
-        println!("An error occured when creating the album folder. Make sure you have the rights to write files in the folder you chose");
-        return;
+/// Downloads the archive of the purchased `album`, then extracts the tracks at the specified
+/// indices. The archive is not downloaded if every track is already on disk. Returns their
+/// status, reported to the `progress` tracker unless they are done and still have to be tagged.
+async fn download_archive(
+    album: &Album,
+    purchase: &PurchaseLink,
+    tracks: &[usize],
+    logger: &Logger,
+    progress: &ProgressTracker,
+    settings: &UserSettings,
+) -> Vec<TrackStatus> {
+    let (existing, missing): (Vec<usize>, Vec<usize>) = tracks
+        .iter()
+        .partition(|&&i| Path::new(&album.tracks[i].path).is_file());
+    for &i in &existing {
+        let track = &album.tracks[i];
+        logger.info(format!(
+            r#"Track already exists, skipping "{}""#,
+            track.title
+        ));
+        progress.set_status(&track.path, TrackStatus::Skipped);
+    }
+    if missing.is_empty() {
+        return vec![TrackStatus::Skipped; tracks.len()];
     }
 
-    // Download artwork
-    let artwork = if save_cover_art_in_folder || save_cover_art_in_tags {
-        download_artwork(&album).await.ok()
+    let archive = Track::new(
+        album,
+        0.0,
+        None,
+        purchase.url.clone(),
+        0,
+        album.title.clone(),
+        "{album}.zip",
+    );
+    let archive_path = archive.path.clone();
+    // the bytes of the archive are downloaded for the tracks it holds
+    let paths = missing
+        .iter()
+        .map(|&i| album.tracks[i].path.clone())
+        .collect();
+    progress.bundle(&archive_path, paths);
+    for &i in &missing {
+        progress.set_status(&album.tracks[i].path, TrackStatus::Downloading);
+    }
+
+    let status = download_track_stream(
+        archive,
+        settings.allowed_file_size_difference,
+        settings.download_max_tries,
+        logger.clone(),
+        progress.clone(),
+    )
+    .await
+    .unwrap_or(TrackStatus::Failed);
+
+    // Extract the tracks, the archive is not needed afterwards
+    let extracted = if status == TrackStatus::Failed {
+        Vec::new()
     } else {
-        None
+        logger.info(format!(r#"Extracting the tracks of "{}""#, album.title));
+        let task = {
+            let (album, tracks, format) = (album.clone(), missing.clone(), purchase.format);
+            let archive_path = archive_path.clone();
+            tokio::task::spawn_blocking(move || {
+                archive::extract_tracks(archive_path, &album, &tracks, format)
+            })
+        };
+        let extracted = match task.await {
+            Ok(Ok(extracted)) => extracted,
+            Ok(Err(e)) => {
+                logger.error(format!("Unable to extract {}: {}", archive_path, e));
+                Vec::new()
+            }
+            Err(e) => {
+                logger.error(format!("Unable to extract {}: {}", archive_path, e));
+                Vec::new()
+            }
+        };
+        if let Err(e) = fs::remove_file(&archive_path).await {
+            logger.warn(format!("Unable to remove {}: {}", archive_path, e));
+        }
+        extracted
     };
 
-    // Download tracks
-    let download_tasks: Vec<_> = album
-        .tracks
+    tracks
+        .iter()
+        .map(|i| {
+            if existing.contains(i) {
+                return TrackStatus::Skipped;
+            }
+            let track = &album.tracks[*i];
+            let status = if extracted.contains(i) {
+                TrackStatus::Done
+            } else {
+                if status != TrackStatus::Failed {
+                    logger.error(format!(
+                        r#"Track "{}" was not found in the archive of "{}""#,
+                        track.title, album.title
+                    ));
+                }
+                TrackStatus::Failed
+            };
+            // extracted tracks are done once tagged
+            if status != TrackStatus::Done || !settings.modify_tags {
+                progress.set_status(&track.path, status);
+            }
+            status
+        })
+        .collect()
+}
+
+/// Transcodes the downloaded tracks of `album` at the specified indices with the encoder set in
+/// [`UserSettings::transcoding`]. Returns the album holding the path of the transcoded files; the
+/// tracks which could not be transcoded are marked as failed in `statuses`.
+async fn transcode_tracks(
+    album: &Arc<Album>,
+    tracks: &[usize],
+    statuses: &mut [TrackStatus],
+    logger: &Logger,
+    progress: &ProgressTracker,
+    settings: &UserSettings,
+) -> Arc<Album> {
+    let transcode_tasks: Vec<_> = tracks
         .iter()
-        .map(|track| {
-            tokio::spawn(download_track_stream(
-                track.clone(),
-                allowed_file_size_difference,
-                download_max_tries,
-                sender.clone(),
-            ))
+        .zip(statuses.iter())
+        .map(|(&i, &status)| {
+            let album = album.clone();
+            let transcoding = settings.transcoding.clone();
+            let logger = logger.clone();
+            let progress = progress.clone();
+            tokio::task::spawn_blocking(move || {
+                let track = &album.tracks[i];
+                let output = transcoding.output_path(&track.path);
+                // files already on disk are only transcoded if their output is missing
+                if status == TrackStatus::Failed
+                    || (status == TrackStatus::Skipped && Path::new(&output).exists())
+                {
+                    return (status, output);
+                }
+
+                progress.set_status(&track.path, TrackStatus::Transcoding);
+                logger.info(format!(r#"Transcoding track "{}""#, track.title));
+                let status = match transcoding.transcode(&album, track, &output) {
+                    Ok(_) => status,
+                    Err(e) => {
+                        logger.error(format!("Unable to transcode {}: {}", track.path, e));
+                        TrackStatus::Failed
+                    }
+                };
+                progress.set_status(&track.path, status);
+                (status, output)
+            })
         })
         .collect();
-    join_all(download_tasks).await;
+    let results = join_all(transcode_tasks).await;
+
+    let mut transcoded = (**album).clone();
+    for ((&i, status), result) in tracks.iter().zip(statuses.iter_mut()).zip(results) {
+        match result {
+            Ok((TrackStatus::Failed, _)) | Err(_) => *status = TrackStatus::Failed,
+            Ok((_, output)) => transcoded.tracks[i].path = output,
+        }
+    }
+    Arc::new(transcoded)
+}
+
+/// Returns the album of the release at `url`, without applying the download filters
+async fn fetch_album(
+    sources: &[Box<dyn Source>],
+    url: &str,
+    settings: &UserSettings,
+    logger: &Logger,
+) -> Option<Album> {
+    let urls = std::iter::once(prepend_http(url)).collect();
+    let releases = get_releases(sources, &urls, false, logger).await;
+    get_albums(sources, releases, settings, logger)
+        .await
+        .into_iter()
+        .next()
+}
 
-    let album = Arc::new(album);
+/// Tags again the mp3 files found in `dir` and its subfolders, without downloading them. Each
+/// folder is matched to a release by its `album.json` sidecar, the release url saved in the tags
+/// of its files, or else `album_url`. Returns the number of files tagged.
+async fn retag_library(
+    sources: &[Box<dyn Source>],
+    dir: &Path,
+    album_url: Option<&str>,
+    settings: Arc<UserSettings>,
+    logger: &Logger,
+) -> usize {
+    let folders = match library::scan(dir) {
+        Ok(folders) => folders,
+        Err(e) => {
+            logger.error(format!(
+                "Unable to read the library {}: {}",
+                dir.display(),
+                e
+            ));
+            return 0;
+        }
+    };
+    logger.info(format!("Found {} folder(s) of mp3 files", folders.len()));
+
+    let mut tagged = 0;
+    for folder in folders {
+        let source = folder
+            .album_source()
+            .or_else(|| album_url.map(|url| AlbumSource::Url(url.to_string())));
+        let album = match source {
+            Some(AlbumSource::Sidecar(album)) => Some(*album),
+            Some(AlbumSource::Url(url)) => fetch_album(sources, &url, &settings, logger).await,
+            None => None,
+        };
+        let album = match album {
+            Some(album) => folder.match_album(album),
+            None => {
+                logger.warn(format!(
+                    "No release found for {}, it will not be tagged",
+                    folder.path.display()
+                ));
+                continue;
+            }
+        };
+
+        let artwork = if settings.save_cover_art_in_tags {
+            download_artwork(&album).await.ok()
+        } else {
+            None
+        };
+        logger.info(format!(
+            r#"Tagging {} file(s) of album "{}""#,
+            album.selected_tracks().count(),
+            album.title
+        ));
+
+        let album = Arc::new(album);
+        for i in album.selected_indices() {
+            let path = &album.tracks[i].path;
+            let (album, artwork, settings) = (album.clone(), artwork.clone(), settings.clone());
+            let task = tokio::task::spawn_blocking(move || {
+                retag_track(&album, &album.tracks[i], artwork, &settings)
+            });
+            match task.await {
+                Ok(Ok(_)) => tagged += 1,
+                Ok(Err(e)) => logger.error(format!("Unable to tag {}: {}", path, e)),
+                Err(e) => logger.error(format!("Unable to tag {}: {}", path, e)),
+            }
+        }
+    }
+
+    tagged
+}
+
+/// Downloads, then tags, the tracks of `album` at the specified indices. Purchased albums are
+/// downloaded from their archive, and the tagged tracks are transcoded if
+/// [`UserSettings::transcoding`] is enabled. The duplicates of tracks saved from another release
+/// are skipped. The album folder must already exist. Returns the album holding the path of the
+/// files saved.
+async fn download_tracks(
+    album: Arc<Album>,
+    tracks: Vec<usize>,
+    artwork: Option<id3::frame::Picture>,
+    logger: &Logger,
+    progress: &ProgressTracker,
+    settings: &Arc<UserSettings>,
+) -> Arc<Album> {
+    let UserSettings {
+        save_cover_art_in_tags,
+        modify_tags,
+        duplicates,
+        ref transcoding,
+        ..
+    } = **settings;
+
+    // Duplicates are linked to the file they duplicate once every album is downloaded
+    let (duplicated, tracks): (Vec<usize>, Vec<usize>) = tracks
+        .into_iter()
+        .partition(|&i| album.tracks[i].duplicate_of.is_some());
+    for &i in &duplicated {
+        let track = &album.tracks[i];
+        logger.info(format!(
+            r#"Track already saved from another release, skipping "{}""#,
+            track.title
+        ));
+        progress.set_status(&track.path, TrackStatus::Skipped);
+    }
+
+    // Tracks already transcoded are not downloaded again
+    let is_transcoded = |i: &usize| {
+        let path = &album.tracks[*i].path;
+        let output = transcoding.output_path(path);
+        transcoding.enabled && &output != path && Path::new(&output).exists()
+    };
+    let (transcoded, downloads): (Vec<usize>, Vec<usize>) =
+        tracks.iter().partition(|i| is_transcoded(i));
+    for &i in &transcoded {
+        let track = &album.tracks[i];
+        logger.info(format!(
+            r#"Track already transcoded, skipping "{}""#,
+            track.title
+        ));
+        progress.set_status(&track.path, TrackStatus::Skipped);
+    }
+
+    // Download tracks
+    let downloaded = match &album.purchase {
+        Some(purchase) => {
+            download_archive(&album, purchase, &downloads, logger, progress, settings).await
+        }
+        None => download_streams(&album, &downloads, logger, progress, settings).await,
+    };
+    let mut downloaded = downloaded.into_iter();
+    let mut statuses: Vec<_> = tracks
+        .iter()
+        .map(|i| {
+            if transcoded.contains(i) {
+                TrackStatus::Skipped
+            } else {
+                downloaded.next().unwrap_or(TrackStatus::Failed)
+            }
+        })
+        .collect();
 
-    // Tag tracks if they do not already have a tag
+    // Tag tracks if they do not already have a tag, before they are transcoded: the encoder
+    // carries the tags across (eg. `-map_metadata 0` for ffmpeg)
     if modify_tags {
-        let mut tag_tasks = Vec::with_capacity(album.tracks.len());
-        for i in 0..album.tracks.len() {
+        let mut tag_tasks = Vec::with_capacity(tracks.len());
+        for (&i, &status) in tracks.iter().zip(&statuses) {
+            if status == TrackStatus::Failed || transcoded.contains(&i) {
+                continue;
+            }
+
             let album = album.clone();
-            let sender = sender.clone();
+            let logger = logger.clone();
+            let progress = progress.clone();
             let settings = settings.clone();
             let artwork = if save_cover_art_in_tags {
                 artwork.clone()
             } else {
                 None
             };
+            let transcode = transcoding.enabled;
 
             tag_tasks.push(tokio::spawn(async move {
-                tag_track(album, i, sender, artwork, settings)
+                let path = album.tracks[i].path.clone();
+                if status == TrackStatus::Done {
+                    progress.set_status(&path, TrackStatus::Tagging);
+                }
+                if let Err(e) = tag_track(album, i, logger.clone(), artwork, settings) {
+                    logger.error(format!("Unable to tag {}: {}", path, e));
+                }
+                // transcoded tracks are done once transcoded
+                if status == TrackStatus::Done && !transcode {
+                    progress.set_status(&path, TrackStatus::Done);
+                }
             }));
         }
         join_all(tag_tasks).await;
     }
 
-    // TODO Save cover art in folder
-    // Create playlist file
-    if settings.create_playlist {
-        let playlist_path = helper::parse_filename(&settings.playlist_file_name_format, &album);
-        let playlist_path: PathBuf = [&album.path, &playlist_path].iter().collect();
-        let res = playlist::write_playlist(settings.playlist_format, &album, playlist_path);
-        match res {
-            Err(_) => sender.try_send(Message::Log(
-                    format!("An error occured while writing playlist for {}. Make sure you have the rights to write files in the folder you chose", &album.title),
-                    LogLevel::Error,
-                    ))
-                .expect("Failed to send message"),
-            Ok(_) => sender.try_send(Message::Log(
-                    format!("Saved playlist for album \"{}\"", &album.title),
-                    LogLevel::Info,
-                    ))
-                .expect("Failed to send message"),
+    // Transcode tracks
+    let mut saved = if transcoding.enabled {
+        transcode_tracks(&album, &tracks, &mut statuses, logger, progress, settings).await
+    } else {
+        album.clone()
+    };
+    if !duplicated.is_empty() {
+        let saved = Arc::make_mut(&mut saved);
+        for i in duplicated {
+            let track = &mut saved.tracks[i];
+            track.path = dedup::saved_path(duplicates, track, transcoding);
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn band_regex() {
-        let s = r#"<div class="desktop-header">
-            <a href="https://theracers.bandcamp.com" referrerpolicy="strict-origin-when-cross-origin"><img src="https://f4.bcbits.com/img/0024058603_100.png" width="975" height="180"></a>
+    // Run the hook of the failed tracks
+    for (&i, status) in tracks.iter().zip(&statuses) {
+        if *status == TrackStatus::Failed {
+            let data = HookData::track(&saved, &saved.tracks[i]);
+            settings.hooks.trigger(data, logger).await;
+        }
+    }
 
-        </div>"#;
+    saved
+}
 
-        let expected = String::from("https://theracers.bandcamp.com");
-        let actual = BAND_RE.captures(&s).unwrap().name("url").unwrap().as_str();
-        assert_eq!(actual, expected);
+/// Downloads again the tracks of `album` at the specified indices (eg. after they failed), then
+/// completes the album as [`download_album`] does
+async fn retry_tracks(
+    album: Album,
+    tracks: Vec<usize>,
+    logger: Logger,
+    progress: ProgressTracker,
+    settings: Arc<UserSettings>,
+) {
+    if let Err(e) = fs::create_dir_all(&album.path).await {
+        logger.error(format!("An error occured when creating the album folder. Make sure you have the rights to write files in the folder you chose: {}", e));
+        for &i in &tracks {
+            progress.set_status(&album.tracks[i].path, TrackStatus::Failed);
+        }
+        return;
     }
 
-    #[test]
-    fn html_quote_regex() {
-        let s = r#"data-tralbum="{&quot;url&quot;:&quot;}""#;
-
-        let msg = "should find html escaped quotes";
-        let expected = true;
-        let actual = HTML_QUOTE_RE.is_match(&s);
-        assert_eq!(actual, expected, "{}", msg);
+    let artwork = if settings.modify_tags && settings.save_cover_art_in_tags {
+        download_artwork(&album).await.ok()
+    } else {
+        None
+    };
 
-        let msg = "should replace html escaped quotes";
-        let expected = r#"data-tralbum="{"url":"}""#;
-        let actual = HTML_QUOTE_RE.replace_all(&s, "\"");
-        assert_eq!(actual, expected, "{}", msg);
+    let downloaded = Arc::new(album);
+    let mut album = download_tracks(
+        downloaded.clone(),
+        tracks.clone(),
+        artwork,
+        &logger,
+        &progress,
+        &settings,
+    )
+    .await;
+
+    // The other tracks were saved by the earlier download
+    let saved = Arc::make_mut(&mut album);
+    for i in saved.selected_indices() {
+        if !tracks.contains(&i) {
+            let track = &mut saved.tracks[i];
+            track.path = dedup::saved_path(settings.duplicates, track, &settings.transcoding);
+        }
     }
 
-    #[test]
-    fn html_ampersand_regex() {
-        let s = r#"Madness &amp; Hubris"#;
+    complete_album(&downloaded, album, &logger, &progress, &settings).await;
+}
 
-        let msg = "should find html escaped ampersand";
-        let expected = true;
-        let actual = HTML_AMP_RE.is_match(&s);
-        assert_eq!(actual, expected, "{}", msg);
+/// Write the playlist and the metadata sidecars of the `saved` album once its tracks are
+/// downloaded, then run the hook of the complete album. The progress is tracked on the files of
+/// the `downloaded` album.
+async fn complete_album(
+    downloaded: &Album,
+    mut album: Arc<Album>,
+    logger: &Logger,
+    progress: &ProgressTracker,
+    settings: &UserSettings,
+) {
+    // TODO Save cover art in folder
+    // Create playlist file
+    if settings.create_playlist {
+        let playlist_path = playlist::playlist_path(
+            settings.playlist_format,
+            &album,
+            &settings.playlist_file_name_format,
+        );
+        // the sidecars record the playlist written, eg. to find it when reorganising the album
+        Arc::make_mut(&mut album).playlist_path = playlist_path.to_string_lossy().into();
+        let res = playlist::write_playlist(settings.playlist_format, &album, playlist_path);
+        match res {
+            Err(_) => logger.error(format!("An error occured while writing playlist for {}. Make sure you have the rights to write files in the folder you chose", &album.title)),
+            Ok(_) => logger.info(format!("Saved playlist for album \"{}\"", &album.title)),
+        }
+    }
 
-        let msg = "should replace html escaped '&'";
-        let expected = r#"Madness & Hubris"#;
-        let actual = HTML_AMP_RE.replace_all(&s, "&");
-        assert_eq!(actual, expected, "{}", msg);
+    // Write the metadata sidecars
+    if settings.write_album_json {
+        if let Err(e) = sidecar::write_json(&album, Utc::now()) {
+            logger.error(format!(
+                "An error occured while writing the metadata of {}: {}",
+                &album.title, e
+            ));
+        }
+    }
+    if settings.write_album_nfo {
+        if let Err(e) = sidecar::write_nfo(&album) {
+            logger.error(format!(
+                "An error occured while writing the NFO of {}: {}",
+                &album.title, e
+            ));
+        }
     }
 
-    #[test]
-    fn html_lt_regex() {
-        let s = r#"&lt;foo &lt;bar"#;
+    // Run the hook of the complete album, the progress is tracked on the downloaded files
+    let tracks: Vec<_> = downloaded.selected_tracks().collect();
+    let failed = tracks
+        .iter()
+        .filter(|track| progress.status(&track.path) == Some(TrackStatus::Failed))
+        .count();
+    let data = HookData::album(&album, tracks.len() - failed, failed);
+    settings.hooks.trigger(data, logger).await;
+}
 
-        let msg = "should find html escaped '<'";
-        let expected = true;
-        let actual = HTML_LT_RE.is_match(&s);
-        assert_eq!(actual, expected, "{}", msg);
+/// Downloads an album, delivering status updates to the UI via the `logger` and `progress`
+/// tracker
+async fn download_album(
+    album: Album,
+    logger: Logger,
+    progress: ProgressTracker,
+    settings: Arc<UserSettings>,
+) {
+    let UserSettings {
+        save_cover_art_in_folder,
+        save_cover_art_in_tags,
+        ..
+    } = *settings;
 
-        let msg = "should replace html escaped '<'";
-        let expected = r#"<foo <bar"#;
-        let actual = HTML_LT_RE.replace_all(&s, "<");
-        assert_eq!(actual, expected, "{}", msg);
+    // TODO cancellation
+
+    // Create directory to place track files
+    if let Err(e) = fs::create_dir_all(&album.path).await {
+        logger.error(format!("An error occured when creating the album folder. Make sure you have the rights to write files in the folder you chose: {}", e));
+        for track in album.selected_tracks() {
+            progress.set_status(&track.path, TrackStatus::Failed);
+        }
+        return;
     }
 
-    #[test]
-    fn html_gt_regex() {
-        let s = r#"foo&gt; bar&gt;"#;
+    // Download artwork
+    let artwork = if save_cover_art_in_folder || save_cover_art_in_tags {
+        download_artwork(&album).await.ok()
+    } else {
+        None
+    };
 
-        let msg = "should find html escaped '>'";
-        let expected = true;
-        let actual = HTML_GT_RE.is_match(&s);
-        assert_eq!(actual, expected, "{}", msg);
+    let downloaded = Arc::new(album);
+    let tracks = downloaded.selected_indices();
+    let album = download_tracks(
+        downloaded.clone(),
+        tracks,
+        artwork,
+        &logger,
+        &progress,
+        &settings,
+    )
+    .await;
+
+    complete_album(&downloaded, album, &logger, &progress, &settings).await;
+}
 
-        let msg = "should replace html escaped '>'";
-        let expected = r#"foo> bar>"#;
-        let actual = HTML_GT_RE.replace_all(&s, ">");
-        assert_eq!(actual, expected, "{}", msg);
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::core::{DuplicateAction, Hooks, TrackIndex, Transcoding};
+    use helper::{
+        mock_server::{pages, MockServer, Response},
+        test_helper::{test_dir, test_logger},
+    };
+    use settings::{DownloadFormat, PlaylistFormat};
+
+    /// Returns a server answering the alphabet at `/range/26`
+    async fn alphabet_server() -> MockServer {
+        MockServer::start(vec![(
+            "/range/26",
+            Response::text("abcdefghijklmnopqrstuvwxyz"),
+        )])
+        .await
     }
 
     #[tokio::test]
     async fn get_html_text() {
+        let server = alphabet_server().await;
         let msg = "Gets the content at url in utf8 text form";
         let expected = "abcdefghijklmnopqrstuvwxyz";
-        let actual = get_url_text("http://httpbin.org/range/26").await.unwrap();
+        let actual = get_url_text(&server.url("/range/26")).await.unwrap();
         assert_eq!(actual, expected, "{}", msg);
     }
 
     #[tokio::test]
     async fn client_get_html_text() {
+        let server = alphabet_server().await;
         let client = reqwest::Client::new();
         let msg = "Gets the content at url in utf8 text form";
         let expected = "abcdefghijklmnopqrstuvwxyz";
-        let actual = client_get_url_text(&client, "http://httpbin.org/range/26")
+        let actual = client_get_url_text(&client, &server.url("/range/26"))
             .await
             .unwrap();
 
         assert_eq!(actual, expected, "{}", msg);
     }
 
-    #[tokio::test]
-    async fn gets_music_page() {
-        let client = reqwest::Client::new();
-        let url = "https://theracers.bandcamp.com/";
-
-        let expected = "https://theracers.bandcamp.com/music";
-        let actual = get_music_page_url(&client, url).await.unwrap();
-        assert_eq!(actual, expected,);
-    }
-
     #[tokio::test]
     async fn gets_discography() {
-        let urls: HashSet<_> = vec![
-            "https://moter.bandcamp.com/album/wave-transmission",
-            "https://theracers.bandcamp.com/",
-        ]
-        .into_iter()
-        .collect();
+        let server = MockServer::bandcamp().await;
+        let sources = source::default_sources();
+        let urls: HashSet<_> = vec![server.url("/album/first-album"), server.url("/")]
+            .into_iter()
+            .collect();
 
         let mut expected: Vec<_> = vec![
-            "https://moter.bandcamp.com/album/moter-ep",
-            "https://moter.bandcamp.com/album/last-train-to-synthville",
-            "https://moter.bandcamp.com/album/wave-transmission",
-            "https://moter.bandcamp.com/album/omegadriver",
-            "https://moter.bandcamp.com/album/aerodnmx",
-            "https://theracers.bandcamp.com/track/tunnel-vision",
-            "https://theracers.bandcamp.com/track/deep-blue-the-racers-feat-jim-gauntner-remix",
-            "https://theracers.bandcamp.com/album/the-midnight-by-the-racers-i",
-            "https://theracers.bandcamp.com/track/final-lap",
+            server.url("/album/first-album"),
+            server.url("/track/single-track"),
         ];
-        let mut actual = get_artist_discography(&urls).await;
+        let mut actual: Vec<_> = get_releases(&sources, &urls, true, &test_logger())
+            .await
+            .into_iter()
+            .map(|release| release.url)
+            .collect();
         actual.sort();
         expected.sort();
         assert_eq!(actual, expected,);
     }
 
+    #[tokio::test]
+    async fn files_label_releases() {
+        let server = MockServer::label().await;
+        let sources = source::default_sources();
+        let mut settings = UserSettings {
+            download_artist_discography: true,
+            downloads_path: PathBuf::from("/music/{label}/{artist}"),
+            label_artist_filter: String::from("mock racers"),
+            retrieve_files_size: false,
+            ..UserSettings::default()
+        };
+
+        let msg = "should file the releases of a label under its name";
+        let albums = fetch_urls(&sources, &server.url("/"), &settings, &test_logger()).await;
+        let actual: Vec<_> = albums.iter().map(|album| album.path.as_str()).collect();
+        let expected = PathBuf::from("/music/Mock Label Records/The Mock Racers");
+        let expected = expected.to_string_lossy();
+        assert_eq!(actual, vec![expected.as_ref(); 2], "{}", msg);
+
+        let msg = "should skip the releases of the artists not matching the filter";
+        settings.label_artist_filter = String::from("Nightride");
+        let albums = fetch_urls(&sources, &server.url("/"), &settings, &test_logger()).await;
+        assert!(albums.is_empty(), "{}", msg);
+    }
+
+    #[tokio::test]
+    async fn downloads_discography_end_to_end() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("downloads_discography_end_to_end");
+        let logger = test_logger();
+        let settings = Arc::new(UserSettings {
+            download_artist_discography: true,
+            downloads_path: dir.join("{artist}").join("{album}"),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            retrieve_files_size: true,
+            modify_tags: true,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            create_playlist: false,
+            ..UserSettings::default()
+        });
+
+        let sources = source::default_sources();
+        let mut albums = fetch_urls(&sources, &server.url("/"), &settings, &logger).await;
+        albums.sort_by(|a, b| a.title.cmp(&b.title));
+
+        let msg = "should find every release of the artist";
+        let actual: Vec<_> = albums.iter().map(|album| album.title.as_str()).collect();
+        assert_eq!(actual, vec!["First Album", "Single Track"], "{}", msg);
+
+        let msg = "should retrieve the files size beforehand";
+        let actual: Vec<_> = albums.iter().map(Album::size).collect();
+        assert_eq!(actual, vec![96 * 1024, 16 * 1024], "{}", msg);
+
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        for album in albums.clone() {
+            download_album(album, logger.clone(), progress.clone(), settings.clone()).await;
+        }
+
+        let msg = "should download every track";
+        let stats = progress.stats();
+        assert_eq!(stats.total.tracks_done, 3, "{}", msg);
+        assert_eq!(stats.total.bytes_done, 112 * 1024, "{}", msg);
+
+        let msg = "should tag the downloaded tracks";
+        let track = &albums[0].tracks[1];
+        let tag = id3::Tag::read_from_path(&track.path).unwrap();
+        assert_eq!(tag.title(), Some("Pit Stop"), "{}", msg);
+        assert_eq!(tag.album(), Some("First Album"), "{}", msg);
+        assert_eq!(tag.artist(), Some("The Mock Racers"), "{}", msg);
+        assert_eq!(tag.track(), Some(2), "{}", msg);
+
+        let msg = "should skip the tracks already downloaded";
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(albums[1].clone(), logger, progress.clone(), settings).await;
+        let path = &albums[1].tracks[0].path;
+        assert_eq!(progress.status(path), Some(TrackStatus::Skipped), "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn downloads_selected_tracks() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("downloads_selected_tracks");
+        let logger = test_logger();
+        let settings = Arc::new(UserSettings {
+            downloads_path: dir.clone(),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            track_selection: "2".parse().unwrap(),
+            retrieve_files_size: false,
+            modify_tags: false,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            create_playlist: true,
+            playlist_format: PlaylistFormat::M3U,
+            write_album_json: true,
+            ..UserSettings::default()
+        });
+
+        let sources = source::default_sources();
+        let url = server.url("/album/first-album");
+        let albums = fetch_urls(&sources, &url, &settings, &logger).await;
+        let album = albums[0].clone();
+
+        let msg = "should flag the selected tracks";
+        let actual: Vec<_> = album.tracks.iter().map(|track| track.selected).collect();
+        assert_eq!(actual, vec![false, true], "{}", msg);
+
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(album.clone(), logger, progress.clone(), settings).await;
+
+        let msg = "should only download the selected tracks";
+        assert_eq!(progress.stats().total.tracks_total, 1, "{}", msg);
+        assert!(!Path::new(&album.tracks[0].path).exists(), "{}", msg);
+        assert!(Path::new(&album.tracks[1].path).exists(), "{}", msg);
+
+        let msg = "should only list the selected tracks in the playlist";
+        let playlist = std::fs::read_to_string(dir.join("First Album.m3u")).unwrap();
+        assert!(!playlist.contains(&album.tracks[0].path), "{}", msg);
+        assert!(playlist.contains(&album.tracks[1].path), "{}", msg);
+
+        let msg = "should write the album metadata in the album folder";
+        let json = std::fs::read_to_string(dir.join(sidecar::JSON_FILE_NAME)).unwrap();
+        let actual: Album = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual.title, album.title, "{}", msg);
+        assert_eq!(actual.tracks.len(), 2, "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn skips_duplicates() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("skips_duplicates");
+        let logger = test_logger();
+        let settings = UserSettings {
+            downloads_path: dir.join("{album}"),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            retrieve_files_size: false,
+            modify_tags: false,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            create_playlist: true,
+            playlist_format: PlaylistFormat::M3U,
+            duplicates: DuplicateAction::Skip,
+            ..UserSettings::default()
+        };
+
+        let sources = source::default_sources();
+        let url = server.url("/album/first-album");
+        let mut albums = fetch_urls(&sources, &url, &settings, &logger).await;
+        // the same tracks released on a compilation
+        let mut compilation = albums[0].clone();
+        compilation.title = String::from("Compilation");
+        compilation.relocate(
+            &settings.downloads_path.to_string_lossy(),
+            &settings.file_name_format,
+        );
+        albums.push(compilation);
+        let index = TrackIndex::default();
+        dedup::flag_duplicates(&mut albums, &index, &settings.transcoding);
+
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        let settings = Arc::new(settings);
+        for album in albums.clone() {
+            download_album(album, logger.clone(), progress.clone(), settings.clone()).await;
+        }
+
+        let msg = "should not download the duplicates";
+        let compilation = &albums[1];
+        assert!(Path::new(&albums[0].tracks[1].path).exists(), "{}", msg);
+        assert!(!Path::new(&compilation.tracks[1].path).exists(), "{}", msg);
+        let status = progress.status(&compilation.tracks[1].path);
+        assert_eq!(status, Some(TrackStatus::Skipped), "{}", msg);
+
+        let msg = "should list the saved files in the playlist";
+        let playlist = dir.join("Compilation").join("Compilation.m3u");
+        let playlist = std::fs::read_to_string(playlist).unwrap();
+        assert!(playlist.contains(&albums[0].tracks[1].path), "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn retags_library() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("retags_library");
+        let logger = test_logger();
+        let settings = Arc::new(UserSettings {
+            save_cover_art_in_tags: false,
+            ..UserSettings::default()
+        });
+        let sources = source::default_sources();
+        let folder = dir.join("The Mock Racers - First Album");
+        std::fs::create_dir_all(&folder).unwrap();
+        let mut tag = id3::Tag::new();
+        tag.set_track(1);
+        tag.set_title("Track 1");
+        let tagged_file = folder.join("01.mp3");
+        std::fs::write(&tagged_file, pages::MP3_1).unwrap();
+        tag.write_to_path(&tagged_file, id3::Version::Id3v24)
+            .unwrap();
+        std::fs::write(folder.join("pit stop.mp3"), pages::MP3_2).unwrap();
+
+        let msg = "should tag the files matched to the supplied release";
+        let url = server.url("/album/first-album");
+        let tagged = retag_library(&sources, &dir, Some(&url), settings.clone(), &logger).await;
+        assert_eq!(tagged, 2, "{}", msg);
+        let tag = id3::Tag::read_from_path(&tagged_file).unwrap();
+        assert_eq!(tag.title(), Some("Opening Lap"), "{}", msg);
+        assert_eq!(tag.album(), Some("First Album"), "{}", msg);
+        let tag = id3::Tag::read_from_path(folder.join("pit stop.mp3")).unwrap();
+        assert_eq!(tag.track(), Some(2), "{}", msg);
+        assert_eq!(tag::release_url(&tag), Some(url.as_str()), "{}", msg);
+
+        let msg = "should find the release from the saved url";
+        let tagged = retag_library(&sources, &dir, None, settings, &logger).await;
+        assert_eq!(tagged, 2, "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn transcodes_tracks() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("transcodes_tracks");
+        let logger = test_logger();
+        let settings = Arc::new(UserSettings {
+            downloads_path: dir.clone(),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            transcoding: Transcoding {
+                enabled: true,
+                encoder: String::from("cp"),
+                arguments: String::from("{input} {output}"),
+                extension: String::from("m4a"),
+                ..Transcoding::default()
+            },
+            retrieve_files_size: false,
+            modify_tags: false,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            create_playlist: true,
+            playlist_format: PlaylistFormat::M3U,
+            ..UserSettings::default()
+        });
+
+        let sources = source::default_sources();
+        let url = server.url("/album/first-album");
+        let albums = fetch_urls(&sources, &url, &settings, &logger).await;
+        let album = albums[0].clone();
+
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(
+            album.clone(),
+            logger.clone(),
+            progress.clone(),
+            settings.clone(),
+        )
+        .await;
+
+        let msg = "should replace the downloaded files with the transcoded ones";
+        for (track, data) in album.tracks.iter().zip([pages::MP3_1, pages::MP3_2]) {
+            let output = Path::new(&track.path).with_extension("m4a");
+            assert_eq!(std::fs::read(&output).unwrap(), data, "{}", msg);
+            assert!(!Path::new(&track.path).exists(), "{}", msg);
+            let status = progress.status(&track.path);
+            assert_eq!(status, Some(TrackStatus::Done), "{}", msg);
+        }
+
+        let msg = "should list the transcoded files in the playlist";
+        let playlist = std::fs::read_to_string(dir.join("First Album.m3u")).unwrap();
+        assert!(playlist.contains("02 - Pit Stop.m4a"), "{}", msg);
+        assert!(!playlist.contains(".mp3"), "{}", msg);
+
+        let msg = "should not download again the transcoded tracks";
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(album.clone(), logger, progress.clone(), settings).await;
+        for track in &album.tracks {
+            assert!(!Path::new(&track.path).exists(), "{}", msg);
+            let status = progress.status(&track.path);
+            assert_eq!(status, Some(TrackStatus::Skipped), "{}", msg);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tags_transcoded_tracks() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("tags_transcoded_tracks");
+        let logger = test_logger();
+        // the copy carries the tags across like an encoder mapping the metadata
+        let settings = Arc::new(UserSettings {
+            downloads_path: dir.clone(),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            transcoding: Transcoding {
+                enabled: true,
+                encoder: String::from("cp"),
+                arguments: String::from("{input} {output}"),
+                extension: String::from("m4a"),
+                ..Transcoding::default()
+            },
+            retrieve_files_size: false,
+            modify_tags: true,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            create_playlist: false,
+            ..UserSettings::default()
+        });
+
+        let sources = source::default_sources();
+        let url = server.url("/album/first-album");
+        let albums = fetch_urls(&sources, &url, &settings, &logger).await;
+        let album = albums[0].clone();
+
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(album.clone(), logger, progress.clone(), settings).await;
+
+        let msg = "should tag the tracks before transcoding them";
+        for track in &album.tracks {
+            let output = Path::new(&track.path).with_extension("m4a");
+            let tag = id3::Tag::read_from_path(&output).unwrap();
+            assert_eq!(tag.title(), Some(track.title.as_str()), "{}", msg);
+            assert_eq!(tag.album(), Some(album.title.as_str()), "{}", msg);
+            let status = progress.status(&track.path);
+            assert_eq!(status, Some(TrackStatus::Done), "{}", msg);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn runs_album_hooks() {
+        let server = MockServer::bandcamp().await;
+        let dir = test_dir("runs_album_hooks");
+        let script = dir.join("hook.sh");
+        let events = dir.join("events");
+        std::fs::write(
+            &script,
+            format!(
+                "echo \"$BANDCAMP_EVENT $BANDCAMP_TRACKS_DONE $BANDCAMP_ALBUM\" >> '{}'",
+                events.to_string_lossy()
+            ),
+        )
+        .unwrap();
+        let logger = test_logger();
+        let settings = Arc::new(UserSettings {
+            downloads_path: dir.join("music"),
+            hooks: Hooks {
+                album_complete: format!("sh {}", script.to_string_lossy()),
+                ..Hooks::default()
+            },
+            retrieve_files_size: false,
+            modify_tags: false,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            create_playlist: true,
+            playlist_format: PlaylistFormat::M3U,
+            write_album_json: true,
+            ..UserSettings::default()
+        });
+
+        let sources = source::default_sources();
+        let url = server.url("/album/first-album");
+        let albums = fetch_urls(&sources, &url, &settings, &logger).await;
+        let album = albums[0].clone();
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(
+            album.clone(),
+            logger.clone(),
+            progress.clone(),
+            settings.clone(),
+        )
+        .await;
+
+        let msg = "should run the hook once the album is downloaded";
+        let actual = std::fs::read_to_string(&events).unwrap();
+        assert_eq!(actual, "album-complete 2 First Album\n", "{}", msg);
+
+        let msg = "should write the playlist and sidecar again and run the hook once retried";
+        let folder = PathBuf::from(&album.path);
+        let playlist = folder.join("First Album.m3u");
+        std::fs::remove_file(&playlist).unwrap();
+        std::fs::remove_file(folder.join(sidecar::JSON_FILE_NAME)).unwrap();
+        std::fs::remove_file(&album.tracks[1].path).unwrap();
+        retry_tracks(album.clone(), vec![1], logger, progress, settings).await;
+        let playlist = std::fs::read_to_string(&playlist).unwrap();
+        for track in &album.tracks {
+            assert!(playlist.contains(&track.path), "{}", msg);
+        }
+        let (saved, _) = sidecar::read_json(&folder).unwrap();
+        let paths: Vec<_> = saved.tracks.iter().map(|track| &track.path).collect();
+        let expected: Vec<_> = album.tracks.iter().map(|track| &track.path).collect();
+        assert_eq!(paths, expected, "{}", msg);
+        let actual = std::fs::read_to_string(&events).unwrap();
+        let expected = "album-complete 2 First Album\n".repeat(2);
+        assert_eq!(actual, expected, "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn downloads_purchases() {
+        let server = MockServer::collection().await;
+        let dir = test_dir("downloads_purchases");
+        let logger = test_logger();
+        let settings = Arc::new(UserSettings {
+            downloads_path: dir.clone(),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            identity_cookie: String::from("fan"),
+            fan_page_url: server.url("/fan"),
+            preferred_format: DownloadFormat::Alac,
+            download_free_releases: true,
+            retrieve_files_size: false,
+            modify_tags: false,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            create_playlist: true,
+            playlist_format: PlaylistFormat::M3U,
+            ..UserSettings::default()
+        });
+
+        let sources = source::default_sources();
+        let urls = [
+            server.url("/album/first-album"),
+            server.url("/track/single-track"),
+        ]
+        .join("\n");
+        let mut albums = fetch_urls(&sources, &urls, &settings, &logger).await;
+        albums.sort_by(|a, b| a.title.cmp(&b.title));
+        let (album, track) = (albums[0].clone(), albums[1].clone());
+
+        let msg = "should download albums as an archive in the closest available format";
+        let expected = PurchaseLink {
+            url: server.url("/purchase/1-flac.zip"),
+            format: DownloadFormat::Flac,
+        };
+        assert_eq!(album.purchase, Some(expected), "{}", msg);
+        assert!(album.tracks[0].path.ends_with(".flac"), "{}", msg);
+
+        let msg = "should retrieve the size of the archive of the purchased albums";
+        let mut sized = vec![album.clone()];
+        retrieve_files_size(&mut sized, &logger).await;
+        assert_eq!(sized[0].size(), pages::album_zip().len() as u64, "{}", msg);
+
+        let msg = "should download tracks as a file in the closest available format, rather than \
+                   their free download";
+        assert_eq!(track.purchase, None, "{}", msg);
+        assert_eq!(
+            track.tracks[0].mp3_url,
+            server.url("/purchase/2.flac"),
+            "{}",
+            msg
+        );
+        assert!(track.tracks[0].path.ends_with(".flac"), "{}", msg);
+
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(
+            album.clone(),
+            logger.clone(),
+            progress.clone(),
+            settings.clone(),
+        )
+        .await;
+        download_album(track.clone(), logger, progress.clone(), settings.clone()).await;
+
+        let msg = "should save the purchased files";
+        let file = std::fs::read(&track.tracks[0].path).unwrap();
+        assert_eq!(file, pages::TRACK_FLAC, "{}", msg);
+
+        let msg = "should extract the album tracks from their archive";
+        for (track, data) in album.tracks.iter().zip([pages::FLAC_1, pages::FLAC_2]) {
+            assert_eq!(std::fs::read(&track.path).unwrap(), data, "{}", msg);
+            let status = progress.status(&track.path);
+            assert_eq!(status, Some(TrackStatus::Done), "{}", msg);
+        }
+        let archive = Path::new(&album.path).join("First Album.zip");
+        assert!(!archive.exists(), "{}", msg);
+
+        let msg = "should count the bytes of the archive in the album progress";
+        let stats = progress.stats();
+        let archive_size = pages::album_zip().len() as u64;
+        assert_eq!(stats.albums[0].count.bytes_done, archive_size, "{}", msg);
+
+        let msg = "should list the extracted tracks in the playlist";
+        let playlist = std::fs::read_to_string(dir.join("First Album.m3u")).unwrap();
+        assert!(playlist.contains(&album.tracks[1].path), "{}", msg);
+
+        let msg = "should not download the archive again once its tracks are on disk";
+        let mut again = album.clone();
+        let missing = PurchaseLink {
+            url: server.url("/purchase/missing.zip"),
+            format: DownloadFormat::Flac,
+        };
+        again.purchase = Some(missing);
+        let progress = ProgressTracker::new(&[again.clone()], test_logger());
+        download_album(again, test_logger(), progress.clone(), settings).await;
+        for track in &album.tracks {
+            let status = progress.status(&track.path);
+            assert_eq!(status, Some(TrackStatus::Skipped), "{}", msg);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn ignores_purchases_without_session() {
+        let server = MockServer::collection().await;
+        let settings = UserSettings {
+            identity_cookie: String::from("someone-else"),
+            fan_page_url: server.url("/fan"),
+            retrieve_files_size: false,
+            ..UserSettings::default()
+        };
+
+        let msg = "should download the streams when the collection cannot be read";
+        let sources = source::default_sources();
+        let url = server.url("/album/first-album");
+        let albums = fetch_urls(&sources, &url, &settings, &test_logger()).await;
+        assert_eq!(albums[0].purchase, None, "{}", msg);
+    }
+
+    #[tokio::test]
+    async fn downloads_free_releases() {
+        let server = MockServer::free_releases().await;
+        let dir = test_dir("downloads_free_releases");
+        let logger = test_logger();
+        let settings = Arc::new(UserSettings {
+            downloads_path: dir.clone(),
+            file_name_format: String::from("{tracknum} - {title}.mp3"),
+            preferred_format: DownloadFormat::Flac,
+            download_free_releases: true,
+            retrieve_files_size: false,
+            modify_tags: false,
+            save_cover_art_in_folder: false,
+            save_cover_art_in_tags: false,
+            ..UserSettings::default()
+        });
+
+        let sources = source::default_sources();
+        let urls = [
+            server.url("/album/free-album"),
+            server.url("/track/email-track"),
+        ]
+        .join("\n");
+        let mut albums = fetch_urls(&sources, &urls, &settings, &logger).await;
+        albums.sort_by(|a, b| a.title.cmp(&b.title));
+        let (track, album) = (albums[0].clone(), albums[1].clone());
+
+        let msg = "should download the free albums from their download page";
+        let expected = PurchaseLink {
+            url: server.url("/free/4-flac.zip"),
+            format: DownloadFormat::Flac,
+        };
+        assert_eq!(album.purchase, Some(expected), "{}", msg);
+
+        let msg = "should download the stream of the releases requiring an email";
+        assert!(track.requires_email, "{}", msg);
+        assert_eq!(
+            track.tracks[0].mp3_url,
+            server.url("/stream/3.mp3"),
+            "{}",
+            msg
+        );
+
+        let progress = ProgressTracker::new(&albums, logger.clone());
+        download_album(album.clone(), logger, progress, settings.clone()).await;
+
+        let msg = "should extract the tracks of the free albums";
+        for (track, data) in album.tracks.iter().zip([pages::FLAC_1, pages::FLAC_2]) {
+            assert_eq!(std::fs::read(&track.path).unwrap(), data, "{}", msg);
+        }
+
+        let msg = "should download the streams of the free releases unless enabled";
+        let settings = UserSettings {
+            download_free_releases: false,
+            ..(*settings).clone()
+        };
+        let url = server.url("/album/free-album");
+        let albums = fetch_urls(&sources, &url, &settings, &test_logger()).await;
+        assert_eq!(albums[0].purchase, None, "{}", msg);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn fetches_new_releases() {
+        let server = MockServer::bandcamp().await;
+        let sources = source::default_sources();
+        let settings = UserSettings {
+            downloads_path: PathBuf::from("/music"),
+            retrieve_files_size: false,
+            ..UserSettings::default()
+        };
+        let mut history = History::default();
+        history.insert(&server.url("/album/first-album"));
+
+        let msg = "should only fetch the releases missing from the history";
+        let urls = vec![server.url("/")];
+        let albums = fetch_new_releases(&sources, &urls, &history, &settings, &test_logger()).await;
+        let actual: Vec<_> = albums.iter().map(|album| album.url.clone()).collect();
+        assert_eq!(actual, vec![server.url("/track/single-track")], "{}", msg);
+    }
+
     #[test]
     fn adds_http_protocol() {
         let s = "foobar.bandcamp.com/";
@@ -1,12 +1,26 @@
 use bandcamp_downloader::{
     self,
+    cli::{self, Args},
     settings::UserSettings,
-    ui::{IcedUi, Ui},
+    ui::{CliUi, IcedUi, Ui},
 };
 
-/// Create UI instance according to environment variable.
+/// Create UI instance according to the command line and environment variable.
 /// Default to gui
-fn select_ui() -> Box<dyn Ui> {
+fn select_ui(args: &Args) -> Box<dyn Ui> {
+    if let Some(dir) = &args.reorganise {
+        return Box::new(CliUi::reorganising(dir.into(), args.dry_run));
+    }
+    if let Some(dir) = &args.retag {
+        return Box::new(CliUi::retagging(dir.into(), args.album_url.clone()));
+    }
+    if args.watch {
+        return Box::new(CliUi::watching(args.urls.clone()));
+    }
+    if !args.urls.is_empty() {
+        return Box::new(CliUi::new(args.urls.clone(), args.dry_run));
+    }
+
     let ui = std::env::var("UI").unwrap_or_else(|_| String::from("gui"));
 
     match ui.as_str() {
@@ -16,8 +30,34 @@ fn select_ui() -> Box<dyn Ui> {
 }
 
 fn main() {
-    let ui = select_ui();
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+
+    let mut user_settings = UserSettings::load().unwrap_or_default();
+    if args.edits_subscriptions() {
+        args.apply_subscriptions(&mut user_settings);
+        if let Err(e) = user_settings.save() {
+            eprintln!("Unable to save the subscriptions: {}", e);
+        }
+        if args.urls.is_empty() && !args.watch && args.retag.is_none() && args.reorganise.is_none()
+        {
+            for url in &user_settings.subscriptions {
+                println!("{}", url);
+            }
+            return;
+        }
+    }
+    args.apply(&mut user_settings);
 
-    let user_settings = UserSettings::load().unwrap_or_default();
+    let ui = select_ui(&args);
     ui.run(user_settings);
 }
@@ -3,10 +3,18 @@ use std::path::PathBuf;
 use chrono::{DateTime, Datelike, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::Track;
+use super::{PurchaseLink, Track};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Album {
+    /// The Bandcamp id of the album (or track release), if known.
+    #[serde(default)]
+    pub id: Option<u64>,
+
+    /// The album description.
+    #[serde(default)]
+    pub about: Option<String>,
+
     /// The album artist.
     pub artist: String,
 
@@ -19,20 +27,52 @@ pub struct Album {
     /// The URL where the artwork should be downloaded from.
     pub artwork_url: Option<String>,
 
+    /// The Bandcamp id of the artist (or label) account hosting the album, if known.
+    #[serde(default)]
+    pub band_id: Option<u64>,
+
+    /// The album credits.
+    #[serde(default)]
+    pub credits: Option<String>,
+
+    /// The name of the label (or account) which published the album, if known.
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// The page to download the album from, if it is free (or name your price).
+    #[serde(default)]
+    pub free_download_page: Option<String>,
+
     /// The local path (full path) to the folder where the album should be saved.
     pub path: String,
 
     /// The local path (full path with file name) where the playlist file should be saved.
     pub playlist_path: String,
 
+    /// The archive of the album tracks bought by the user, downloaded instead of their streams.
+    #[serde(default)]
+    pub purchase: Option<PurchaseLink>,
+
+    /// True if the free download of the album is only sent by email.
+    #[serde(default)]
+    pub requires_email: bool,
+
     /// The release date of the album.
     pub release_date: DateTime<Utc>,
 
+    /// Tags (genres, locations...) the album is filed under.
+    #[serde(default)]
+    pub tags: Vec<String>,
+
     /// The album title.
     pub title: String,
 
     /// The list of tracks contained in the album.
     pub tracks: Vec<Track>,
+
+    /// The URL of the album (or track) page.
+    #[serde(default)]
+    pub url: String,
 }
 
 impl Album {
@@ -40,18 +80,29 @@ impl Album {
     pub fn new(
         artist: &str,
         artwork_url: Option<&str>,
+        label: Option<&str>,
         title: &str,
         release_date: DateTime<Utc>,
         folder_path: &str,
     ) -> Self {
         let mut album = Self {
+            id: None,
+            about: None,
+            band_id: None,
+            credits: None,
             artist: artist.to_string(),
             artwork_url: artwork_url.map(|url| url.to_string()),
             title: title.to_string(),
             release_date,
             tracks: Vec::new(),
+            url: String::new(),
+            label: label.map(|label| label.to_string()),
+            tags: Vec::new(),
             path: String::new(),
             playlist_path: String::new(),
+            purchase: None,
+            free_download_page: None,
+            requires_email: false,
             artwork_path: String::new(),
             artwork_temp_path: String::new(),
         };
@@ -67,6 +118,52 @@ impl Album {
         self.artwork_url.is_some()
     }
 
+    /// Returns the name of the label which published the album, or its artist if the label is
+    /// unknown (eg. self-released albums).
+    pub fn publisher(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.artist)
+    }
+
+    /// True if the album is a compilation of several artists, its tracks being titled
+    /// "Artist - Title".
+    pub fn is_compilation(&self) -> bool {
+        const VARIOUS_ARTISTS: [&str; 3] = ["various artists", "various", "va"];
+        VARIOUS_ARTISTS.contains(&self.artist.trim().to_lowercase().as_str())
+    }
+
+    /// Returns the sum of the known file sizes (in bytes) of the selected tracks.
+    pub fn size(&self) -> u64 {
+        self.selected_tracks().filter_map(|track| track.size).sum()
+    }
+
+    /// Returns the tracks selected for download.
+    pub fn selected_tracks(&self) -> impl Iterator<Item = &Track> {
+        self.tracks.iter().filter(|track| track.selected)
+    }
+
+    /// Moves the album to the folder of `folder_format`, its tracks being named from
+    /// `filename_format`. The files are not moved.
+    pub fn relocate(&mut self, folder_format: &str, filename_format: &str) {
+        self.path = self.parse_folder_path(folder_format);
+        self.playlist_path = self.parse_playlist_path();
+
+        let mut tracks = std::mem::take(&mut self.tracks);
+        for track in tracks.iter_mut() {
+            track.relocate(self, filename_format);
+        }
+        self.tracks = tracks;
+    }
+
+    /// Returns the indices of the tracks selected for download.
+    pub fn selected_indices(&self) -> Vec<usize> {
+        self.tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, track)| track.selected)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
     /// Returns the file extension to be used for the playlist, depending of the type of playlist
     /// defined in UserSettings.
     fn get_playlist_file_extension() -> &'static str {
@@ -81,6 +178,7 @@ impl Album {
             .replace("{day}", &format!("{:02}", self.release_date.day()))
             .replace("{album}", &self.title)
             .replace("{artist}", &self.artist)
+            .replace("{label}", self.publisher())
     }
 
     /// Returns the file name to be used for the cover art of the specified album from the file name
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::DownloadFormat;
+
+/// Release of a fan collection, from the `data-blob` attribute of a fan page
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonCollectionItem {
+    #[serde(rename = "item_url")]
+    pub url: String,
+
+    #[serde(rename = "item_title")]
+    pub title: String,
+
+    #[serde(rename = "band_name")]
+    pub artist: String,
+
+    /// Kind of sale (eg. `p` for a payment), keying the redownload urls along with its id
+    #[serde(default)]
+    pub sale_item_type: Option<String>,
+
+    #[serde(default)]
+    pub sale_item_id: Option<u64>,
+}
+
+impl JsonCollectionItem {
+    /// Returns the release, along with its download page found in `download_pages`
+    fn into_item(self, download_pages: &HashMap<String, String>) -> CollectionItem {
+        let sale = self
+            .sale_item_type
+            .as_ref()
+            .zip(self.sale_item_id)
+            .map(|(kind, id)| format!("{}{}", kind, id));
+        CollectionItem {
+            download_page: sale.and_then(|sale| download_pages.get(&sale).cloned()),
+            url: self.url,
+            title: self.title,
+            artist: self.artist,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct JsonItemCache {
+    #[serde(default)]
+    pub collection: HashMap<String, JsonCollectionItem>,
+
+    #[serde(default)]
+    pub wishlist: HashMap<String, JsonCollectionItem>,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct JsonCollectionData {
+    /// Download page urls of the purchases, by sale
+    #[serde(default)]
+    pub redownload_urls: HashMap<String, String>,
+
+    /// Total number of releases, the page only holds the first ones
+    #[serde(default)]
+    pub item_count: usize,
+
+    /// Token of the last release on the page, to request the next ones
+    #[serde(default)]
+    pub last_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonFanData {
+    pub fan_id: u64,
+}
+
+/// Data of a fan page, from its `data-blob` attribute
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonFanPage {
+    #[serde(default)]
+    pub fan_data: Option<JsonFanData>,
+
+    #[serde(default)]
+    pub item_cache: JsonItemCache,
+
+    #[serde(default)]
+    pub collection_data: JsonCollectionData,
+
+    #[serde(default)]
+    pub wishlist_data: JsonCollectionData,
+}
+
+impl JsonFanPage {
+    /// Returns the first releases of the collection (or wishlist), along with their download page
+    pub fn into_page(self, kind: CollectionKind) -> CollectionPage {
+        // purchases are only listed along with the collection
+        let download_pages = &self.collection_data.redownload_urls;
+        let (items, data) = match kind {
+            CollectionKind::Collection => (self.item_cache.collection, &self.collection_data),
+            CollectionKind::Wishlist => (self.item_cache.wishlist, &self.wishlist_data),
+        };
+
+        CollectionPage {
+            more_available: data.last_token.is_some() && data.item_count > items.len(),
+            items: items
+                .into_values()
+                .map(|item| item.into_item(download_pages))
+                .collect(),
+            fan_id: self.fan_data.map(|fan| fan.fan_id),
+            last_token: data.last_token.clone(),
+        }
+    }
+}
+
+/// Next releases of a fan collection (or wishlist), from the collection API
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonCollectionItems {
+    #[serde(default)]
+    pub items: Vec<JsonCollectionItem>,
+
+    #[serde(default)]
+    pub more_available: bool,
+
+    #[serde(default)]
+    pub last_token: Option<String>,
+
+    /// Download page urls of the purchases, by sale
+    #[serde(default)]
+    pub redownload_urls: HashMap<String, String>,
+}
+
+impl JsonCollectionItems {
+    /// Returns the releases, along with their download page
+    pub fn into_page(self, fan_id: Option<u64>) -> CollectionPage {
+        let download_pages = &self.redownload_urls;
+
+        CollectionPage {
+            items: self
+                .items
+                .into_iter()
+                .map(|item| item.into_item(download_pages))
+                .collect(),
+            fan_id,
+            last_token: self.last_token,
+            more_available: self.more_available,
+        }
+    }
+}
+
+/// Request of the collection API for the releases following `older_than_token`
+#[derive(Debug, Serialize, PartialEq)]
+pub struct JsonCollectionRequest<'a> {
+    pub fan_id: u64,
+    pub older_than_token: &'a str,
+    pub count: usize,
+}
+
+/// Download of a purchase in one format, from a download page
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonDownload {
+    #[serde(rename = "url")]
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonDigitalItem {
+    /// Downloads by format
+    #[serde(default)]
+    pub downloads: HashMap<String, JsonDownload>,
+}
+
+/// Data of a purchase download page, from its `data-blob` attribute
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonDownloadPage {
+    #[serde(rename = "digital_items")]
+    pub items: Vec<JsonDigitalItem>,
+}
+
+impl JsonDownloadPage {
+    /// Returns the download urls of the purchased release, by format
+    pub fn into_links(self) -> HashMap<String, String> {
+        self.items
+            .into_iter()
+            .next()
+            .map(|item| {
+                item.downloads
+                    .into_iter()
+                    .map(|(format, download)| (format, download.url))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// A release of a fan collection
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionItem {
+    /// The URL of the release page.
+    pub url: String,
+
+    /// The release title.
+    pub title: String,
+
+    /// The release artist.
+    pub artist: String,
+
+    /// The URL of the page to download the release from, if it was purchased.
+    pub download_page: Option<String>,
+}
+
+/// Releases listed on a fan page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionKind {
+    /// Releases bought (or downloaded for free) by the fan
+    Collection,
+    /// Releases the fan wishes to buy
+    Wishlist,
+}
+
+/// A batch of the releases of a fan collection (or wishlist)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionPage {
+    pub items: Vec<CollectionItem>,
+
+    /// The id of the fan, needed to request the next releases.
+    pub fan_id: Option<u64>,
+
+    /// The token of the last release of the batch, needed to request the next releases.
+    pub last_token: Option<String>,
+
+    /// True if more releases follow this batch.
+    pub more_available: bool,
+}
+
+/// Link to download a purchased release
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PurchaseLink {
+    /// The URL of the file (an archive of the tracks for albums).
+    pub url: String,
+
+    /// The format of the file (of the tracks of the archive for albums).
+    pub format: DownloadFormat,
+}
@@ -4,14 +4,13 @@ use serde::{Deserialize, Deserializer};
 use crate::model::{Album, Track};
 
 /// Convert bandcamp datetime string format to a chrono DateTime object
-fn datetime_from_str<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+pub(super) fn datetime_from_str<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
     D: Deserializer<'de>,
 {
     const FORMAT: &str = "%d %b %Y %T %z";
     let s = String::deserialize(deserializer)?;
     let s = s.replace("GMT", "+0000");
-    println!("{}", s);
     Utc.datetime_from_str(&s, FORMAT)
         .map_err(serde::de::Error::custom)
 }
@@ -24,6 +23,9 @@ pub struct JsonMp3File {
 
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct JsonTrack {
+    #[serde(rename = "id", default)]
+    pub id: Option<u64>,
+
     #[serde(rename = "duration")]
     pub duration: f32,
 
@@ -39,6 +41,10 @@ pub struct JsonTrack {
 
     #[serde(rename = "title")]
     pub title: String,
+
+    /// Set if the track is not by the artist of its album (eg. on a compilation)
+    #[serde(rename = "artist", default)]
+    pub artist: Option<String>,
 }
 
 impl JsonTrack {
@@ -53,7 +59,7 @@ impl JsonTrack {
         // For bandcamp track pages, Number will be 0. Set 1 instead
         let number = self.number.or(Some(1));
 
-        Track::new(
+        let mut track = Track::new(
             album,
             self.duration,
             self.lyrics,
@@ -61,8 +67,11 @@ impl JsonTrack {
             number.unwrap(),
             self.title,
             filename_format,
-        )
-        .into()
+        );
+        track.id = self.id;
+        track.set_artist(album, self.artist);
+
+        Some(track)
     }
 }
 
@@ -74,10 +83,26 @@ pub struct JsonAlbumData {
     #[serde(rename = "release_date")]
     #[serde(deserialize_with = "datetime_from_str")]
     pub release_date: DateTime<Utc>,
+
+    /// Set if the free download is sent by email
+    #[serde(rename = "require_email", default)]
+    pub require_email: Option<u32>,
+
+    #[serde(rename = "band_id", default)]
+    pub band_id: Option<u64>,
+
+    #[serde(rename = "about", default)]
+    pub about: Option<String>,
+
+    #[serde(rename = "credits", default)]
+    pub credits: Option<String>,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
 pub struct JsonAlbum {
+    #[serde(rename = "id", default)]
+    pub id: Option<u64>,
+
     #[serde(rename = "current")]
     pub album_data: JsonAlbumData,
 
@@ -85,14 +110,23 @@ pub struct JsonAlbum {
     pub art_id: Option<usize>,
 
     #[serde(rename = "artist")]
-    pub artist: String,
+    pub artist: Option<String>,
+
+    /// Page to download the release from, for free (or name your price) releases
+    #[serde(rename = "freeDownloadPage", default)]
+    pub free_download_page: Option<String>,
 
     #[serde(rename = "trackinfo")]
     pub tracks: Vec<JsonTrack>,
 }
 
 impl JsonAlbum {
-    pub fn into_album(self, folder_path: &str, filename_format: &str) -> Album {
+    pub fn into_album(
+        self,
+        label: Option<&str>,
+        folder_path: &str,
+        filename_format: &str,
+    ) -> Album {
         const URL_END: &str = "_0.jpg";
         // Uses the art_id variable to retrieve the image from Bandcamp hosting site
         const URL_START: &str = "https://f4.bcbits.com/img/a";
@@ -103,12 +137,19 @@ impl JsonAlbum {
             .map(|id| format!("{}{:010}{}", URL_START, id, URL_END));
 
         let mut album = Album::new(
-            &self.artist,
+            self.artist.as_deref().unwrap_or_default(),
             artwork_url.as_deref(),
+            label,
             &self.album_data.title,
             self.album_data.release_date,
             folder_path,
         );
+        album.free_download_page = self.free_download_page;
+        album.requires_email = self.album_data.require_email.unwrap_or(0) > 0;
+        album.id = self.id;
+        album.band_id = self.album_data.band_id;
+        album.about = self.album_data.about;
+        album.credits = self.album_data.credits;
 
         // Some tracks do not have their URL filled on some albums (pre-release...)
         // Forget those tracks here
@@ -122,6 +163,21 @@ impl JsonAlbum {
     }
 }
 
+/// Release of a music grid, from the `data-client-items` attribute
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonClientItem {
+    /// Release page url, relative to the band page for its own releases
+    #[serde(rename = "page_url")]
+    pub page_url: String,
+}
+
+/// Band (or label) account hosting a page, from the `data-band` attribute
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonBand {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -143,8 +199,10 @@ mod test {
         let test_str = r#"{"video_mobile_url":null,"album_preorder":false,"file":{"mp3-128":"https://t4.bcbits.com/stream/f19f73f3022113d2e0362cc017a2640f/mp3-128/3291645056?p=0&ts=1593226703&t=c000e57bbab5d336049099dbdad88ee289a8706a&token=1593226703_7712a8c4b48e9e7c5d5658b30794b2bf02cf9392"},"encoding_pending":null,"lyrics":null,"has_free_download":null,"streaming":1,"video_poster_url":null,"unreleased_track":false,"play_count":null,"is_draft":false,"free_album_download":false,"video_caption":null,"title_link":"/track/sleepover","is_capped":null,"sizeof_lyrics":0,"video_featured":null,"has_lyrics":false,"video_source_type":null,"private":null,"title":"Sleepover","alt_link":null,"has_info":false,"track_id":3291645056,"track_license_id":null,"video_source_id":null,"track_num":1,"encodings_id":3274042554,"id":3291645056,"encoding_error":null,"video_id":null,"duration":157.204,"is_downloadable":true,"license_type":1}"#;
         let actual: JsonTrack = serde_json::from_str(test_str).unwrap();
         let expected = JsonTrack {
+            id: Some(3291645056),
             duration:  157.204,
             title: String::from("Sleepover"),
+            artist: None,
             number: Some(1),
             lyrics: None,
             file: Some(JsonMp3File {
@@ -164,6 +222,10 @@ mod test {
             release_date: Utc
                 .datetime_from_str("06 Oct 2017 00:00:00 +0000", "%d %b %Y %T %z")
                 .unwrap(),
+            require_email: None,
+            band_id: Some(4055192856),
+            about: Some(String::from("Late night music from the back seat. ‘Are You Anywhere‘, sophomore album from Tokyo based UK native submerse blends early 90’s slow-jams and instrumental hip hop wrapped in smooth DX7 keys hybridized with his own unique sound. ‘Are You Anywhere’ is the second full length release from submerse on Project: Mooncircle with features from fellow Tokyo based beat maker fitz ambro$e.\r\n\r\n‘Are You Anywhere‘ comes out worldwide on limited green colored vinyl (including download code) & limited edition CD via Perfect Touch in Japan.\r\n\r\nMore information: http://projectmooncircle.com/releases/submerse-are-you-anywhere/")),
+            credits: None,
         };
         assert_eq!(actual, expected);
     }
@@ -202,17 +264,25 @@ mod test {
 }"#;
         let actual: JsonAlbum = serde_json::from_str(test_str).unwrap();
         let expected = JsonAlbum {
-            artist: String::from("The Racers"),
+            artist: Some(String::from("The Racers")),
+            id: Some(350943074),
             art_id: Some(2129006133),
+            free_download_page: None,
             album_data: JsonAlbumData {
                 title: String::from("Final Lap"),
                 release_date: Utc
                     .datetime_from_str("24 Apr 2020 00:00:00 +0000", "%d %b %Y %T %z")
                     .unwrap(),
+                require_email: None,
+                band_id: Some(1173700968),
+                about: None,
+                credits: None,
             },
             tracks: vec![JsonTrack {
+                id: Some(350943074),
                 duration:  311.327,
                 title: String::from("Final Lap"),
+                artist: None,
                 number: None,
                 lyrics: None,
                 file: Some(JsonMp3File {
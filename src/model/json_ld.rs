@@ -0,0 +1,278 @@
+//! schema.org structured data (JSON-LD) embedded in the Bandcamp pages
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use super::json::datetime_from_str;
+use crate::model::{Album, Track};
+
+/// Convert an optional bandcamp datetime string to a chrono DateTime object
+fn optional_datetime_from_str<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    datetime_from_str(deserializer).map(Some)
+}
+
+/// Returns the number of seconds of an ISO 8601 duration (eg. `P00H05M11S`)
+fn parse_duration(duration: &str) -> Option<f32> {
+    lazy_static! {
+        static ref DURATION_RE: Regex =
+            Regex::new(r"^P(?:T)?(?:(?P<h>\d+)H)?(?:(?P<m>\d+)M)?(?:(?P<s>\d+(?:\.\d+)?)S)?$")
+                .unwrap();
+    }
+
+    let captures = DURATION_RE.captures(duration)?;
+    let value = |name| {
+        captures
+            .name(name)
+            .and_then(|value| value.as_str().parse::<f32>().ok())
+            .unwrap_or(0.0)
+    };
+
+    Some(value("h") * 3600.0 + value("m") * 60.0 + value("s"))
+}
+
+/// A named entity (artist, label...)
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonLdEntity {
+    #[serde(rename = "name")]
+    pub name: String,
+}
+
+/// Extra property of a recording (eg. its stream url)
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonLdProperty {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    #[serde(rename = "value")]
+    pub value: serde_json::Value,
+}
+
+/// Keywords, either as a list or as comma separated text
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum JsonLdKeywords {
+    List(Vec<String>),
+    Text(String),
+}
+
+impl JsonLdKeywords {
+    fn into_tags(self) -> Vec<String> {
+        match self {
+            Self::List(tags) => tags,
+            Self::Text(text) => text
+                .split(',')
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// schema.org `MusicRecording`
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonLdRecording {
+    #[serde(rename = "name")]
+    pub name: String,
+
+    /// ISO 8601 duration
+    #[serde(rename = "duration")]
+    pub duration: Option<String>,
+
+    #[serde(rename = "byArtist")]
+    pub artist: Option<JsonLdEntity>,
+
+    #[serde(rename = "additionalProperty", default)]
+    pub properties: Vec<JsonLdProperty>,
+}
+
+impl JsonLdRecording {
+    fn property(&self, name: &str) -> Option<&serde_json::Value> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+            .map(|property| &property.value)
+    }
+
+    pub fn into_track(self, album: &Album, number: u32, filename_format: &str) -> Option<Track> {
+        // Tracks which cannot be streamed have no url
+        let mp3_url = self.property("file_mp3-128")?.as_str()?.to_string();
+        let duration = self
+            .property("duration_secs")
+            .and_then(|value| value.as_f64())
+            .map(|secs| secs as f32)
+            .or_else(|| self.duration.as_deref().and_then(parse_duration))
+            .unwrap_or_default();
+
+        let mut track = Track::new(
+            album,
+            duration,
+            None,
+            mp3_url,
+            number,
+            self.name,
+            filename_format,
+        );
+        track.set_artist(album, self.artist.map(|artist| artist.name));
+
+        Some(track)
+    }
+}
+
+/// Element of a track list
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonLdListItem {
+    #[serde(rename = "position")]
+    pub position: Option<u32>,
+
+    #[serde(rename = "item")]
+    pub item: JsonLdRecording,
+}
+
+/// Track list of an album
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonLdTrackList {
+    #[serde(rename = "itemListElement", default)]
+    pub items: Vec<JsonLdListItem>,
+}
+
+/// schema.org `MusicAlbum` (or `MusicRecording`, for track pages) structured data
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct JsonLdAlbum {
+    /// Album (or track) title
+    #[serde(rename = "name")]
+    pub name: Option<String>,
+
+    #[serde(rename = "byArtist")]
+    pub artist: Option<JsonLdEntity>,
+
+    /// Label (or account) which published the album
+    #[serde(rename = "publisher")]
+    pub publisher: Option<JsonLdEntity>,
+
+    #[serde(rename = "datePublished", default)]
+    #[serde(deserialize_with = "optional_datetime_from_str")]
+    pub date_published: Option<DateTime<Utc>>,
+
+    /// Cover art url
+    #[serde(rename = "image")]
+    pub image: Option<String>,
+
+    #[serde(rename = "keywords")]
+    pub keywords: Option<JsonLdKeywords>,
+
+    /// Album tracks, absent from track pages
+    #[serde(rename = "track")]
+    pub tracks: Option<JsonLdTrackList>,
+
+    /// Track duration, on track pages
+    #[serde(rename = "duration")]
+    pub duration: Option<String>,
+
+    /// Track properties, on track pages
+    #[serde(rename = "additionalProperty", default)]
+    pub properties: Vec<JsonLdProperty>,
+}
+
+impl JsonLdAlbum {
+    /// Takes the tags of the album
+    pub fn take_tags(&mut self) -> Vec<String> {
+        self.keywords
+            .take()
+            .map(JsonLdKeywords::into_tags)
+            .unwrap_or_default()
+    }
+
+    /// Takes the name of the label which published the album, unless it is its artist (eg. a
+    /// self-released album)
+    pub fn take_label(&mut self) -> Option<String> {
+        let publisher = self.publisher.take()?;
+        match &self.artist {
+            Some(artist) if artist.name == publisher.name => None,
+            _ => Some(publisher.name),
+        }
+    }
+
+    /// Build an Album from the structured data, published by `label` if known. Returns None if
+    /// the title or the release date are missing.
+    pub fn into_album(
+        mut self,
+        label: Option<&str>,
+        folder_path: &str,
+        filename_format: &str,
+    ) -> Option<Album> {
+        let title = self.name.take()?;
+        let release_date = self.date_published?;
+        let label = label.map(String::from).or_else(|| self.take_label());
+        let artist = self
+            .artist
+            .take()
+            .map(|artist| artist.name)
+            .unwrap_or_default();
+
+        let mut album = Album::new(
+            &artist,
+            self.image.as_deref(),
+            label.as_deref(),
+            &title,
+            release_date,
+            folder_path,
+        );
+        album.tags = self.take_tags();
+
+        album.tracks = match self.tracks {
+            Some(list) => {
+                let mut items = list.items;
+                items.sort_by_key(|item| item.position);
+                items
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, item)| {
+                        let number = item.position.unwrap_or(i as u32 + 1);
+                        item.item.into_track(&album, number, filename_format)
+                    })
+                    .collect()
+            }
+            // a track page describes a single recording
+            None => {
+                let recording = JsonLdRecording {
+                    name: title,
+                    duration: self.duration,
+                    artist: None,
+                    properties: self.properties,
+                };
+                recording
+                    .into_track(&album, 1, filename_format)
+                    .into_iter()
+                    .collect()
+            }
+        };
+
+        Some(album)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_duration() {
+        let msg = "should parse hours, minutes and seconds";
+        assert_eq!(parse_duration("P00H05M11S"), Some(311.0), "{}", msg);
+        assert_eq!(parse_duration("PT1H2M3.5S"), Some(3723.5), "{}", msg);
+
+        let msg = "should reject other formats";
+        assert_eq!(parse_duration("05:11"), None, "{}", msg);
+    }
+
+    #[test]
+    fn splits_keywords() {
+        let keywords: JsonLdKeywords =
+            serde_json::from_str(r#""electronic, synthwave,Paris""#).unwrap();
+        let expected = vec!["electronic", "synthwave", "Paris"];
+        assert_eq!(keywords.into_tags(), expected);
+    }
+}
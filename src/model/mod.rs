@@ -1,9 +1,13 @@
 pub use album::Album;
+pub use collection::*;
 pub use json::*;
+pub use json_ld::*;
 pub use track::Track;
 
 mod album;
+mod collection;
 mod json;
+mod json_ld;
 mod track;
 
 enum FileType {
@@ -1,12 +1,21 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
 use super::Album;
 use crate::helper;
 
+/// Tracks saved before the selection was introduced were all downloaded
+fn selected_by_default() -> bool {
+    true
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Track {
+    /// The Bandcamp id of the track, if known.
+    #[serde(default)]
+    pub id: Option<u64>,
+
     /// The track length (in seconds).
     pub duration: f32,
 
@@ -22,8 +31,23 @@ pub struct Track {
     /// The local path (full path with file name) where the track file should be saved.
     pub path: String,
 
+    /// The file of another release holding the same track, if it is not downloaded again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+
+    /// True if the track should be downloaded along with its album.
+    #[serde(default = "selected_by_default")]
+    pub selected: bool,
+
+    /// The file size (in bytes), if it was retrieved before downloading.
+    pub size: Option<u64>,
+
     /// The track title.
     pub title: String,
+
+    /// The track artist, if it differs from the artist of its album (eg. on a compilation).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
 }
 
 impl Track {
@@ -38,18 +62,36 @@ impl Track {
         filename_format: &str,
     ) -> Self {
         let mut track = Self {
+            id: None,
             duration,
             lyrics,
             mp3_url,
             number,
             title,
+            artist: None,
             path: String::new(),
+            duplicate_of: None,
+            selected: true,
+            size: None,
         };
         track.path = track.parse_track_file_path(album, filename_format);
 
         track
     }
 
+    /// Sets the artist of the track to `artist` if known, or to the one of its "Artist - Title"
+    /// title if `album` is a compilation, unless it is the artist of `album`.
+    pub(crate) fn set_artist(&mut self, album: &Album, artist: Option<String>) {
+        let artist = artist.or_else(|| {
+            if !album.is_compilation() {
+                return None;
+            }
+            let (artist, _) = self.title.split_once(" - ")?;
+            Some(artist.trim().to_string())
+        });
+        self.artist = artist.filter(|artist| !artist.is_empty() && *artist != album.artist);
+    }
+
     /// Returns the file name to be used for the track from the provided file name format, by
     /// replacing the placeholders strings with their corresponding values.
     /// The returned file name DOES contain the extension.
@@ -61,6 +103,16 @@ impl Track {
         helper::sanitize_file_name(&file_name)
     }
 
+    /// Sets the path of the track to the one of `filename_format` in the folder of `album`,
+    /// keeping the extension of its current file (eg. the one of a purchase).
+    pub(crate) fn relocate(&mut self, album: &Album, filename_format: &str) {
+        let mut path = PathBuf::from(self.parse_track_file_path(album, filename_format));
+        if let Some(extension) = Path::new(&self.path).extension() {
+            path.set_extension(extension);
+        }
+        self.path = path.to_string_lossy().into();
+    }
+
     /// Returns the file path to be used for the track from the file name format saved in the UserSettings, by
     /// replacing the placeholders strings with their corresponding values. The returned file path DOES contain the extension.
     fn parse_track_file_path(&self, album: &Album, filename_format: &str) -> String {
@@ -3,9 +3,11 @@ use std::{fs, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::core::tag::EditAction;
+use crate::core::{
+    tag::EditAction, DuplicateAction, Hooks, ReleaseFilter, TrackSelection, Transcoding,
+};
 use crate::ui::Theme;
-use crate::{helper, Result};
+use crate::{error::Error, helper, Result};
 
 /// UI localization option
 #[derive(Deserialize, Debug, Copy, Clone, Eq, PartialEq, Serialize)]
@@ -63,6 +65,124 @@ impl std::fmt::Display for PlaylistFormat {
     }
 }
 
+/// Formats purchased releases can be downloaded in
+#[derive(Debug, Deserialize, Copy, Clone, Eq, PartialEq, Serialize)]
+pub enum DownloadFormat {
+    Mp3_320,
+    Mp3V0,
+    Flac,
+    Alac,
+    Wav,
+    AiffLossless,
+    Aac,
+    Vorbis,
+}
+
+impl DownloadFormat {
+    /// Every format, from the highest quality to the lowest of each kind
+    pub const ALL: [DownloadFormat; 8] = [
+        Self::Flac,
+        Self::Alac,
+        Self::Wav,
+        Self::AiffLossless,
+        Self::Mp3_320,
+        Self::Mp3V0,
+        Self::Aac,
+        Self::Vorbis,
+    ];
+
+    /// Format of the purchases downloads when none is set
+    fn default_preferred_format() -> Self {
+        Self::Mp3_320
+    }
+
+    /// True if the audio files in this format are not compressed with losses
+    pub fn is_lossless(&self) -> bool {
+        matches!(
+            self,
+            Self::Flac | Self::Alac | Self::Wav | Self::AiffLossless
+        )
+    }
+
+    /// Returns the formats to download the purchases in, by order of preference: this format,
+    /// then the other formats of the same kind (lossless or lossy), then the remaining ones.
+    pub fn fallback_order(&self) -> Vec<Self> {
+        let (same_kind, other_kind): (Vec<_>, Vec<_>) = Self::ALL
+            .iter()
+            .filter(|&format| format != self)
+            .partition(|format| format.is_lossless() == self.is_lossless());
+
+        std::iter::once(*self)
+            .chain(same_kind)
+            .chain(other_kind)
+            .collect()
+    }
+
+    /// Returns the name of the format on Bandcamp download pages
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Mp3_320 => "mp3-320",
+            Self::Mp3V0 => "mp3-v0",
+            Self::Flac => "flac",
+            Self::Alac => "alac",
+            Self::Wav => "wav",
+            Self::AiffLossless => "aiff-lossless",
+            Self::Aac => "aac-hi",
+            Self::Vorbis => "vorbis",
+        }
+    }
+
+    /// Returns the extension of the audio files in this format
+    pub fn extension(&self) -> &str {
+        match self {
+            Self::Mp3_320 | Self::Mp3V0 => "mp3",
+            Self::Flac => "flac",
+            Self::Alac | Self::Aac => "m4a",
+            Self::Wav => "wav",
+            Self::AiffLossless => "aiff",
+            Self::Vorbis => "ogg",
+        }
+    }
+
+    pub fn description(&self) -> &str {
+        match self {
+            Self::Mp3_320 => "MP3 320",
+            Self::Mp3V0 => "MP3 V0",
+            Self::Flac => "FLAC",
+            Self::Alac => "ALAC",
+            Self::Wav => "WAV",
+            Self::AiffLossless => "AIFF",
+            Self::Aac => "AAC",
+            Self::Vorbis => "Ogg Vorbis",
+        }
+    }
+}
+
+impl std::fmt::Display for DownloadFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl std::str::FromStr for DownloadFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .find(|format| format.value().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| {
+                let formats: Vec<_> = Self::ALL.iter().map(DownloadFormat::value).collect();
+                Error::Usage(format!(
+                    r#"unknown format "{}", expected one of {}"#,
+                    s,
+                    formats.join(", ")
+                ))
+            })
+    }
+}
+
 /// User configurable application settings
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct UserSettings {
@@ -74,6 +194,56 @@ pub struct UserSettings {
 
     /// Download entire artist discography
     pub download_artist_discography: bool,
+    /// Comma separated names of the artists to download from label discographies. Every artist is
+    /// downloaded if empty.
+    #[serde(default)]
+    pub label_artist_filter: String,
+    /// Filters the releases found must match to be downloaded
+    #[serde(default)]
+    pub release_filter: ReleaseFilter,
+    /// Track numbers to download from every album, set for the session from the command line
+    #[serde(skip)]
+    pub track_selection: TrackSelection,
+    /// Urls of the followed artists (or labels), whose new releases are downloaded
+    #[serde(default)]
+    pub subscriptions: Vec<String>,
+    /// Time in minutes between two checks of the subscriptions for new releases. Never checked
+    /// if 0.
+    #[serde(default)]
+    pub subscription_check_interval: u64,
+
+    // Bandcamp account
+    /// Value of the Bandcamp `identity` cookie of the user, authenticating the requests to their
+    /// collection
+    #[serde(default)]
+    pub identity_cookie: String,
+    /// Path to a cookies.txt file holding the Bandcamp cookies, used if no identity cookie is set
+    #[serde(default)]
+    pub cookies_file: String,
+    /// Url of the user fan page (eg. https://bandcamp.com/username), listing their purchases
+    #[serde(default)]
+    pub fan_page_url: String,
+    /// Preferred format of the purchased releases downloads, the closest available format is
+    /// downloaded otherwise
+    #[serde(
+        alias = "purchase_format",
+        default = "DownloadFormat::default_preferred_format"
+    )]
+    pub preferred_format: DownloadFormat,
+    /// Download the free (or name your price) releases from their download page, in the preferred
+    /// format, instead of their streams
+    #[serde(default)]
+    pub download_free_releases: bool,
+    /// Write the album metadata to an `album.json` file in the album folder
+    #[serde(default)]
+    pub write_album_json: bool,
+    /// Write the album metadata to an `album.nfo` file in the album folder
+    #[serde(default)]
+    pub write_album_nfo: bool,
+    /// What to do with the tracks already saved from another release
+    #[serde(default)]
+    pub duplicates: DuplicateAction,
+
     /// Maximum number of download attempts
     pub download_max_tries: u32,
     /// If true, download albums serially; concurrent download otherwise.
@@ -107,6 +277,13 @@ pub struct UserSettings {
     /// Action to apply if modifying id3 Date fields
     pub tag_year: EditAction,
 
+    /// Transcoding of the downloaded tracks by an external encoder
+    #[serde(default)]
+    pub transcoding: Transcoding,
+    /// User commands run on download events
+    #[serde(default)]
+    pub hooks: Hooks,
+
     // playlist settings
     /// Create playlists for downloaded albums
     pub create_playlist: bool,
@@ -151,12 +328,30 @@ impl Default for UserSettings {
 
             file_name_format: String::from("{tracknum} {artist} - {title}.mp3"),
 
+            transcoding: Transcoding::default(),
+            hooks: Hooks::default(),
+
             create_playlist: false,
             playlist_format: PlaylistFormat::M3U,
             playlist_file_name_format: String::from("{album}"),
 
             downloads_path,
             download_artist_discography: false,
+            label_artist_filter: String::new(),
+            release_filter: ReleaseFilter::default(),
+            track_selection: TrackSelection::default(),
+            subscriptions: Vec::new(),
+            subscription_check_interval: 60,
+
+            identity_cookie: String::new(),
+            cookies_file: String::new(),
+            fan_page_url: String::new(),
+            preferred_format: DownloadFormat::default_preferred_format(),
+            download_free_releases: false,
+            write_album_json: false,
+            write_album_nfo: false,
+            duplicates: DuplicateAction::Download,
+
             download_one_album_at_a_time: false,
             download_max_tries: 7,
             download_retry_cooldown: 0.2,
@@ -197,6 +392,25 @@ impl UserSettings {
         serde_json::from_str(&settings).map_err(From::from)
     }
 
+    /// Follow the artist at `url`. Returns false if it was already followed.
+    pub fn subscribe(&mut self, url: &str) -> bool {
+        let url = url.trim();
+        if url.is_empty() || self.subscriptions.iter().any(|followed| followed == url) {
+            return false;
+        }
+
+        self.subscriptions.push(url.to_string());
+        true
+    }
+
+    /// Stop following the artist at `url`. Returns false if it was not followed.
+    pub fn unsubscribe(&mut self, url: &str) -> bool {
+        let count = self.subscriptions.len();
+        self.subscriptions.retain(|followed| followed != url.trim());
+
+        self.subscriptions.len() != count
+    }
+
     /// Attempt to save user settings to the filesystem
     pub fn save(&self) -> Result<()> {
         let mut path = helper::get_root_dir();
@@ -207,3 +421,33 @@ impl UserSettings {
         fs::write(path, settings).map_err(From::from)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn orders_fallback_formats() {
+        let msg = "should fall back on the formats of the same kind first";
+        let expected = vec![
+            DownloadFormat::Alac,
+            DownloadFormat::Flac,
+            DownloadFormat::Wav,
+            DownloadFormat::AiffLossless,
+            DownloadFormat::Mp3_320,
+            DownloadFormat::Mp3V0,
+            DownloadFormat::Aac,
+            DownloadFormat::Vorbis,
+        ];
+        assert_eq!(DownloadFormat::Alac.fallback_order(), expected, "{}", msg);
+
+        let actual = &DownloadFormat::Vorbis.fallback_order()[..4];
+        let expected = [
+            DownloadFormat::Vorbis,
+            DownloadFormat::Mp3_320,
+            DownloadFormat::Mp3V0,
+            DownloadFormat::Aac,
+        ];
+        assert_eq!(actual, expected, "{}", msg);
+    }
+}
@@ -0,0 +1,167 @@
+//! Bandcamp source
+use async_trait::async_trait;
+
+use super::{Release, Source};
+use crate::{
+    core::Logger,
+    error::Error,
+    helper::{self, Page},
+    model::Album,
+    Result,
+};
+
+/// Albums and tracks hosted on Bandcamp
+#[derive(Debug, Default)]
+pub struct Bandcamp {
+    // TODO: proxy support
+    client: reqwest::Client,
+}
+
+impl Bandcamp {
+    /// Create a new instance of this struct
+    pub fn new() -> Self {
+        Self {
+            client: crate::client().clone(),
+        }
+    }
+
+    /// Get artist "music" bandcamp page (http://artist.bandcamp.com/music)
+    async fn music_page_url(&self, url: &str, logger: &Logger) -> Result<String> {
+        // Retrieve URL HTML source code
+        let raw_html = match crate::client_get_url_text(&self.client, url).await {
+            Ok(res) => res,
+            Err(e) => {
+                logger.error(format!("Could not retrieve data for {}: {}", url, e));
+                return Err(e);
+            }
+        };
+
+        // Get artist "music" bandcamp page (http://artist.bandcamp.com/music)
+        Page::parse(&raw_html)
+            .band_url()
+            .map(|url| format!("{}{}", url, "/music"))
+            .ok_or_else(|| {
+                logger.error(format!("No discography could be found on {}. Try to uncheck the \"Download artist discography\" option", url));
+                Error::NoDiscography
+            })
+    }
+
+    /// Returns the artist's (or label's) discography from any URL (artist, label, album, track).
+    async fn discography(&self, url: &str, logger: &Logger) -> Result<Vec<Release>> {
+        logger.info(format!("Retrieving artist discography from {}", url));
+
+        // Get artist "music" bandcamp page (http://artist.bandcamp.com/music)
+        let music_page_url = match self.music_page_url(url, logger).await {
+            Ok(res) => res,
+            Err(e) => {
+                logger.error(format!("Could not retrieve music page url for {}", url));
+                return Err(e);
+            }
+        };
+
+        // Retrieve artist "music" page HTML source code
+        let raw_html = match crate::client_get_url_text(&self.client, &music_page_url).await {
+            Ok(res) => res,
+            Err(e) => {
+                logger.error(format!(
+                    "Could not retrieve HTML for {}: {}",
+                    music_page_url, e
+                ));
+                return Err(e);
+            }
+        };
+
+        // Releases found on a label page are filed under the label
+        let label = helper::get_label(&raw_html);
+        if let Some(label) = &label {
+            logger.info(format!(
+                "{} is the page of the label {}",
+                music_page_url, label
+            ));
+        }
+
+        let mut albums_urls = Vec::new();
+        match helper::get_albums_url(&raw_html) {
+            Err(_) => {
+                logger.warn(format!("No referred album could be found on {}. Try to uncheck the \"Download artist discography\" option", music_page_url));
+            }
+            Ok(found_albums) => {
+                albums_urls.extend(found_albums);
+            }
+        }
+
+        if albums_urls.is_empty() {
+            // This seem to be a one-album artist with no "music" page => URL redirects to the unique album URL
+            albums_urls.push(url.to_string());
+        }
+
+        let releases = albums_urls
+            .into_iter()
+            .map(|url| Release {
+                url,
+                label: label.clone(),
+            })
+            .collect();
+        Ok(releases)
+    }
+}
+
+#[async_trait]
+impl Source for Bandcamp {
+    fn name(&self) -> &str {
+        "Bandcamp"
+    }
+
+    /// Bandcamp pages may be served from an artist's own domain, so any url is accepted. This
+    /// source should therefore be the last one tried.
+    fn handles(&self, _url: &str) -> bool {
+        true
+    }
+
+    async fn releases(
+        &self,
+        url: &str,
+        discography: bool,
+        logger: &Logger,
+    ) -> Result<Vec<Release>> {
+        if discography {
+            self.discography(url, logger).await
+        } else {
+            Ok(vec![Release::new(url)])
+        }
+    }
+
+    async fn album(
+        &self,
+        release: &Release,
+        save_dir: &str,
+        filename_format: &str,
+    ) -> Result<Album> {
+        // TODO cancellation
+        let raw_html = crate::client_get_url_text(&self.client, &release.url).await?;
+
+        helper::get_album(
+            &raw_html,
+            release.label.as_deref(),
+            save_dir,
+            filename_format,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helper::{mock_server::MockServer, test_helper::test_logger};
+
+    #[tokio::test]
+    async fn gets_music_page() {
+        let server = MockServer::bandcamp().await;
+        let source = Bandcamp::new();
+        let url = server.url("/album/first-album");
+
+        let expected = server.url("/music");
+        let actual = source.music_page_url(&url, &test_logger()).await.unwrap();
+        assert_eq!(actual, expected,);
+    }
+}
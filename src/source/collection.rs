@@ -0,0 +1,273 @@
+//! Fan collections and wishlists, along with the purchases of the logged in user
+use async_trait::async_trait;
+use reqwest::{header::CONTENT_TYPE, RequestBuilder, Url};
+
+use super::{Bandcamp, Release, Source};
+use crate::{
+    core::{Logger, Session},
+    error::Error,
+    helper,
+    model::{
+        Album, CollectionItem, CollectionKind, JsonCollectionItems, JsonCollectionRequest,
+        PurchaseLink,
+    },
+    settings::DownloadFormat,
+    Result,
+};
+
+/// Bandcamp pages found at `bandcamp.com/<name>` which are not fan pages
+const RESERVED_PATHS: [&str; 11] = [
+    "about",
+    "api",
+    "artists",
+    "discover",
+    "fans",
+    "help",
+    "login",
+    "search",
+    "signup",
+    "tag",
+    "terms_of_use",
+];
+
+/// Number of releases requested at once from the collection API
+const BATCH_SIZE: usize = 100;
+
+/// Releases of the collection (or wishlist) of a Bandcamp fan, found on their public fan page
+/// (`bandcamp.com/<name>` or `bandcamp.com/<name>/wishlist`)
+#[derive(Debug, Default)]
+pub struct Collection {
+    /// Source of the releases of the collection
+    bandcamp: Bandcamp,
+}
+
+impl Collection {
+    /// Create a new instance of this struct
+    pub fn new() -> Self {
+        Self {
+            bandcamp: Bandcamp::new(),
+        }
+    }
+}
+
+/// Returns the kind of releases listed on the fan page at `url`, or None if `url` is not a fan
+/// page
+fn kind(url: &str) -> Option<CollectionKind> {
+    let url = Url::parse(url).ok()?;
+    if !matches!(
+        url.host_str(),
+        Some("bandcamp.com") | Some("www.bandcamp.com")
+    ) {
+        return None;
+    }
+
+    let segments: Vec<_> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    match segments[..] {
+        [name, ..] if RESERVED_PATHS.contains(&name) => None,
+        [_] => Some(CollectionKind::Collection),
+        [_, "wishlist"] => Some(CollectionKind::Wishlist),
+        _ => None,
+    }
+}
+
+/// Returns the url of the collection API listing the releases of the fan page at `url`
+fn api_url(url: &str, kind: CollectionKind) -> Result<String> {
+    let url = Url::parse(url).map_err(|e| Error::Api(e.to_string()))?;
+    let endpoint = match kind {
+        CollectionKind::Collection => "collection_items",
+        CollectionKind::Wishlist => "wishlist_items",
+    };
+    Ok(format!(
+        "{}/api/fancollection/1/{}",
+        url.origin().ascii_serialization(),
+        endpoint
+    ))
+}
+
+/// Add the `session` cookies, if any, to `request`
+fn authenticate(request: RequestBuilder, session: Option<&Session>) -> RequestBuilder {
+    match session {
+        Some(session) => session.authenticate(request),
+        None => request,
+    }
+}
+
+/// Returns the releases of the collection (or wishlist) of the fan page at `url`, requesting the
+/// ones missing from the page to the collection API
+async fn items(
+    url: &str,
+    kind: CollectionKind,
+    session: Option<&Session>,
+    logger: &Logger,
+) -> Result<Vec<CollectionItem>> {
+    let client = crate::client();
+    let raw_html = authenticate(client.get(url), session)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    let mut page = helper::get_collection(&raw_html, kind)?;
+    let mut items = std::mem::take(&mut page.items);
+    let api_url = api_url(url, kind)?;
+
+    // The page only holds the first releases
+    while page.more_available {
+        let (fan_id, token) = match (page.fan_id, &page.last_token) {
+            (Some(fan_id), Some(token)) => (fan_id, token),
+            _ => break,
+        };
+        logger.verbose(format!(
+            "Retrieving the next releases of {} ({} found)",
+            url,
+            items.len()
+        ));
+
+        let request = JsonCollectionRequest {
+            fan_id,
+            older_than_token: token,
+            count: BATCH_SIZE,
+        };
+        let body = serde_json::to_string(&request)?;
+        let response = authenticate(client.post(&api_url), session)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let mut next =
+            serde_json::from_str::<JsonCollectionItems>(&response)?.into_page(page.fan_id);
+        if next.items.is_empty() {
+            break;
+        }
+        items.append(&mut next.items);
+        page = next;
+    }
+
+    Ok(items)
+}
+
+/// Returns the purchased releases listed on the fan page at `fan_url`
+pub async fn purchases(
+    session: &Session,
+    fan_url: &str,
+    logger: &Logger,
+) -> Result<Vec<CollectionItem>> {
+    logger.verbose(format!("Retrieving the collection of {}", fan_url));
+    let mut items = items(fan_url, CollectionKind::Collection, Some(session), logger).await?;
+
+    items.retain(|item| item.download_page.is_some());
+    logger.info(format!(
+        "Found {} purchased releases in the collection",
+        items.len()
+    ));
+    Ok(items)
+}
+
+/// Returns the link to download the purchased `item` in the first available of `formats`, or None
+/// if the release is available in none of them
+pub async fn purchase_link(
+    session: &Session,
+    item: &CollectionItem,
+    formats: &[DownloadFormat],
+) -> Result<Option<PurchaseLink>> {
+    let download_page = match &item.download_page {
+        Some(download_page) => download_page,
+        None => return Ok(None),
+    };
+    let raw_html = session.get_url_text(download_page).await?;
+    helper::get_download_link(&raw_html, formats)
+}
+
+#[async_trait]
+impl Source for Collection {
+    fn name(&self) -> &str {
+        "Bandcamp collection"
+    }
+
+    fn handles(&self, url: &str) -> bool {
+        kind(url).is_some()
+    }
+
+    /// The releases of the collection are returned whether `discography` is set or not
+    async fn releases(&self, url: &str, _: bool, logger: &Logger) -> Result<Vec<Release>> {
+        let kind = kind(url).unwrap_or(CollectionKind::Collection);
+        logger.info(format!("Retrieving the releases of the fan page {}", url));
+
+        let items = match items(url, kind, None, logger).await {
+            Ok(items) => items,
+            Err(e) => {
+                logger.error(format!("Could not retrieve the releases of {}: {}", url, e));
+                return Err(e);
+            }
+        };
+        if items.is_empty() {
+            logger.warn(format!("No release could be found on {}", url));
+        }
+
+        Ok(items.iter().map(|item| Release::new(&item.url)).collect())
+    }
+
+    async fn album(
+        &self,
+        release: &Release,
+        save_dir: &str,
+        filename_format: &str,
+    ) -> Result<Album> {
+        self.bandcamp
+            .album(release, save_dir, filename_format)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::helper::{mock_server::MockServer, test_helper::test_logger};
+
+    #[test]
+    fn recognises_fan_pages() {
+        let msg = "should recognise the collections and wishlists";
+        let actual = kind("https://bandcamp.com/mockfan");
+        assert_eq!(actual, Some(CollectionKind::Collection), "{}", msg);
+        let actual = kind("https://bandcamp.com/mockfan/wishlist/");
+        assert_eq!(actual, Some(CollectionKind::Wishlist), "{}", msg);
+
+        let msg = "should ignore the other pages";
+        assert_eq!(kind("https://bandcamp.com/discover"), None, "{}", msg);
+        assert_eq!(kind("https://bandcamp.com/tag/synthwave"), None, "{}", msg);
+        assert_eq!(
+            kind("https://theracers.bandcamp.com/music"),
+            None,
+            "{}",
+            msg
+        );
+        assert_eq!(kind("https://bandcamp.com/"), None, "{}", msg);
+    }
+
+    #[tokio::test]
+    async fn lists_every_page() {
+        let server = MockServer::collection().await;
+        let logger = test_logger();
+
+        let msg = "should list the releases of the following pages";
+        let url = server.url("/fan/wishlist");
+        let mut actual: Vec<_> = items(&url, CollectionKind::Wishlist, None, &logger)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|item| item.title)
+            .collect();
+        actual.sort();
+        assert_eq!(
+            actual,
+            vec!["Fourth Album", "Second Album", "Third Album"],
+            "{}",
+            msg
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Sources resolving the urls supplied by the user into albums to download
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+
+use crate::{core::Logger, model::Album, Result};
+
+pub use bandcamp::Bandcamp;
+pub use collection::Collection;
+
+mod bandcamp;
+pub mod collection;
+
+/// A release (album or track) found by a source
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Release {
+    /// Url of the release page
+    pub url: String,
+    /// Label the release was found on, if any
+    pub label: Option<String>,
+}
+
+impl Release {
+    /// Create a release which does not belong to a known label
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            label: None,
+        }
+    }
+}
+
+/// A site albums can be downloaded from.
+///
+/// A source resolves a url into the releases it refers to, then each release into an [`Album`]
+/// along with its tracks. The download engine only deals with the resulting albums.
+#[async_trait]
+pub trait Source: Debug + Send + Sync {
+    /// Name of the source, for display purposes
+    fn name(&self) -> &str;
+
+    /// True if this source can handle `url`
+    fn handles(&self, url: &str) -> bool;
+
+    /// Returns the releases (albums or tracks) found at `url`. If `discography` is true, returns
+    /// every release of the artist (or label) `url` belongs to instead.
+    async fn releases(&self, url: &str, discography: bool, logger: &Logger)
+        -> Result<Vec<Release>>;
+
+    /// Returns the album (and its tracks) of `release`
+    async fn album(
+        &self,
+        release: &Release,
+        save_dir: &str,
+        filename_format: &str,
+    ) -> Result<Album>;
+}
+
+/// Returns the sources available by default, in priority order
+pub fn default_sources() -> Vec<Box<dyn Source>> {
+    vec![Box::new(Collection::new()), Box::new(Bandcamp::new())]
+}
+
+/// Returns the first of the `sources` able to handle `url`
+pub fn find<'a>(sources: &'a [Box<dyn Source>], url: &str) -> Option<&'a dyn Source> {
+    sources
+        .iter()
+        .find(|source| source.handles(url))
+        .map(|source| source.as_ref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Error;
+
+    /// Source handling the urls starting with a prefix
+    #[derive(Debug)]
+    struct Prefixed(&'static str);
+
+    #[async_trait]
+    impl Source for Prefixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn handles(&self, url: &str) -> bool {
+            url.starts_with(self.0)
+        }
+
+        async fn releases(&self, url: &str, _: bool, _: &Logger) -> Result<Vec<Release>> {
+            Ok(vec![Release::new(url)])
+        }
+
+        async fn album(&self, _: &Release, _: &str, _: &str) -> Result<Album> {
+            Err(Error::NoAlbumData)
+        }
+    }
+
+    #[test]
+    fn finds_first_matching_source() {
+        let sources: Vec<Box<dyn Source>> = vec![
+            Box::new(Prefixed("http://a.")),
+            Box::new(Prefixed("http://")),
+        ];
+
+        let msg = "should pick the first source handling the url";
+        let actual = find(&sources, "http://a.example.com").map(|source| source.name());
+        assert_eq!(actual, Some("http://a."), "{}", msg);
+
+        let actual = find(&sources, "http://b.example.com").map(|source| source.name());
+        assert_eq!(actual, Some("http://"), "{}", msg);
+
+        let msg = "should return None when no source handles the url";
+        let actual = find(&sources, "ftp://example.com").map(|source| source.name());
+        assert_eq!(actual, None, "{}", msg);
+    }
+}
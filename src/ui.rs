@@ -1,13 +1,13 @@
 //! User interface module
-use std::hash::{Hash, Hasher};
-
 use serde::{Deserialize, Serialize};
 
-use crate::settings::UserSettings;
+use crate::{core::DownloadStats, model::Album, settings::UserSettings};
 
 pub use self::iced::IcedUi;
+pub use cli::CliUi;
 pub use intl::IntlString;
 
+mod cli;
 mod iced;
 mod intl;
 
@@ -20,6 +20,8 @@ pub trait Ui {
 /// Log message severity levels.
 #[derive(Debug, Clone)]
 pub enum LogLevel {
+    /// Detailed information, only displayed if verbose logging is enabled.
+    Verbose,
     /// A notice of normal event progress, for information only.
     Info,
     /// A potential concern that doesn't *require* intervention.
@@ -28,8 +30,21 @@ pub enum LogLevel {
     Error,
 }
 
+impl std::fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self {
+            Self::Verbose => "VERBOSE",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+        };
+        // use pad so width and alignment flags are honored
+        f.pad(level)
+    }
+}
+
 /// Download progress state
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Clone, Eq, Hash, PartialEq)]
 pub struct Progress {
     /// File download path
     pub path: String,
@@ -39,16 +54,29 @@ pub struct Progress {
     pub total: u64,
 }
 
-impl Hash for Progress {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.path.hash(state);
-        self.total.hash(state);
-    }
+/// Processing state of a track
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TrackStatus {
+    /// Waiting to be downloaded
+    Queued,
+    /// Download in progress
+    Downloading,
+    /// Writing the id3 tag
+    Tagging,
+    /// Converting the downloaded file with the encoder
+    Transcoding,
+    /// Downloaded (and tagged)
+    Done,
+    /// Could not be downloaded
+    Failed,
+    /// Already on disk, not downloaded
+    Skipped,
 }
 
-impl PartialEq for Progress {
-    fn eq(&self, other: &Self) -> bool {
-        (self.path == other.path) && (self.total == other.total)
+impl TrackStatus {
+    /// True if the track will not be processed any further
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Done | Self::Failed | Self::Skipped)
     }
 }
 
@@ -57,12 +85,33 @@ impl PartialEq for Progress {
 pub enum Message {
     /// Start file downloads
     StartDownloads,
+    /// List the files that would be downloaded, to pick the ones to download
+    PreviewDownloads,
+    /// Follow the artists of the supplied urls, to download their new releases
+    FollowUrls,
     /// Cancel all in-progress downloads
     CancelDownloads,
+    /// Download again the failed tracks of the album at the specified index (or only the
+    /// specified track)
+    RetryFailed(usize, Option<usize>),
+    /// Give up on the failed tracks of the album at the specified index (or only the specified
+    /// track)
+    SkipFailed(usize, Option<usize>),
+    /// Open the folder of the album at the specified index
+    OpenFolder(usize),
     /// Log some text at the specified log level
     Log(String, LogLevel),
+    /// Register the albums queued for download by a new run, replacing the ones of the previous
+    /// run
+    AlbumsQueued(Vec<Album>),
+    /// Notify the new releases of the followed artists, about to be downloaded
+    NewReleases(Vec<Album>),
+    /// Update the processing state of the track at the specified path
+    TrackStatus(String, TrackStatus),
     /// Update file download progress
     Progress(Progress),
+    /// Update the aggregate progress of the downloads
+    DownloadStats(DownloadStats),
 }
 
 /// UI theme (colorscheme)
@@ -0,0 +1,179 @@
+//! Command line interface, downloading the supplied urls without a window
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use futures::{channel::mpsc, future, FutureExt, StreamExt};
+
+use super::{LogLevel, Message, Ui};
+use crate::{
+    core::{DownloadService, DownloadStats, Logger},
+    helper,
+    settings::UserSettings,
+};
+
+/// Downloads a list of urls, printing the log to the terminal
+#[derive(Debug)]
+pub struct CliUi {
+    urls: Vec<String>,
+    dry_run: bool,
+    watch: bool,
+    /// Library to tag again instead of downloading, and the release of its unmatched folders
+    retag: Option<(PathBuf, Option<String>)>,
+    /// Downloads to move to the paths of the current formats
+    reorganise: Option<PathBuf>,
+}
+
+impl CliUi {
+    /// Create a new instance downloading `urls`. If `dry_run` is true, only prints the files
+    /// that would be downloaded.
+    pub fn new(urls: Vec<String>, dry_run: bool) -> Self {
+        Self {
+            urls,
+            dry_run,
+            watch: false,
+            retag: None,
+            reorganise: None,
+        }
+    }
+
+    /// Create a new instance downloading `urls`, then the new releases of the followed artists
+    /// until interrupted
+    pub fn watching(urls: Vec<String>) -> Self {
+        Self {
+            urls,
+            dry_run: false,
+            watch: true,
+            retag: None,
+            reorganise: None,
+        }
+    }
+
+    /// Create a new instance tagging again the mp3 files of the library at `dir`, its folders
+    /// not matched otherwise being matched to the release at `album_url`
+    pub fn retagging(dir: PathBuf, album_url: Option<String>) -> Self {
+        Self {
+            urls: Vec::new(),
+            dry_run: false,
+            watch: false,
+            retag: Some((dir, album_url)),
+            reorganise: None,
+        }
+    }
+
+    /// Create a new instance moving the albums saved in `dir` to the paths of the current
+    /// formats. If `dry_run` is true, only prints the moves.
+    pub fn reorganising(dir: PathBuf, dry_run: bool) -> Self {
+        Self {
+            urls: Vec::new(),
+            dry_run,
+            watch: false,
+            retag: None,
+            reorganise: Some(dir),
+        }
+    }
+}
+
+/// Print the log messages, keeping the latest download stats in `stats`
+fn print(message: Message, stats: &mut Option<DownloadStats>) {
+    match message {
+        Message::Log(text, level @ LogLevel::Warn)
+        | Message::Log(text, level @ LogLevel::Error) => {
+            eprintln!("{:<7} {}", level, text)
+        }
+        Message::Log(text, level) => println!("{:<7} {}", level, text),
+        Message::NewReleases(albums) => {
+            for album in albums {
+                println!("New release: {} - {}", album.artist, album.title);
+            }
+        }
+        Message::DownloadStats(latest) => *stats = Some(latest),
+        _ => {}
+    }
+}
+
+impl Ui for CliUi {
+    fn run(&self, user_settings: UserSettings) {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                eprintln!("Unable to start the async runtime: {}", e);
+                return;
+            }
+        };
+
+        let (sender, mut receiver) = mpsc::channel(100);
+        let logger = Logger::new(sender, user_settings.show_verbose_log)
+            .with_file(helper::get_root_dir().join(Logger::FILE_NAME));
+        let service = Arc::new(DownloadService::new());
+        let urls = self.urls.join("\n");
+        let mut stats = None;
+        let mut plan = Vec::new();
+
+        let dry_run = self.dry_run;
+        let watch = self.watch;
+        let retag = self.retag.clone();
+        let reorganise = self.reorganise.clone();
+        let mut moves = Vec::new();
+        runtime.block_on(async {
+            let downloads = async {
+                if let Some(dir) = reorganise {
+                    moves = service
+                        .reorganise(dir, dry_run, logger, user_settings)
+                        .await;
+                    return;
+                }
+                if let Some((dir, album_url)) = retag {
+                    service
+                        .retag_library(dir, album_url, logger, user_settings)
+                        .await;
+                    return;
+                }
+                if dry_run {
+                    plan = service.preview(urls, logger, user_settings).await;
+                    return;
+                }
+                if !urls.is_empty() {
+                    let service = service.clone();
+                    service
+                        .start_downloads(urls, logger.clone(), user_settings.clone())
+                        .await;
+                }
+                if watch {
+                    let settings = Arc::new(Mutex::new(user_settings));
+                    service.watch(logger, settings).await;
+                }
+            }
+            .boxed();
+            let printer = receiver.by_ref().for_each(|message| {
+                print(message, &mut stats);
+                future::ready(())
+            });
+            future::select(downloads, printer).await;
+
+            // the service keeps a logger: close the channel to only read what is left
+            receiver.close();
+            while let Some(message) = receiver.next().await {
+                print(message, &mut stats);
+            }
+        });
+
+        for album in plan {
+            println!("{}", album);
+        }
+        for album in moves {
+            println!("{}", album);
+        }
+        if let Some(stats) = stats {
+            let total = stats.total;
+            println!(
+                "{} of {} track(s) downloaded, {} failed",
+                total.tracks_done, total.tracks_total, total.tracks_failed
+            );
+        }
+    }
+}
@@ -8,7 +8,12 @@ use super::{
     components::{main_view, settings_view, Entry, EntryMessage},
     subscription, Message, SettingType,
 };
-use crate::{core::DownloadService, helper::log_info, settings::UserSettings, ui};
+use crate::{
+    core::{DownloadService, Logger},
+    helper,
+    settings::UserSettings,
+    ui,
+};
 
 /// Application flags
 #[derive(Debug)]
@@ -53,7 +58,7 @@ pub struct App {
     ui_state: UiState,
     cur_view: View,
 
-    sender: mpsc::Sender<ui::Message>,
+    logger: Logger,
     receiver: SharedReceiver<ui::Message>,
 }
 
@@ -64,6 +69,8 @@ impl App {
         let (sender, receiver) = mpsc::channel(50);
 
         let intl = Arc::new(ui::IntlString::default());
+        let logger = Logger::new(sender, user_settings.show_verbose_log)
+            .with_file(helper::get_root_dir().join(Logger::FILE_NAME));
         let user_settings = Arc::new(std::sync::Mutex::new(user_settings));
         let download_service = DownloadService::new();
         let ui_state = UiState {
@@ -79,7 +86,7 @@ impl App {
             ui_state,
             cur_view: View::Main,
 
-            sender,
+            logger,
             receiver: Arc::new(Mutex::new(receiver)),
         }
     }
@@ -122,19 +129,37 @@ impl Application for App {
     type Flags = AppFlags;
 
     fn new(flags: AppFlags) -> (Self, Command<Message>) {
-        (Self::new(flags), Command::none())
+        let app = Self::new(flags);
+        // check the subscriptions in the background for as long as the app runs
+        let watch = Arc::clone(&app.download_service)
+            .watch(app.logger.clone(), Arc::clone(&app.user_settings));
+
+        (app, Command::perform(watch, Message::WatchStopped))
     }
 
     fn title(&self) -> String {
-        self.cur_view.title(&self.intl)
+        let title = self.cur_view.title(&self.intl);
+        let stats = &self.ui_state.main.download_stats;
+        let total = &stats.total;
+
+        // Display the download progress while downloads are running
+        if total.tracks_done + total.tracks_failed < total.tracks_total {
+            let eta = stats
+                .eta
+                .map(|eta| format!(" (ETA {})", helper::format_duration(eta)))
+                .unwrap_or_default();
+            return format!("{:.0}%{} - {}", total.percent(), eta, title);
+        }
+
+        title
     }
 
     fn update(&mut self, message: Message, _clipboard: &mut iced::Clipboard) -> Command<Message> {
         /// update the specified field of user_settings with the provided value
         macro_rules! update_setting {
-            ($field: ident, $value: expr) => {{
+            ($($field: ident).+, $value: expr) => {{
                 let mut user_settings = self.user_settings.lock().unwrap();
-                user_settings.$field = $value.into();
+                user_settings.$($field).+ = $value.into();
             }};
         }
 
@@ -162,6 +187,9 @@ impl Application for App {
                     self.ui_state.main.url_state.url_list.remove(i);
                 }
             },
+            Message::ToggleAlbum(i) => {
+                self.ui_state.main.progress_list.toggle(i);
+            }
             Message::OpenSettings => {
                 self.cur_view = View::Settings;
             }
@@ -169,33 +197,124 @@ impl Application for App {
                 self.cur_view = View::Main;
             }
             Message::Domain(ui::Message::StartDownloads) => {
+                let settings = self.user_settings.lock().unwrap().clone();
+
+                // download the selection of the pending preview, if any
+                if let Some(preview) = self.ui_state.main.preview.take() {
+                    self.logger.info("Start download of the previewed albums");
+                    return Command::perform(
+                        Arc::clone(&self.download_service).download(
+                            preview.into_selection(),
+                            self.logger.clone(),
+                            settings,
+                        ),
+                        Message::DownloadsComplete,
+                    );
+                }
+
                 let urls = self.urls();
-                log_info(self.sender.clone(), format!("Start download\n{}", urls));
-                self.ui_state.main.downloading_files.clear();
+                self.logger.info(format!("Start download\n{}", urls));
 
-                let settings = self.user_settings.lock().unwrap().clone();
                 return Command::perform(
                     Arc::clone(&self.download_service).start_downloads(
                         urls,
-                        self.sender.clone(),
+                        self.logger.clone(),
                         settings,
                     ),
                     Message::DownloadsComplete,
                 );
             }
+            Message::Domain(ui::Message::PreviewDownloads) => {
+                let urls = self.urls();
+                self.logger.info(format!("Preview download\n{}", urls));
+
+                let settings = self.user_settings.lock().unwrap().clone();
+                return Command::perform(
+                    Arc::clone(&self.download_service).preview(urls, self.logger.clone(), settings),
+                    Message::PreviewReady,
+                );
+            }
+            Message::Domain(ui::Message::FollowUrls) => {
+                let urls = self.urls();
+                let settings = self.user_settings.clone();
+                {
+                    let mut user_settings = settings.lock().unwrap();
+                    for url in urls.lines() {
+                        if user_settings.subscribe(url) {
+                            self.logger.info(format!("Following {}", url));
+                        }
+                    }
+                }
+
+                return Command::perform(async move { settings.lock().unwrap().save() }, |_| {
+                    Message::OpenMain
+                });
+            }
+            Message::PreviewReady(plan) => {
+                self.ui_state.main.set_preview(plan);
+            }
+            Message::ExpandPreviewAlbum(i) => {
+                if let Some(preview) = &mut self.ui_state.main.preview {
+                    preview.expand(i);
+                }
+            }
+            Message::TogglePreviewAlbum(i) => {
+                if let Some(preview) = &mut self.ui_state.main.preview {
+                    preview.toggle_album(i);
+                }
+            }
+            Message::TogglePreviewTrack(i, track) => {
+                if let Some(preview) = &mut self.ui_state.main.preview {
+                    preview.toggle_track(i, track);
+                }
+            }
+            Message::DiscardPreview => {
+                self.ui_state.main.preview = None;
+            }
             Message::Domain(ui::Message::CancelDownloads) => {
-                log_info(self.sender.clone(), "cancel download clicked");
+                self.logger.info("cancel download clicked");
+            }
+            Message::Domain(ui::Message::RetryFailed(album, track)) => {
+                return Command::perform(
+                    Arc::clone(&self.download_service).retry(album, track),
+                    Message::RetryComplete,
+                );
+            }
+            Message::Domain(ui::Message::SkipFailed(album, track)) => {
+                self.download_service.skip(album, track);
+            }
+            Message::Domain(ui::Message::OpenFolder(album)) => {
+                self.download_service.open_folder(album);
             }
             Message::Domain(ui::Message::Log(value, level)) => {
-                println!("{}", value);
                 self.ui_state.main.add_log(&value, level);
             }
+            Message::Domain(ui::Message::AlbumsQueued(albums)) => {
+                // the albums of a new run replace the ones of the previous run, which is over
+                self.ui_state.main.clear_progress();
+                self.ui_state.main.add_albums(albums);
+            }
+            Message::Domain(ui::Message::NewReleases(albums)) => {
+                // the new releases are downloaded in a run of their own
+                for album in albums {
+                    let text = format!("New release: {} - {}", album.artist, album.title);
+                    self.ui_state.main.add_log(text, ui::LogLevel::Info);
+                }
+            }
+            Message::Domain(ui::Message::TrackStatus(path, status)) => {
+                self.ui_state.main.set_status(&path, status);
+            }
             Message::Domain(ui::Message::Progress(dl_progress)) => {
-                self.ui_state.main.downloading_files.replace(dl_progress);
+                self.ui_state.main.set_progress(dl_progress);
+            }
+            Message::Domain(ui::Message::DownloadStats(stats)) => {
+                self.ui_state.main.set_stats(stats);
             }
             Message::DownloadsComplete(_) => {
-                log_info(self.sender.clone(), "All downloads complete");
+                self.logger.info("All downloads complete");
             }
+            Message::RetryComplete(_) => {}
+            Message::WatchStopped(_) => {}
             Message::SettingsSaved => {
                 let settings = self.user_settings.clone();
                 return Command::perform(async move { settings.lock().unwrap().save() }, |_| {
@@ -218,6 +337,9 @@ impl Application for App {
                 SettingType::Discography(value) => {
                     update_setting!(download_artist_discography, value)
                 }
+                SettingType::LabelArtistFilter(value) => {
+                    update_setting!(label_artist_filter, value)
+                }
                 SettingType::ArtFilename(value) => {
                     update_setting!(cover_art_file_name_format, value)
                 }
@@ -242,6 +364,61 @@ impl Application for App {
                 SettingType::DownloadSerial(value) => {
                     update_setting!(download_one_album_at_a_time, value)
                 }
+                SettingType::DownloadFreeReleases(value) => {
+                    update_setting!(download_free_releases, value)
+                }
+                SettingType::WriteAlbumJson(value) => update_setting!(write_album_json, value),
+                SettingType::WriteAlbumNfo(value) => update_setting!(write_album_nfo, value),
+                SettingType::Duplicates(value) => update_setting!(duplicates, value),
+                SettingType::FilterAfter(value) => {
+                    update_setting!(release_filter.released_after, value)
+                }
+                SettingType::FilterBefore(value) => {
+                    update_setting!(release_filter.released_before, value)
+                }
+                SettingType::FilterTitle(value) => {
+                    update_setting!(release_filter.title_pattern, value)
+                }
+                SettingType::FilterMinTracks(value) => {
+                    update_setting!(release_filter.min_tracks, value)
+                }
+                SettingType::FilterMaxTracks(value) => {
+                    update_setting!(release_filter.max_tracks, value)
+                }
+                SettingType::FilterReleaseType(value) => {
+                    update_setting!(release_filter.release_type, value)
+                }
+                SettingType::SubscriptionInterval(value) => {
+                    update_setting!(subscription_check_interval, value)
+                }
+                SettingType::Unsubscribe(url) => {
+                    self.user_settings.lock().unwrap().unsubscribe(&url);
+                }
+                SettingType::IdentityCookie(value) => update_setting!(identity_cookie, value),
+                SettingType::CookiesFile(value) => update_setting!(cookies_file, value),
+                SettingType::FanPageUrl(value) => update_setting!(fan_page_url, value),
+                SettingType::PreferredFormat(value) => update_setting!(preferred_format, value),
+                SettingType::TranscodeEnabled(value) => update_setting!(transcoding.enabled, value),
+                SettingType::TranscodeEncoder(value) => update_setting!(transcoding.encoder, value),
+                SettingType::TranscodeArguments(value) => {
+                    update_setting!(transcoding.arguments, value)
+                }
+                SettingType::TranscodeExtension(value) => {
+                    update_setting!(transcoding.extension, value)
+                }
+                SettingType::TranscodeBitrate(value) => update_setting!(transcoding.bitrate, value),
+                SettingType::TranscodeKeepOriginals(value) => {
+                    update_setting!(transcoding.keep_originals, value)
+                }
+                SettingType::HookAlbumComplete(value) => {
+                    update_setting!(hooks.album_complete, value)
+                }
+                SettingType::HookRunComplete(value) => update_setting!(hooks.run_complete, value),
+                SettingType::HookTrackFailed(value) => update_setting!(hooks.track_failed, value),
+                SettingType::VerboseLog(value) => {
+                    self.logger.set_verbose(value);
+                    update_setting!(show_verbose_log, value)
+                }
             },
         }
         Command::none()
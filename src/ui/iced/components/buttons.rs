@@ -27,6 +27,15 @@ pub fn download<'a>(state: &'a mut button::State, intl: &IntlString) -> Button<'
         .on_press(Message::Domain(ui::Message::StartDownloads))
 }
 
+pub fn preview<'a>(state: &'a mut button::State, intl: &IntlString) -> Button<'a, Message> {
+    button(state, &intl.preview_button, None)
+        .on_press(Message::Domain(ui::Message::PreviewDownloads))
+}
+
+pub fn follow<'a>(state: &'a mut button::State, intl: &IntlString) -> Button<'a, Message> {
+    button(state, &intl.follow_button, None).on_press(Message::Domain(ui::Message::FollowUrls))
+}
+
 pub fn settings<'a>(state: &'a mut button::State, intl: &IntlString) -> Button<'a, Message> {
     button(state, &intl.settings_button_text, None).on_press(Message::OpenSettings)
 }
@@ -1,12 +1,12 @@
-use std::collections::HashSet;
+use iced::{button, scrollable, text_input, Column, Container, Element, Length, Row};
 
-use iced::{button, scrollable, text_input, Column, Container, Element, Length};
-
-use super::UrlState;
+use super::{PreviewList, ProgressList, UrlState};
+use crate::core::{DownloadStats, PlannedAlbum};
+use crate::model::Album;
 use crate::settings::UserSettings;
 use crate::ui::{
     iced::{components, Message},
-    IntlString, LogLevel, Progress,
+    IntlString, LogLevel, Progress, TrackStatus,
 };
 
 /// main view UI state
@@ -16,12 +16,19 @@ pub struct State {
 
     // TODO: move to app state
     pub download_progress: f32,
-    pub downloading_files: HashSet<Progress>,
+    /// Download progress of each album and track
+    pub progress_list: ProgressList,
+    /// Albums found by the latest preview, waiting to be downloaded
+    pub preview: Option<PreviewList>,
+    /// Aggregate progress of the current downloads
+    pub download_stats: DownloadStats,
     pub log: Vec<String>,
 
     pub save_input: text_input::State,
     pub scroll_state: scrollable::State,
     pub download: button::State,
+    pub preview_button: button::State,
+    pub follow_button: button::State,
     pub cancel: button::State,
     pub settings: button::State,
 }
@@ -32,12 +39,16 @@ impl State {
             url_state: UrlState::default(),
 
             download_progress: 0.0,
-            downloading_files: HashSet::new(),
+            progress_list: ProgressList::default(),
+            preview: None,
+            download_stats: DownloadStats::default(),
             log: Vec::new(),
 
             scroll_state: scrollable::State::new(),
             save_input: text_input::State::new(),
             download: button::State::new(),
+            preview_button: button::State::new(),
+            follow_button: button::State::new(),
             cancel: button::State::new(),
             settings: button::State::new(),
         }
@@ -46,6 +57,38 @@ impl State {
     pub fn add_log<T: ToString>(&mut self, value: T, _type: LogLevel) {
         self.log.push(value.to_string());
     }
+
+    /// Add albums to the download progress list
+    pub fn add_albums(&mut self, albums: Vec<Album>) {
+        self.progress_list.add_albums(albums);
+    }
+
+    /// Update the download progress of a file
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress_list.set_progress(progress);
+    }
+
+    /// Update the processing state of a file
+    pub fn set_status(&mut self, path: &str, status: TrackStatus) {
+        self.progress_list.set_status(path, status);
+    }
+
+    /// Update the aggregate progress of the downloads
+    pub fn set_stats(&mut self, stats: DownloadStats) {
+        self.download_progress = stats.total.percent();
+        self.download_stats = stats;
+    }
+
+    /// Show the albums found by a preview
+    pub fn set_preview(&mut self, plan: Vec<PlannedAlbum>) {
+        self.preview = Some(PreviewList::new(plan));
+    }
+
+    /// Reset the download progress before a new run
+    pub fn clear_progress(&mut self) {
+        self.progress_list.clear();
+        self.set_stats(DownloadStats::default());
+    }
 }
 
 pub fn view<'a>(
@@ -60,11 +103,18 @@ pub fn view<'a>(
         intl,
     );
     let event_log = components::event_log(&mut state.scroll_state, &state.log, intl);
+    let progress_list = match &mut state.preview {
+        Some(preview) => components::preview_list(preview, intl),
+        None => components::progress_list(&mut state.progress_list, intl),
+    };
     let discog_checkbox =
         components::discography_checkbox(settings.download_artist_discography, intl);
-    let progress_bar = components::download_progress_bar(&state.downloading_files);
+    let progress_bar =
+        components::download_progress(state.download_progress, &state.download_stats);
     let controls = components::controls(
         &mut state.download,
+        &mut state.preview_button,
+        &mut state.follow_button,
         &mut state.cancel,
         &mut state.settings,
         intl,
@@ -76,7 +126,12 @@ pub fn view<'a>(
         .push(save_dir)
         .push(discog_checkbox)
         .push(progress_bar)
-        .push(event_log)
+        .push(
+            Row::new()
+                .spacing(5)
+                .push(Container::new(progress_list).width(Length::FillPortion(1)))
+                .push(Container::new(event_log).width(Length::FillPortion(1))),
+        )
         .push(controls);
 
     Container::new(content)
@@ -1,16 +1,18 @@
-use std::collections::HashSet;
-
-use iced::{Checkbox, Length, ProgressBar, Row, Text};
+use iced::{Align, Checkbox, Length, ProgressBar, Row, Text};
 
 use crate::{
+    core::DownloadStats,
+    helper,
     ui::iced::{style, Message, SettingType},
-    ui::{IntlString, Progress},
+    ui::IntlString,
 };
 
 use controls::controls;
 use event_log::event_log;
 use file_format_input::filename_format;
 use indent_row::indent;
+pub use preview_list::{preview_list, PreviewList};
+pub use progress_list::{progress_list, ProgressList};
 use styled_pick_list::styled_pick_list;
 use styled_text as StyledText;
 use styled_text_input::labeled_input;
@@ -25,6 +27,8 @@ mod entry;
 mod event_log;
 mod file_format_input;
 pub mod main_view;
+mod preview_list;
+mod progress_list;
 pub mod settings_view;
 mod styled_pick_list;
 mod styled_text_input;
@@ -55,19 +59,35 @@ pub fn discography_checkbox<'a>(state: bool, intl: &IntlString) -> Row<'a, Messa
     })
 }
 
-/// Creates a ProgressBar to display the completion percentage calculated from a set of Progress
-/// events
-pub fn download_progress_bar(files: &HashSet<Progress>) -> ProgressBar {
-    let (downloaded, total_size) = files.iter().fold((0.0, 0.0), |(done, total), file| {
-        (done + file.complete as f32, total + file.total as f32)
-    });
-    let download_progress = (downloaded / total_size) * 100.0;
-
-    ProgressBar::new(0.0..=100.0, download_progress)
+/// Creates a ProgressBar to display the download completion percentage
+pub fn download_progress_bar(percent: f32) -> ProgressBar {
+    ProgressBar::new(0.0..=100.0, percent)
         .height(Length::Units(18))
         .style(style::Theme::Light)
 }
 
+/// Download progress bar along with the completion status, speed and estimated time remaining
+pub fn download_progress<'a>(percent: f32, stats: &DownloadStats) -> Row<'a, Message> {
+    let eta = stats
+        .eta
+        .map(helper::format_duration)
+        .unwrap_or_else(|| String::from("--:--"));
+    let status = styled_text(format!(
+        "{}/{} - {}/s - ETA {}",
+        stats.total.tracks_done,
+        stats.total.tracks_total,
+        helper::format_bytes(stats.throughput as u64),
+        eta
+    ))
+    .width(Length::Units(200));
+
+    Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(download_progress_bar(percent))
+        .push(status)
+}
+
 mod indent_row {
     use iced::{Align, Length, Row, Space};
 
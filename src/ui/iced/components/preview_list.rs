@@ -0,0 +1,194 @@
+use iced::{
+    button, scrollable, Align, Button, Checkbox, Column, Container, Element, Length, Row,
+    Scrollable,
+};
+
+use super::{indent, StyledText, TEXT_SIZE};
+use crate::{
+    core::PlannedAlbum,
+    model::Album,
+    ui::{
+        iced::{style, Message},
+        IntlString,
+    },
+};
+
+/// Planned album and the tracks selected for download
+#[derive(Debug)]
+struct AlbumEntry {
+    plan: PlannedAlbum,
+    selected: Vec<bool>,
+    expanded: bool,
+    toggle: button::State,
+}
+
+impl AlbumEntry {
+    /// True if any track is selected
+    fn is_selected(&self) -> bool {
+        self.selected.iter().any(|selected| *selected)
+    }
+}
+
+/// Albums resolved by a preview, to pick the albums and tracks to download
+#[derive(Debug, Default)]
+pub struct PreviewList {
+    albums: Vec<AlbumEntry>,
+    scroll_state: scrollable::State,
+    discard: button::State,
+}
+
+impl PreviewList {
+    /// Create a list of the planned albums, keeping the tracks already selected
+    pub fn new(plan: Vec<PlannedAlbum>) -> Self {
+        let albums = plan
+            .into_iter()
+            .map(|plan| AlbumEntry {
+                selected: plan
+                    .album
+                    .tracks
+                    .iter()
+                    .map(|track| track.selected)
+                    .collect(),
+                plan,
+                expanded: false,
+                toggle: button::State::new(),
+            })
+            .collect();
+
+        Self {
+            albums,
+            ..Self::default()
+        }
+    }
+
+    /// Select (or deselect) every track of an album
+    pub fn toggle_album(&mut self, album: usize) {
+        if let Some(album) = self.albums.get_mut(album) {
+            let selected = !album.is_selected();
+            album
+                .selected
+                .iter_mut()
+                .for_each(|track| *track = selected);
+        }
+    }
+
+    /// Select (or deselect) a track
+    pub fn toggle_track(&mut self, album: usize, track: usize) {
+        if let Some(track) = self
+            .albums
+            .get_mut(album)
+            .and_then(|album| album.selected.get_mut(track))
+        {
+            *track = !*track;
+        }
+    }
+
+    /// Expand or collapse the tracks of an album
+    pub fn expand(&mut self, album: usize) {
+        if let Some(album) = self.albums.get_mut(album) {
+            album.expanded = !album.expanded;
+        }
+    }
+
+    /// Returns the selected albums, flagging their selected tracks
+    pub fn into_selection(self) -> Vec<Album> {
+        self.albums
+            .into_iter()
+            .filter(AlbumEntry::is_selected)
+            .map(|entry| {
+                let mut album = entry.plan.album;
+                for (track, selected) in album.tracks.iter_mut().zip(entry.selected) {
+                    track.selected = selected;
+                }
+                album
+            })
+            .collect()
+    }
+}
+
+fn checkbox(
+    checked: bool,
+    label: &str,
+    message: impl 'static + Fn(bool) -> Message,
+) -> Checkbox<Message> {
+    Checkbox::new(checked, label, message)
+        .size(16)
+        .text_size(TEXT_SIZE)
+        .width(Length::Fill)
+}
+
+fn album_rows<'a>(
+    index: usize,
+    entry: &'a mut AlbumEntry,
+    intl: &IntlString,
+) -> Column<'a, Message> {
+    let is_selected = entry.is_selected();
+    let existing = entry.plan.existing.iter().filter(|exists| **exists).count();
+    let album = &entry.plan.album;
+    let toggle = Button::new(
+        &mut entry.toggle,
+        StyledText(if entry.expanded { "-" } else { "+" }),
+    )
+    .width(Length::Units(20))
+    .style(style::Theme::Light)
+    .on_press(Message::ExpandPreviewAlbum(index));
+    let label = format!("{} - {}", album.artist, album.title);
+    let header = Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(toggle)
+        .push(checkbox(is_selected, &label, move |_| {
+            Message::TogglePreviewAlbum(index)
+        }))
+        .push(StyledText(format!("{}/{}", existing, album.tracks.len())).width(Length::Units(40)));
+
+    let mut rows = Column::new().spacing(2).push(header);
+    if entry.expanded {
+        rows = rows.push(indent(25).push(StyledText(&album.path)));
+        let tracks = album.tracks.iter().zip(&entry.plan.existing);
+        for (i, ((track, exists), selected)) in tracks.zip(&entry.selected).enumerate() {
+            let label = format!("{:02} {}", track.number, track.title);
+            let mut row = indent(25)
+                .spacing(5)
+                .push(checkbox(*selected, &label, move |_| {
+                    Message::TogglePreviewTrack(index, i)
+                }));
+            if *exists {
+                row = row.push(StyledText(&intl.preview_on_disk).width(Length::Units(80)));
+            }
+            rows = rows.push(row);
+        }
+    }
+
+    rows
+}
+
+/// Selectable tree of the albums and tracks found by a preview
+pub fn preview_list<'a>(state: &'a mut PreviewList, intl: &IntlString) -> Element<'a, Message> {
+    const LIST_HEIGHT: u16 = 200;
+    let header = Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(StyledText(&intl.preview_title).width(Length::Fill))
+        .push(
+            Button::new(&mut state.discard, StyledText(&intl.discard_preview_button))
+                .style(style::Theme::Light)
+                .on_press(Message::DiscardPreview),
+        );
+
+    let mut content = Column::new().spacing(2);
+    if state.albums.is_empty() {
+        content = content.push(StyledText(&intl.progress_placeholder));
+    } else {
+        for (i, album) in state.albums.iter_mut().enumerate() {
+            content = content.push(album_rows(i, album, intl));
+        }
+    }
+
+    let scroll =
+        Scrollable::new(&mut state.scroll_state).push(Container::new(content).width(Length::Fill));
+
+    Container::new(Column::new().spacing(5).push(header).push(scroll))
+        .height(Length::Units(LIST_HEIGHT))
+        .into()
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use iced::{
+    button, scrollable, Align, Button, Column, Container, Element, Length, ProgressBar, Row,
+    Scrollable,
+};
+
+use super::{indent, StyledText};
+use crate::{
+    model::Album,
+    ui::{
+        self,
+        iced::{style, Message},
+        IntlString, Progress, TrackStatus,
+    },
+};
+
+/// Download state of a track
+#[derive(Debug)]
+struct TrackEntry {
+    /// Position of the track in its album
+    index: usize,
+    number: u32,
+    title: String,
+    status: TrackStatus,
+    complete: u64,
+    total: u64,
+    retry: button::State,
+    skip: button::State,
+}
+
+impl TrackEntry {
+    fn percent(&self) -> f32 {
+        match self.status {
+            TrackStatus::Done | TrackStatus::Skipped => 100.0,
+            _ if self.total == 0 => 0.0,
+            _ => self.complete as f32 / self.total as f32 * 100.0,
+        }
+    }
+}
+
+/// Download state of an album and its tracks
+#[derive(Debug)]
+struct AlbumEntry {
+    title: String,
+    expanded: bool,
+    toggle: button::State,
+    retry: button::State,
+    skip: button::State,
+    open: button::State,
+    tracks: Vec<TrackEntry>,
+}
+
+impl AlbumEntry {
+    /// Returns the number of finished tracks
+    fn finished(&self) -> usize {
+        self.tracks
+            .iter()
+            .filter(|track| track.status.is_finished())
+            .count()
+    }
+
+    /// True if any track could not be downloaded
+    fn has_failed(&self) -> bool {
+        self.tracks
+            .iter()
+            .any(|track| track.status == TrackStatus::Failed)
+    }
+}
+
+/// Per-album and per-track download progress
+#[derive(Debug, Default)]
+pub struct ProgressList {
+    albums: Vec<AlbumEntry>,
+    /// Position of each track in `albums`, by file path
+    index: HashMap<String, (usize, usize)>,
+    scroll_state: scrollable::State,
+}
+
+impl ProgressList {
+    /// Append queued albums to the list, along with their selected tracks
+    pub fn add_albums(&mut self, albums: Vec<Album>) {
+        for album in albums {
+            let album_index = self.albums.len();
+            let tracks = album
+                .tracks
+                .into_iter()
+                .enumerate()
+                .filter(|(_, track)| track.selected)
+                .enumerate()
+                .map(|(entry_index, (index, track))| {
+                    self.index
+                        .insert(track.path.clone(), (album_index, entry_index));
+                    TrackEntry {
+                        index,
+                        number: track.number,
+                        title: track.title,
+                        status: TrackStatus::Queued,
+                        complete: 0,
+                        total: track.size.unwrap_or(0),
+                        retry: button::State::new(),
+                        skip: button::State::new(),
+                    }
+                })
+                .collect();
+
+            self.albums.push(AlbumEntry {
+                title: album.title,
+                expanded: false,
+                toggle: button::State::new(),
+                retry: button::State::new(),
+                skip: button::State::new(),
+                open: button::State::new(),
+                tracks,
+            });
+        }
+    }
+
+    fn track_mut(&mut self, path: &str) -> Option<&mut TrackEntry> {
+        let (album, track) = *self.index.get(path)?;
+        self.albums.get_mut(album)?.tracks.get_mut(track)
+    }
+
+    /// Update the downloaded bytes of a track
+    pub fn set_progress(&mut self, progress: Progress) {
+        if let Some(track) = self.track_mut(&progress.path) {
+            track.complete = progress.complete;
+            track.total = progress.total;
+        }
+    }
+
+    /// Update the processing state of a track
+    pub fn set_status(&mut self, path: &str, status: TrackStatus) {
+        if let Some(track) = self.track_mut(path) {
+            // retried tracks start over
+            if status == TrackStatus::Queued {
+                track.complete = 0;
+            }
+            track.status = status;
+        }
+    }
+
+    /// Expand or collapse the tracks of an album
+    pub fn toggle(&mut self, album: usize) {
+        if let Some(album) = self.albums.get_mut(album) {
+            album.expanded = !album.expanded;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.albums.clear();
+        self.index.clear();
+    }
+}
+
+fn status_label(status: TrackStatus, intl: &IntlString) -> &str {
+    match status {
+        TrackStatus::Queued => &intl.status_queued,
+        TrackStatus::Downloading => &intl.status_downloading,
+        TrackStatus::Tagging => &intl.status_tagging,
+        TrackStatus::Transcoding => &intl.status_transcoding,
+        TrackStatus::Done => &intl.status_done,
+        TrackStatus::Failed => &intl.status_failed,
+        TrackStatus::Skipped => &intl.status_skipped,
+    }
+}
+
+fn progress_bar(percent: f32) -> ProgressBar {
+    ProgressBar::new(0.0..=100.0, percent)
+        .height(Length::Units(8))
+        .width(Length::Units(60))
+        .style(style::Theme::Light)
+}
+
+/// Small button acting on an item of the list
+fn item_button<'a>(
+    state: &'a mut button::State,
+    label: &str,
+    message: ui::Message,
+) -> Button<'a, Message> {
+    Button::new(state, StyledText(label))
+        .style(style::Theme::Light)
+        .on_press(Message::Domain(message))
+}
+
+fn track_row<'a>(album: usize, track: &'a mut TrackEntry, intl: &IntlString) -> Row<'a, Message> {
+    let index = track.index;
+    let row = indent(20)
+        .spacing(5)
+        .push(StyledText(format!("{:02} {}", track.number, track.title)).width(Length::Fill))
+        .push(StyledText(status_label(track.status, intl)).width(Length::Units(80)));
+
+    if track.status == TrackStatus::Failed {
+        row.push(item_button(
+            &mut track.retry,
+            &intl.retry_button,
+            ui::Message::RetryFailed(album, Some(index)),
+        ))
+        .push(item_button(
+            &mut track.skip,
+            &intl.skip_button,
+            ui::Message::SkipFailed(album, Some(index)),
+        ))
+    } else {
+        row.push(progress_bar(track.percent()))
+    }
+}
+
+fn album_rows<'a>(
+    index: usize,
+    album: &'a mut AlbumEntry,
+    intl: &IntlString,
+) -> Column<'a, Message> {
+    let finished = album.finished();
+    let has_failed = album.has_failed();
+    let total = album.tracks.len();
+    let percent = if total == 0 {
+        0.0
+    } else {
+        album.tracks.iter().map(TrackEntry::percent).sum::<f32>() / total as f32
+    };
+
+    let toggle = Button::new(
+        &mut album.toggle,
+        StyledText(if album.expanded { "-" } else { "+" }),
+    )
+    .width(Length::Units(20))
+    .style(style::Theme::Light)
+    .on_press(Message::ToggleAlbum(index));
+    let mut header = Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(toggle)
+        .push(StyledText(&album.title).width(Length::Fill))
+        .push(StyledText(format!("{}/{}", finished, total)).width(Length::Units(40)));
+    if has_failed {
+        header = header
+            .push(item_button(
+                &mut album.retry,
+                &intl.retry_button,
+                ui::Message::RetryFailed(index, None),
+            ))
+            .push(item_button(
+                &mut album.skip,
+                &intl.skip_button,
+                ui::Message::SkipFailed(index, None),
+            ));
+    }
+    header = header
+        .push(item_button(
+            &mut album.open,
+            &intl.open_folder_button,
+            ui::Message::OpenFolder(index),
+        ))
+        .push(progress_bar(percent));
+
+    let mut rows = Column::new().spacing(2).push(header);
+    if album.expanded {
+        for track in album.tracks.iter_mut() {
+            rows = rows.push(track_row(index, track, intl));
+        }
+    }
+
+    rows
+}
+
+/// Expandable tree of the queued albums and their tracks
+pub fn progress_list<'a>(state: &'a mut ProgressList, intl: &IntlString) -> Element<'a, Message> {
+    const LIST_HEIGHT: u16 = 200;
+    let mut content = Column::new().spacing(2);
+    if state.albums.is_empty() {
+        content = content.push(StyledText(&intl.progress_placeholder));
+    } else {
+        for (i, album) in state.albums.iter_mut().enumerate() {
+            content = content.push(album_rows(i, album, intl));
+        }
+    }
+
+    let scroll =
+        Scrollable::new(&mut state.scroll_state).push(Container::new(content).width(Length::Fill));
+
+    Container::new(scroll)
+        .height(Length::Units(LIST_HEIGHT))
+        .into()
+}
@@ -6,11 +6,16 @@ use crate::ui::{
     IntlString,
 };
 
+mod account;
 mod cover_art;
 mod downloads;
+mod filters;
 mod general;
+mod hooks;
 mod naming;
 mod playlist;
+mod subscriptions;
+mod transcoding;
 
 #[derive(Debug, Clone)]
 pub enum SettingsMessage {
@@ -19,6 +24,11 @@ pub enum SettingsMessage {
     Art,
     Playlist,
     Downloads,
+    Filters,
+    Subscriptions,
+    Account,
+    Transcoding,
+    Hooks,
 }
 
 /// Renderable views for Settings sections
@@ -29,6 +39,11 @@ pub enum View {
     Art(cover_art::State),
     Playlist(playlist::State),
     Downloads(downloads::State),
+    Filters(filters::State),
+    Subscriptions(subscriptions::State),
+    Account(account::State),
+    Transcoding(transcoding::State),
+    Hooks(hooks::State),
 }
 
 impl Default for View {
@@ -49,6 +64,11 @@ impl View {
             Self::Art(state) => state.view(settings, intl),
             Self::Playlist(state) => state.view(settings, intl),
             Self::Downloads(state) => state.view(settings, intl),
+            Self::Filters(state) => state.view(settings, intl),
+            Self::Subscriptions(state) => state.view(settings, intl),
+            Self::Account(state) => state.view(settings, intl),
+            Self::Transcoding(state) => state.view(settings, intl),
+            Self::Hooks(state) => state.view(settings, intl),
         }
     }
 }
@@ -60,6 +80,11 @@ struct Sections {
     art: button::State,
     playlist: button::State,
     downloads: button::State,
+    filters: button::State,
+    subscriptions: button::State,
+    account: button::State,
+    transcoding: button::State,
+    hooks: button::State,
 }
 
 impl Sections {
@@ -82,6 +107,12 @@ impl Sections {
         let art = section_button!(art, cover_art, SettingsMessage::Art);
         let playlist = section_button!(playlist, playlist, SettingsMessage::Playlist);
         let downloads = section_button!(downloads, downloads, SettingsMessage::Downloads);
+        let filters = section_button!(filters, filters, SettingsMessage::Filters);
+        let subscriptions =
+            section_button!(subscriptions, subscriptions, SettingsMessage::Subscriptions);
+        let account = section_button!(account, account, SettingsMessage::Account);
+        let transcoding = section_button!(transcoding, transcoding, SettingsMessage::Transcoding);
+        let hooks = section_button!(hooks, hooks, SettingsMessage::Hooks);
 
         Container::new(
             Column::new()
@@ -92,6 +123,11 @@ impl Sections {
                 .push(art)
                 .push(playlist)
                 .push(downloads)
+                .push(filters)
+                .push(subscriptions)
+                .push(account)
+                .push(transcoding)
+                .push(hooks)
                 .height(Length::Fill),
         )
         .height(Length::Fill)
@@ -154,6 +190,15 @@ impl State {
             SettingsMessage::Art => self.current_view = View::Art(Default::default()),
             SettingsMessage::Playlist => self.current_view = View::Playlist(Default::default()),
             SettingsMessage::Downloads => self.current_view = View::Downloads(Default::default()),
+            SettingsMessage::Filters => self.current_view = View::Filters(Default::default()),
+            SettingsMessage::Subscriptions => {
+                self.current_view = View::Subscriptions(Default::default())
+            }
+            SettingsMessage::Account => self.current_view = View::Account(Default::default()),
+            SettingsMessage::Transcoding => {
+                self.current_view = View::Transcoding(Default::default())
+            }
+            SettingsMessage::Hooks => self.current_view = View::Hooks(Default::default()),
         }
     }
 }
@@ -0,0 +1,91 @@
+//! Bandcamp account settings view
+use iced::{pick_list, text_input, Align, Column, Element, Length, Row, Space};
+
+use crate::settings::{DownloadFormat, UserSettings};
+use crate::ui::{
+    iced::{
+        components::{self, labeled_input},
+        Message, SettingType,
+    },
+    IntlString,
+};
+
+labeled_input!(
+    #[doc = "Identity cookie input"]
+    identity_cookie_input,
+    identity_cookie,
+    identity_cookie_placeholder,
+    SettingType::IdentityCookie
+);
+
+labeled_input!(
+    #[doc = "Cookies file path input"]
+    cookies_file_input,
+    cookies_file,
+    cookies_file_placeholder,
+    SettingType::CookiesFile
+);
+
+labeled_input!(
+    #[doc = "Fan page url input"]
+    fan_page_input,
+    fan_page,
+    fan_page_placeholder,
+    SettingType::FanPageUrl
+);
+
+fn format_picker<'a>(
+    pick_list_state: &'a mut pick_list::State<DownloadFormat>,
+    selected_format: &DownloadFormat,
+    intl: &IntlString,
+) -> Element<'a, Message> {
+    let label = components::StyledText(format!("{}:", &intl.preferred_format));
+
+    let pick_list = components::styled_pick_list(
+        pick_list_state,
+        &DownloadFormat::ALL[..],
+        Some(*selected_format),
+        |a| SettingType::PreferredFormat(a).into(),
+    );
+
+    Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(label)
+        .push(pick_list)
+        .into()
+}
+
+/// Account settings view state
+#[derive(Debug, Default)]
+pub struct State {
+    identity_cookie_input: text_input::State,
+    cookies_file_input: text_input::State,
+    fan_page_input: text_input::State,
+    format_list: pick_list::State<DownloadFormat>,
+}
+
+impl State {
+    pub fn view(&mut self, settings: &UserSettings, intl: &IntlString) -> Element<'_, Message> {
+        let identity_cookie = identity_cookie_input(
+            &mut self.identity_cookie_input,
+            &settings.identity_cookie,
+            intl,
+        );
+        let cookies_file =
+            cookies_file_input(&mut self.cookies_file_input, &settings.cookies_file, intl);
+        let fan_page = fan_page_input(&mut self.fan_page_input, &settings.fan_page_url, intl);
+        let format_list = format_picker(&mut self.format_list, &settings.preferred_format, intl);
+
+        Column::new()
+            .spacing(5)
+            .height(Length::Fill)
+            .width(Length::FillPortion(3))
+            .push(identity_cookie)
+            .push(cookies_file)
+            .push(fan_page)
+            .push(format_list)
+            .push(Space::with_height(Length::Fill))
+            .into()
+    }
+}
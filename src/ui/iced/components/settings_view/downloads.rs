@@ -1,15 +1,50 @@
 //! Download settings view
-use iced::{Column, Element, Length, Space};
+use iced::{pick_list, text_input, Align, Column, Element, Length, Row, Space};
 
+use crate::core::DuplicateAction;
 use crate::settings::UserSettings;
 use crate::ui::{
-    iced::{components, Message, SettingType},
+    iced::{components, components::labeled_input, Message, SettingType},
     IntlString,
 };
 
+labeled_input!(
+    #[doc = "Label discography artist filter input"]
+    artist_filter_input,
+    label_artist_filter,
+    label_artist_filter_placeholder,
+    SettingType::LabelArtistFilter
+);
+
+/// Duplicate tracks action picker with label
+fn duplicates_picker<'a>(
+    pick_list_state: &'a mut pick_list::State<DuplicateAction>,
+    selected: DuplicateAction,
+    intl: &IntlString,
+) -> Element<'a, Message> {
+    let label = components::StyledText(format!("{}:", &intl.duplicates));
+
+    let pick_list = components::styled_pick_list(
+        pick_list_state,
+        &DuplicateAction::ALL[..],
+        Some(selected),
+        |a| SettingType::Duplicates(a).into(),
+    );
+
+    Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(label)
+        .push(pick_list)
+        .into()
+}
+
 /// Download settings view state
 #[derive(Debug, Default)]
-pub struct State {}
+pub struct State {
+    artist_filter_input: text_input::State,
+    duplicates_list: pick_list::State<DuplicateAction>,
+}
 
 impl State {
     pub fn view(&mut self, settings: &UserSettings, intl: &IntlString) -> Element<Message> {
@@ -26,12 +61,34 @@ impl State {
             download_serial,
             SettingType::DownloadSerial
         );
+        let free_checkbox = checkbox!(
+            download_free_releases,
+            download_free_releases,
+            SettingType::DownloadFreeReleases
+        );
+        let json_checkbox = checkbox!(
+            write_album_json,
+            write_album_json,
+            SettingType::WriteAlbumJson
+        );
+        let nfo_checkbox = checkbox!(write_album_nfo, write_album_nfo, SettingType::WriteAlbumNfo);
+        let duplicates = duplicates_picker(&mut self.duplicates_list, settings.duplicates, intl);
+        let artist_filter = artist_filter_input(
+            &mut self.artist_filter_input,
+            &settings.label_artist_filter,
+            intl,
+        );
 
         Column::new()
             .spacing(5)
             .height(Length::Fill)
             .width(Length::FillPortion(3))
             .push(serial_checkbox)
+            .push(free_checkbox)
+            .push(json_checkbox)
+            .push(nfo_checkbox)
+            .push(duplicates)
+            .push(artist_filter)
             .push(Space::with_height(Length::Fill))
             .into()
     }
@@ -0,0 +1,131 @@
+//! Release filter settings view
+use iced::{pick_list, text_input, Align, Column, Element, Length, Row, Space};
+
+use crate::core::ReleaseType;
+use crate::settings::UserSettings;
+use crate::ui::{
+    iced::{components, components::labeled_input, Message, SettingType},
+    IntlString,
+};
+
+labeled_input!(
+    #[doc = "Earliest release date input"]
+    after_input,
+    released_after,
+    filter_date_placeholder,
+    SettingType::FilterAfter
+);
+labeled_input!(
+    #[doc = "Latest release date input"]
+    before_input,
+    released_before,
+    filter_date_placeholder,
+    SettingType::FilterBefore
+);
+labeled_input!(
+    #[doc = "Release title pattern input"]
+    title_input,
+    title_pattern,
+    title_pattern_placeholder,
+    SettingType::FilterTitle
+);
+
+/// Track count input with label. Anything but a number clears the value.
+fn count_input<'a>(
+    state: &'a mut text_input::State,
+    label: &str,
+    value: Option<usize>,
+    intl: &IntlString,
+    message: fn(Option<usize>) -> SettingType,
+) -> Element<'a, Message> {
+    let value = value.map(|value| value.to_string()).unwrap_or_default();
+    let input = components::styled_text_input::styled_text_input(
+        state,
+        &intl.track_count_placeholder,
+        &value,
+        move |a| message(a.trim().parse().ok()).into(),
+    );
+
+    Row::new()
+        .align_items(Align::Center)
+        .spacing(5)
+        .push(components::StyledText(label))
+        .push(input)
+        .into()
+}
+
+fn type_picker<'a>(
+    pick_list_state: &'a mut pick_list::State<ReleaseType>,
+    selected_type: &ReleaseType,
+    intl: &IntlString,
+) -> Element<'a, Message> {
+    let label = components::StyledText(format!("{}:", &intl.release_type));
+
+    let pick_list = components::styled_pick_list(
+        pick_list_state,
+        &ReleaseType::ALL[..],
+        Some(*selected_type),
+        |a| SettingType::FilterReleaseType(a).into(),
+    );
+
+    Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(label)
+        .push(pick_list)
+        .into()
+}
+
+/// Release filter settings view state
+#[derive(Debug, Default)]
+pub struct State {
+    after_input: text_input::State,
+    before_input: text_input::State,
+    title_input: text_input::State,
+    min_tracks_input: text_input::State,
+    max_tracks_input: text_input::State,
+    type_list: pick_list::State<ReleaseType>,
+}
+
+impl State {
+    pub fn view(&mut self, settings: &UserSettings, intl: &IntlString) -> Element<'_, Message> {
+        let filter = &settings.release_filter;
+
+        Column::new()
+            .spacing(5)
+            .height(Length::Fill)
+            .width(Length::FillPortion(3))
+            .push(type_picker(&mut self.type_list, &filter.release_type, intl))
+            .push(after_input(
+                &mut self.after_input,
+                &filter.released_after,
+                intl,
+            ))
+            .push(before_input(
+                &mut self.before_input,
+                &filter.released_before,
+                intl,
+            ))
+            .push(title_input(
+                &mut self.title_input,
+                &filter.title_pattern,
+                intl,
+            ))
+            .push(count_input(
+                &mut self.min_tracks_input,
+                &intl.min_tracks,
+                filter.min_tracks,
+                intl,
+                SettingType::FilterMinTracks,
+            ))
+            .push(count_input(
+                &mut self.max_tracks_input,
+                &intl.max_tracks,
+                filter.max_tracks,
+                intl,
+                SettingType::FilterMaxTracks,
+            ))
+            .push(Space::with_height(Length::Fill))
+            .into()
+    }
+}
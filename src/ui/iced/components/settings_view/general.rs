@@ -31,7 +31,12 @@ impl State {
                 &settings.language,
                 intl,
             ))
-            .push(theme_picker(&mut self.theme_list, &settings.theme, intl));
+            .push(theme_picker(&mut self.theme_list, &settings.theme, intl))
+            .push(components::checkbox_row(
+                settings.show_verbose_log,
+                &intl.verbose_log,
+                |a| SettingType::VerboseLog(a).into(),
+            ));
 
         Container::new(content)
             .width(Length::Fill)
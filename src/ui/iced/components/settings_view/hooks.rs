@@ -0,0 +1,62 @@
+//! Hooks settings view
+use iced::{text_input, Align, Column, Element, Length, Row, Space};
+
+use crate::settings::UserSettings;
+use crate::ui::{
+    iced::{components, components::labeled_input, Message, SettingType},
+    IntlString,
+};
+
+labeled_input!(
+    #[doc = "Album complete hook input"]
+    album_complete_input,
+    hook_album_complete,
+    hook_placeholder,
+    SettingType::HookAlbumComplete
+);
+
+labeled_input!(
+    #[doc = "Run complete hook input"]
+    run_complete_input,
+    hook_run_complete,
+    hook_placeholder,
+    SettingType::HookRunComplete
+);
+
+labeled_input!(
+    #[doc = "Track failed hook input"]
+    track_failed_input,
+    hook_track_failed,
+    hook_placeholder,
+    SettingType::HookTrackFailed
+);
+
+/// Hooks settings view state
+#[derive(Debug, Default)]
+pub struct State {
+    album_complete_input: text_input::State,
+    run_complete_input: text_input::State,
+    track_failed_input: text_input::State,
+}
+
+impl State {
+    pub fn view(&mut self, settings: &UserSettings, intl: &IntlString) -> Element<'_, Message> {
+        let hooks = &settings.hooks;
+        let album_complete =
+            album_complete_input(&mut self.album_complete_input, &hooks.album_complete, intl);
+        let run_complete =
+            run_complete_input(&mut self.run_complete_input, &hooks.run_complete, intl);
+        let track_failed =
+            track_failed_input(&mut self.track_failed_input, &hooks.track_failed, intl);
+
+        Column::new()
+            .spacing(5)
+            .height(Length::Fill)
+            .width(Length::FillPortion(3))
+            .push(album_complete)
+            .push(run_complete)
+            .push(track_failed)
+            .push(Space::with_height(Length::Fill))
+            .into()
+    }
+}
@@ -0,0 +1,94 @@
+//! Followed artists settings view
+use iced::{
+    button, scrollable, text_input, Align, Button, Column, Element, Length, Row, Scrollable, Space,
+};
+
+use crate::settings::UserSettings;
+use crate::ui::{
+    iced::{components, style, Message, SettingType},
+    IntlString,
+};
+
+/// Check interval input with label. Anything but a number disables the checks.
+fn interval_input<'a>(
+    state: &'a mut text_input::State,
+    value: u64,
+    intl: &IntlString,
+) -> Element<'a, Message> {
+    let value = if value > 0 {
+        value.to_string()
+    } else {
+        String::new()
+    };
+    let input = components::styled_text_input::styled_text_input(
+        state,
+        &intl.subscription_interval_placeholder,
+        &value,
+        |a| SettingType::SubscriptionInterval(a.trim().parse().unwrap_or(0)).into(),
+    );
+
+    Row::new()
+        .align_items(Align::Center)
+        .spacing(5)
+        .push(components::StyledText(&intl.subscription_interval))
+        .push(input)
+        .into()
+}
+
+/// Followed artist url, with a button to stop following it
+fn subscription_row<'a>(
+    state: &'a mut button::State,
+    url: &str,
+    intl: &IntlString,
+) -> Row<'a, Message> {
+    let remove = Button::new(state, components::StyledText(&intl.unsubscribe_button))
+        .style(style::Theme::Light)
+        .on_press(SettingType::Unsubscribe(url.to_string()).into());
+
+    Row::new()
+        .spacing(5)
+        .align_items(Align::Center)
+        .push(components::StyledText(url).width(Length::Fill))
+        .push(remove)
+}
+
+/// Subscriptions settings view state
+#[derive(Debug, Default)]
+pub struct State {
+    interval_input: text_input::State,
+    remove_buttons: Vec<button::State>,
+    scroll_state: scrollable::State,
+}
+
+impl State {
+    pub fn view(&mut self, settings: &UserSettings, intl: &IntlString) -> Element<'_, Message> {
+        let subscriptions = &settings.subscriptions;
+        self.remove_buttons
+            .resize_with(subscriptions.len(), button::State::new);
+
+        let mut list = Column::new().spacing(2);
+        if subscriptions.is_empty() {
+            list = list.push(components::StyledText(&intl.subscriptions_placeholder));
+        }
+        for (state, url) in self.remove_buttons.iter_mut().zip(subscriptions) {
+            list = list.push(subscription_row(state, url, intl));
+        }
+
+        Column::new()
+            .spacing(5)
+            .height(Length::Fill)
+            .width(Length::FillPortion(3))
+            .push(interval_input(
+                &mut self.interval_input,
+                settings.subscription_check_interval,
+                intl,
+            ))
+            .push(
+                Scrollable::new(&mut self.scroll_state)
+                    .height(Length::Fill)
+                    .push(list),
+            )
+            .push(Space::with_height(Length::Units(5)))
+            .into()
+    }
+}
@@ -0,0 +1,99 @@
+//! Transcoding settings view
+use iced::{text_input, Align, Column, Element, Length, Row, Space};
+
+use crate::settings::UserSettings;
+use crate::ui::{
+    iced::{components, components::labeled_input, Message, SettingType},
+    IntlString,
+};
+
+labeled_input!(
+    #[doc = "Encoder path input"]
+    encoder_input,
+    transcode_encoder,
+    transcode_encoder_placeholder,
+    SettingType::TranscodeEncoder
+);
+
+labeled_input!(
+    #[doc = "Encoder arguments input"]
+    arguments_input,
+    transcode_arguments,
+    transcode_arguments_placeholder,
+    SettingType::TranscodeArguments
+);
+
+labeled_input!(
+    #[doc = "Transcoded files extension input"]
+    extension_input,
+    transcode_extension,
+    transcode_extension_placeholder,
+    SettingType::TranscodeExtension
+);
+
+/// Bitrate input with label. Anything but a number is ignored.
+fn bitrate_input<'a>(
+    state: &'a mut text_input::State,
+    value: u32,
+    intl: &IntlString,
+) -> Element<'a, Message> {
+    let input = components::styled_text_input::styled_text_input(
+        state,
+        &intl.transcode_bitrate_placeholder,
+        &value.to_string(),
+        move |a| SettingType::TranscodeBitrate(a.trim().parse().unwrap_or(value)).into(),
+    );
+
+    Row::new()
+        .align_items(Align::Center)
+        .spacing(5)
+        .push(components::StyledText(&intl.transcode_bitrate))
+        .push(input)
+        .into()
+}
+
+/// Transcoding settings view state
+#[derive(Debug, Default)]
+pub struct State {
+    encoder_input: text_input::State,
+    arguments_input: text_input::State,
+    extension_input: text_input::State,
+    bitrate_input: text_input::State,
+}
+
+impl State {
+    pub fn view(&mut self, settings: &UserSettings, intl: &IntlString) -> Element<'_, Message> {
+        macro_rules! checkbox {
+            ($setting: ident, $intl_field: ident, $message: path) => {
+                components::checkbox_row(settings.transcoding.$setting, &intl.$intl_field, |a| {
+                    $message(a).into()
+                })
+            };
+        }
+
+        let transcoding = &settings.transcoding;
+        let enabled_checkbox = checkbox!(enabled, transcode_enabled, SettingType::TranscodeEnabled);
+        let encoder = encoder_input(&mut self.encoder_input, &transcoding.encoder, intl);
+        let arguments = arguments_input(&mut self.arguments_input, &transcoding.arguments, intl);
+        let extension = extension_input(&mut self.extension_input, &transcoding.extension, intl);
+        let bitrate = bitrate_input(&mut self.bitrate_input, transcoding.bitrate, intl);
+        let keep_checkbox = checkbox!(
+            keep_originals,
+            transcode_keep_originals,
+            SettingType::TranscodeKeepOriginals
+        );
+
+        Column::new()
+            .spacing(5)
+            .height(Length::Fill)
+            .width(Length::FillPortion(3))
+            .push(enabled_checkbox)
+            .push(encoder)
+            .push(arguments)
+            .push(extension)
+            .push(bitrate)
+            .push(keep_checkbox)
+            .push(Space::with_height(Length::Fill))
+            .into()
+    }
+}
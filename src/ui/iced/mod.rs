@@ -1,7 +1,7 @@
 //! UI implementation using iced crate
 use iced::Application;
 
-use crate::core::tag;
+use crate::core::{tag, DuplicateAction, PlannedAlbum};
 use crate::{
     settings::{PlaylistFormat, UserSettings},
     ui,
@@ -33,6 +33,8 @@ pub enum SettingType {
     SaveDir(String),
     FilenameFormat(String),
     Discography(bool),
+    LabelArtistFilter(String),
+    VerboseLog(bool),
 
     ArtFilename(String),
     ArtInFolder(bool),
@@ -53,6 +55,36 @@ pub enum SettingType {
     PlaylistFilename(String),
 
     DownloadSerial(bool),
+    DownloadFreeReleases(bool),
+    WriteAlbumJson(bool),
+    WriteAlbumNfo(bool),
+    Duplicates(DuplicateAction),
+
+    FilterAfter(String),
+    FilterBefore(String),
+    FilterTitle(String),
+    FilterMinTracks(Option<usize>),
+    FilterMaxTracks(Option<usize>),
+    FilterReleaseType(crate::core::ReleaseType),
+
+    SubscriptionInterval(u64),
+    Unsubscribe(String),
+
+    IdentityCookie(String),
+    CookiesFile(String),
+    FanPageUrl(String),
+    PreferredFormat(crate::settings::DownloadFormat),
+
+    TranscodeEnabled(bool),
+    TranscodeEncoder(String),
+    TranscodeArguments(String),
+    TranscodeExtension(String),
+    TranscodeBitrate(u32),
+    TranscodeKeepOriginals(bool),
+
+    HookAlbumComplete(String),
+    HookRunComplete(String),
+    HookTrackFailed(String),
 }
 
 /// UI event messages
@@ -66,7 +98,15 @@ pub enum Message {
     ClearUrls,
     SetSaveDir,
     DownloadsComplete(()),
+    RetryComplete(()),
+    WatchStopped(()),
     Url(usize, EntryMessage),
+    ToggleAlbum(usize),
+    PreviewReady(Vec<PlannedAlbum>),
+    ExpandPreviewAlbum(usize),
+    TogglePreviewAlbum(usize),
+    TogglePreviewTrack(usize, usize),
+    DiscardPreview,
     SettingsChanged(SettingType),
     SettingsSaved,
     Settings(settings_view::SettingsMessage),
@@ -51,6 +51,8 @@ pub struct IntlString {
     pub language: String,
     /// Theme label
     pub theme: String,
+    /// Verbose log toggle label
+    pub verbose_log: String,
 
     pub album_artist: String,
     pub album_title: String,
@@ -67,6 +69,104 @@ pub struct IntlString {
 
     pub downloads: String,
     pub download_serial: String,
+    pub download_free_releases: String,
+    pub write_album_json: String,
+    pub write_album_nfo: String,
+    /// Duplicate tracks action picker label
+    pub duplicates: String,
+    /// Label discography artist filter input label
+    pub label_artist_filter: String,
+    /// Label discography artist filter placeholder text
+    pub label_artist_filter_placeholder: String,
+
+    /// Release filters settings view title
+    pub filters: String,
+    /// Release filter input labels
+    pub release_type: String,
+    pub released_after: String,
+    pub released_before: String,
+    pub title_pattern: String,
+    pub min_tracks: String,
+    pub max_tracks: String,
+    /// Release filter placeholder texts
+    pub filter_date_placeholder: String,
+    pub title_pattern_placeholder: String,
+    pub track_count_placeholder: String,
+
+    /// Followed artists settings view title
+    pub subscriptions: String,
+    /// Subscription check interval input label
+    pub subscription_interval: String,
+    /// Subscription check interval placeholder text
+    pub subscription_interval_placeholder: String,
+    /// Placeholder text of the empty followed artists list
+    pub subscriptions_placeholder: String,
+    /// Stop following an artist button label
+    pub unsubscribe_button: String,
+    /// Follow the artists of the urls button label
+    pub follow_button: String,
+
+    /// Bandcamp account settings view title
+    pub account: String,
+    /// Bandcamp account input labels
+    pub identity_cookie: String,
+    pub cookies_file: String,
+    pub fan_page: String,
+    /// Bandcamp account placeholder texts
+    pub identity_cookie_placeholder: String,
+    pub cookies_file_placeholder: String,
+    pub fan_page_placeholder: String,
+    /// Preferred purchases format picker label
+    pub preferred_format: String,
+
+    /// Transcoding settings view title
+    pub transcoding: String,
+    pub transcode_enabled: String,
+    pub transcode_keep_originals: String,
+    /// Transcoding input labels
+    pub transcode_encoder: String,
+    pub transcode_arguments: String,
+    pub transcode_extension: String,
+    pub transcode_bitrate: String,
+    /// Transcoding placeholder texts
+    pub transcode_encoder_placeholder: String,
+    pub transcode_arguments_placeholder: String,
+    pub transcode_extension_placeholder: String,
+    pub transcode_bitrate_placeholder: String,
+
+    /// Hooks settings view title
+    pub hooks: String,
+    /// Hook command input labels
+    pub hook_album_complete: String,
+    pub hook_run_complete: String,
+    pub hook_track_failed: String,
+    /// Hook command placeholder text
+    pub hook_placeholder: String,
+
+    /// Download progress placeholder text
+    pub progress_placeholder: String,
+    /// Preview button label
+    pub preview_button: String,
+    /// Preview list title
+    pub preview_title: String,
+    /// Discard preview button label
+    pub discard_preview_button: String,
+    /// Label of the previewed tracks already on disk
+    pub preview_on_disk: String,
+    /// Track status labels
+    pub status_queued: String,
+    pub status_downloading: String,
+    pub status_tagging: String,
+    pub status_transcoding: String,
+    pub status_done: String,
+    pub status_failed: String,
+    pub status_skipped: String,
+    /// Retry failed downloads button label
+    pub retry_button: String,
+    /// Skip failed downloads button label
+    pub skip_button: String,
+    /// Open album folder button label
+    pub open_folder_button: String,
 }
 
 impl IntlString {